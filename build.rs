@@ -1,17 +1,327 @@
 use anyhow::*;
 use fs_extra::copy_items;
 use fs_extra::dir::CopyOptions;
+use image::{GenericImage, RgbaImage};
+use std::collections::BTreeMap;
 use std::env;
+use std::path::Path;
+
+// Pages never grow past this, wgpu guarantees 2D textures this size on every backend.
+const MAX_PAGE_SIZE: u32 = 2048;
+
+// Each texture's edge pixels are repeated this far out so sampling never bleeds into a neighbour.
+const PADDING: u32 = 1;
 
 fn main() -> Result<()> {
-    println!("cargo:rerun-if-changed=res/*");
+    println!("cargo:rerun-if-changed=res");
 
     let out_dir = env::var("OUT_DIR")?;
     let mut copy_options = CopyOptions::new();
     copy_options.overwrite = true;
-    let mut paths_to_copy = Vec::new();
-    paths_to_copy.push("res/");
-    copy_items(&paths_to_copy, out_dir, &copy_options)?;
+    let paths_to_copy = vec!["res/"];
+    copy_items(&paths_to_copy, &out_dir, &copy_options)?;
+
+    pack_atlases(
+        Path::new("res/textures"),
+        &Path::new(&out_dir).join("res/atlases"),
+    )?;
+
+    Ok(())
+}
+
+struct Sprite {
+    /// Path under `res/textures` with forward slashes, the name it's loaded by.
+    name: String,
+    image: RgbaImage,
+}
+
+struct Placement {
+    name: String,
+    page: usize,
+    x: u32,
+    y: u32,
+    width: u32,
+    height: u32,
+}
+
+/**
+ * Packs the PNGs directly under `res/textures` into one group of pages and each subfolder into
+ * its own, then writes the pages and a `manifest.json` listing where every texture ended up.
+ * Textures too big for a page are left out and load on their own.
+ */
+fn pack_atlases(textures_dir: &Path, atlas_dir: &Path) -> Result<()> {
+    std::fs::create_dir_all(atlas_dir)?;
+
+    let mut groups = BTreeMap::<String, Vec<Sprite>>::new();
+
+    let pattern = textures_dir.join("**").join("*.png");
+    for path in glob::glob(&pattern.to_string_lossy())? {
+        let path = path?;
+        let relative = path.strip_prefix(textures_dir)?;
+
+        let name = relative
+            .components()
+            .map(|c| c.as_os_str().to_string_lossy())
+            .collect::<Vec<_>>()
+            .join("/");
+
+        let group = match name.split_once('/') {
+            Some((folder, _)) => folder.to_owned(),
+            None => String::from("textures"),
+        };
+
+        let image = image::open(&path)
+            .with_context(|| format!("Couldn't read texture {}", path.display()))?
+            .to_rgba8();
+
+        if image.width() + PADDING * 2 > MAX_PAGE_SIZE
+            || image.height() + PADDING * 2 > MAX_PAGE_SIZE
+        {
+            continue;
+        }
+
+        groups
+            .entry(group)
+            .or_default()
+            .push(Sprite { name, image });
+    }
+
+    let mut pages = Vec::new();
+    let mut textures = serde_json::Map::new();
+
+    for (group, mut sprites) in groups {
+        // Tallest first packs tightest. Sorting by name first keeps the layout stable.
+        sprites.sort_by(|a, b| a.name.cmp(&b.name));
+        sprites.sort_by_key(|sprite| std::cmp::Reverse(sprite.image.height()));
+
+        let first_page = pages.len();
+        let placements = pack(&sprites, first_page);
+
+        for page in first_page
+            ..=placements
+                .iter()
+                .map(|p| p.page)
+                .max()
+                .unwrap_or(first_page)
+        {
+            let on_page: Vec<_> = placements.iter().filter(|p| p.page == page).collect();
+            let file_name = format!("{group}-{}.png", page - first_page);
+
+            write_page(&sprites, &on_page, &atlas_dir.join(&file_name))?;
+            pages.push(file_name);
+        }
+
+        for placement in &placements {
+            textures.insert(
+                placement.name.clone(),
+                serde_json::json!({
+                    "page": placement.page,
+                    "x": placement.x,
+                    "y": placement.y,
+                    "width": placement.width,
+                    "height": placement.height,
+                }),
+            );
+        }
+    }
+
+    let manifest = serde_json::json!({ "pages": pages, "textures": textures });
+    std::fs::write(
+        atlas_dir.join("manifest.json"),
+        serde_json::to_string_pretty(&manifest)?,
+    )?;
+
+    Ok(())
+}
+
+// Fills pages one at a time, starting a new one for whatever didn't fit.
+fn pack(sprites: &[Sprite], first_page: usize) -> Vec<Placement> {
+    let mut placements = Vec::new();
+    let mut remaining: Vec<&Sprite> = sprites.iter().collect();
+    let mut page = first_page;
+
+    while !remaining.is_empty() {
+        let mut skyline = Skyline::new(MAX_PAGE_SIZE, MAX_PAGE_SIZE);
+        let mut leftover = Vec::new();
+
+        for sprite in remaining {
+            let width = sprite.image.width() + PADDING * 2;
+            let height = sprite.image.height() + PADDING * 2;
+
+            match skyline.insert(width, height) {
+                Some((x, y)) => placements.push(Placement {
+                    name: sprite.name.clone(),
+                    page,
+                    x: x + PADDING,
+                    y: y + PADDING,
+                    width: sprite.image.width(),
+                    height: sprite.image.height(),
+                }),
+                None => leftover.push(sprite),
+            }
+        }
+
+        remaining = leftover;
+        page += 1;
+    }
+
+    placements
+}
+
+fn write_page(sprites: &[Sprite], placements: &[&Placement], path: &Path) -> Result<()> {
+    let width = placements
+        .iter()
+        .map(|p| p.x + p.width + PADDING)
+        .max()
+        .unwrap_or(1);
+    let height = placements
+        .iter()
+        .map(|p| p.y + p.height + PADDING)
+        .max()
+        .unwrap_or(1);
+
+    let mut page = RgbaImage::new(width, height);
+
+    for placement in placements {
+        let sprite = sprites.iter().find(|s| s.name == placement.name).unwrap();
+        page.copy_from(&sprite.image, placement.x, placement.y)?;
+
+        // Repeat the edges into the padding, clamping to the image for the corners.
+        let (w, h) = sprite.image.dimensions();
+        let padding = PADDING as i64;
+
+        for dy in -padding..h as i64 + padding {
+            for dx in -padding..w as i64 + padding {
+                if dx >= 0 && dx < w as i64 && dy >= 0 && dy < h as i64 {
+                    continue;
+                }
+
+                let source = *sprite.image.get_pixel(
+                    dx.clamp(0, w as i64 - 1) as u32,
+                    dy.clamp(0, h as i64 - 1) as u32,
+                );
+
+                page.put_pixel(
+                    (placement.x as i64 + dx) as u32,
+                    (placement.y as i64 + dy) as u32,
+                    source,
+                );
+            }
+        }
+    }
+
+    page.save(path)
+        .with_context(|| format!("Couldn't write atlas page {}", path.display()))?;
 
     Ok(())
 }
+
+/// Bottom-left skyline packer. Each node is a horizontal segment of the packed area's top edge.
+struct Skyline {
+    width: u32,
+    height: u32,
+    nodes: Vec<SkylineNode>,
+}
+
+struct SkylineNode {
+    x: u32,
+    y: u32,
+    width: u32,
+}
+
+impl Skyline {
+    fn new(width: u32, height: u32) -> Self {
+        Self {
+            width,
+            height,
+            nodes: vec![SkylineNode { x: 0, y: 0, width }],
+        }
+    }
+
+    fn insert(&mut self, width: u32, height: u32) -> Option<(u32, u32)> {
+        let mut best: Option<(usize, u32)> = None;
+
+        for i in 0..self.nodes.len() {
+            if let Some(y) = self.fit(i, width, height) {
+                let better = match best {
+                    Some((best_i, best_y)) => {
+                        y < best_y || (y == best_y && self.nodes[i].x < self.nodes[best_i].x)
+                    }
+                    None => true,
+                };
+
+                if better {
+                    best = Some((i, y));
+                }
+            }
+        }
+
+        let (index, y) = best?;
+        let x = self.nodes[index].x;
+
+        self.nodes.insert(
+            index,
+            SkylineNode {
+                x,
+                y: y + height,
+                width,
+            },
+        );
+
+        // Trim or remove the segments the new one now covers.
+        let right = x + width;
+        let i = index + 1;
+
+        while i < self.nodes.len() && self.nodes[i].x < right {
+            let node_right = self.nodes[i].x + self.nodes[i].width;
+
+            if node_right <= right {
+                self.nodes.remove(i);
+            } else {
+                self.nodes[i].width = node_right - right;
+                self.nodes[i].x = right;
+                break;
+            }
+        }
+
+        // Merge neighbours at the same height.
+        let mut i = 0;
+        while i + 1 < self.nodes.len() {
+            if self.nodes[i].y == self.nodes[i + 1].y {
+                self.nodes[i].width += self.nodes[i + 1].width;
+                self.nodes.remove(i + 1);
+            } else {
+                i += 1;
+            }
+        }
+
+        Some((x, y))
+    }
+
+    // The height a rectangle would rest at with its left edge on node `index`.
+    fn fit(&self, index: usize, width: u32, height: u32) -> Option<u32> {
+        let x = self.nodes[index].x;
+
+        if x + width > self.width {
+            return None;
+        }
+
+        let mut y = 0;
+        let mut remaining = width as i64;
+        let mut i = index;
+
+        while remaining > 0 {
+            let node = self.nodes.get(i)?;
+            y = y.max(node.y);
+
+            if y + height > self.height {
+                return None;
+            }
+
+            remaining -= node.width as i64;
+            i += 1;
+        }
+
+        Some(y)
+    }
+}
@@ -1,12 +1,12 @@
 // Vertex shader
 struct VertexInput {
     @location(0) position: vec3<f32>,
-    @location(1) color: vec3<f32>
+    @location(1) color: vec4<f32>
 };
 
 struct VertexOutput {
     @builtin(position) clip_position: vec4<f32>,
-    @location(0) color: vec3<f32>
+    @location(0) color: vec4<f32>
 };
 
 @vertex
@@ -22,5 +22,5 @@ fn vs_main(
 // Fragment shader
 @fragment
 fn fs_main(in: VertexOutput) -> @location(0) vec4<f32> {
-    return vec4<f32>(in.color, 1.0);
+    return in.color;
 }
\ No newline at end of file
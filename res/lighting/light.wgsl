@@ -0,0 +1,81 @@
+// Vertex shader
+
+struct CameraUniform {
+    view_proj: mat4x4<f32>,
+    pixels_per_unit: f32,
+};
+
+@group(1) @binding(0)
+var<uniform> camera: CameraUniform;
+
+struct LightInput {
+    // x, y, radius and height.
+    @location(0) center: vec4<f32>,
+    // Color times intensity, and the falloff.
+    @location(1) color: vec4<f32>,
+    // Direction, and the cosines of the cone's edge and where it's fully lit.
+    @location(2) cone: vec4<f32>,
+};
+
+struct VertexOutput {
+    @builtin(position) clip_position: vec4<f32>,
+    @location(0) world: vec2<f32>,
+    @location(1) center: vec4<f32>,
+    @location(2) color: vec4<f32>,
+    @location(3) cone: vec4<f32>,
+};
+
+// Two triangles covering the light's radius, no vertex buffer needed.
+@vertex
+fn vs_main(@builtin(vertex_index) index: u32, light: LightInput) -> VertexOutput {
+    var corners = array<vec2<f32>, 6>(
+        vec2<f32>(-1.0, -1.0),
+        vec2<f32>(1.0, -1.0),
+        vec2<f32>(1.0, 1.0),
+        vec2<f32>(-1.0, -1.0),
+        vec2<f32>(1.0, 1.0),
+        vec2<f32>(-1.0, 1.0),
+    );
+
+    let world = light.center.xy + corners[index] * light.center.z;
+
+    var out: VertexOutput;
+    out.clip_position = camera.view_proj * vec4<f32>(world, 0.0, 1.0);
+    out.world = world;
+    out.center = light.center;
+    out.color = light.color;
+    out.cone = light.cone;
+    return out;
+}
+
+// Fragment shader
+
+// The normals of the sprites under each pixel, flat where there are none.
+@group(0) @binding(0)
+var t_normals: texture_2d<f32>;
+
+@fragment
+fn fs_main(in: VertexOutput) -> @location(0) vec4<f32> {
+    let offset = in.world - in.center.xy;
+    let distance = length(offset);
+    let reach = 1.0 - distance / in.center.z;
+
+    if (reach <= 0.0) {
+        discard;
+    }
+
+    let fade = pow(reach, in.color.a);
+
+    var spot = 1.0;
+
+    if (distance > 0.0001) {
+        spot = smoothstep(in.cone.z, in.cone.w, dot(offset / distance, in.cone.xy));
+    }
+
+    // Relative to a flat sprite's, so sprites without normal maps get the light as it is.
+    let normal = textureLoad(t_normals, vec2<i32>(in.clip_position.xy), 0).xyz * 2.0 - 1.0;
+    let to_light = normalize(vec3<f32>(-offset, max(in.center.w, 0.001)));
+    let facing = clamp(dot(normal, to_light) / to_light.z, 0.0, 2.0);
+
+    return vec4<f32>(in.color.rgb * fade * spot * facing, 1.0);
+}
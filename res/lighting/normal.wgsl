@@ -0,0 +1,105 @@
+// Vertex shader, the same as `sprite.wgsl`'s but passing on which way the sprite faces.
+
+struct CameraUniform {
+    view_proj: mat4x4<f32>,
+    // Zero when sprites shouldn't be snapped to the texel grid.
+    pixels_per_unit: f32,
+};
+
+@group(1) @binding(0)
+var<uniform> camera: CameraUniform;
+
+struct VertexInput {
+    @location(0) position: vec3<f32>,
+    @location(1) tex_coords: vec2<f32>,
+};
+
+struct InstanceInput {
+    @location(2) matrix_0: vec4<f32>,
+    @location(3) matrix_1: vec4<f32>,
+    @location(4) matrix_2: vec4<f32>,
+    @location(5) matrix_3: vec4<f32>,
+    // x, y, width and height of the region shown.
+    @location(6) uv_rect: vec4<f32>,
+    @location(7) tint: vec4<f32>,
+    @location(8) flash: vec4<f32>,
+    // Bit 0 flips horizontally, bit 1 vertically.
+    @location(9) flags: u32,
+};
+
+struct VertexOutput {
+    @builtin(position) clip_position: vec4<f32>,
+    @location(0) tex_coords: vec2<f32>,
+    @location(1) alpha: f32,
+    // The sprite's x axis in the world, to rotate its normals with it.
+    @location(2) axis: vec2<f32>,
+    // -1 on each flipped axis.
+    @location(3) flip: vec2<f32>,
+};
+
+@vertex
+fn vs_main(model: VertexInput, instance: InstanceInput) -> VertexOutput {
+    var transform = mat4x4<f32>(
+        instance.matrix_0,
+        instance.matrix_1,
+        instance.matrix_2,
+        instance.matrix_3,
+    );
+
+    if (camera.pixels_per_unit > 0.0) {
+        let snapped = round(transform[3].xy * camera.pixels_per_unit) / camera.pixels_per_unit;
+        transform[3] = vec4<f32>(snapped, transform[3].zw);
+    }
+
+    var out: VertexOutput;
+    out.clip_position = camera.view_proj * transform * vec4<f32>(model.position, 1.0);
+    var tex_coords = model.tex_coords;
+    var flip = vec2<f32>(1.0, 1.0);
+
+    if ((instance.flags & 1u) != 0u) {
+        tex_coords.x = 1.0 - tex_coords.x;
+        flip.x = -1.0;
+    }
+
+    if ((instance.flags & 2u) != 0u) {
+        tex_coords.y = 1.0 - tex_coords.y;
+        flip.y = -1.0;
+    }
+
+    out.tex_coords = instance.uv_rect.xy + tex_coords * instance.uv_rect.zw;
+    out.alpha = instance.tint.a;
+    out.axis = normalize(transform[0].xy);
+    out.flip = flip;
+    return out;
+}
+
+// Fragment shader
+
+@group(0) @binding(0)
+var t_diffuse: texture_2d<f32>;
+@group(0) @binding(1)
+var s_diffuse: sampler;
+
+@group(2) @binding(0)
+var t_normal: texture_2d<f32>;
+@group(2) @binding(1)
+var s_normal: sampler;
+// Where the material's texture lies on its page, the normal map covers just that.
+@group(2) @binding(2)
+var<uniform> region: vec4<f32>;
+
+@fragment
+fn fs_main(in: VertexOutput) -> @location(0) vec4<f32> {
+    let alpha = textureSample(t_diffuse, s_diffuse, in.tex_coords).a * in.alpha;
+    let uv = (in.tex_coords - region.xy) / region.zw;
+    var normal = textureSample(t_normal, s_normal, uv).xyz * 2.0 - 1.0;
+
+    let facing = normal.xy * in.flip;
+    normal = vec3<f32>(
+        in.axis.x * facing.x - in.axis.y * facing.y,
+        in.axis.y * facing.x + in.axis.x * facing.y,
+        normal.z,
+    );
+
+    return vec4<f32>(normalize(normal) * 0.5 + 0.5, alpha);
+}
@@ -0,0 +1,22 @@
+// Vertex shader
+
+struct CameraUniform {
+    view_proj: mat4x4<f32>,
+    pixels_per_unit: f32,
+};
+
+@group(1) @binding(0)
+var<uniform> camera: CameraUniform;
+
+// Far ends of shadows have w at 0, putting them infinitely far from the light.
+@vertex
+fn vs_main(@location(0) position: vec3<f32>) -> @builtin(position) vec4<f32> {
+    return camera.view_proj * vec4<f32>(position.xy, 0.0, position.z);
+}
+
+// Fragment shader, only the stencil is written.
+
+@fragment
+fn fs_main() -> @location(0) vec4<f32> {
+    return vec4<f32>(0.0);
+}
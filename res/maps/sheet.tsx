@@ -0,0 +1,15 @@
+<?xml version="1.0" encoding="UTF-8"?>
+<tileset version="1.10" tiledversion="1.10.2" name="sheet" tilewidth="16" tileheight="16" tilecount="4" columns="2">
+ <image source="../textures/sheet.png" width="32" height="32"/>
+ <tile id="0">
+  <properties>
+   <property name="collision" value="solid"/>
+  </properties>
+ </tile>
+ <tile id="2">
+  <animation>
+   <frame tileid="2" duration="500"/>
+   <frame tileid="3" duration="500"/>
+  </animation>
+ </tile>
+</tileset>
@@ -0,0 +1,56 @@
+// Adds a blur of everything brighter than x, scaled by y and z texels wide, in a single pass.
+
+// Vertex shader
+
+struct VertexOutput {
+    @builtin(position) clip_position: vec4<f32>,
+    @location(0) tex_coords: vec2<f32>,
+};
+
+// One triangle covering the whole viewport, no vertex buffer needed.
+@vertex
+fn vs_main(@builtin(vertex_index) index: u32) -> VertexOutput {
+    let uv = vec2<f32>(f32(index & 2u), f32((index << 1u) & 2u));
+
+    var out: VertexOutput;
+    out.clip_position = vec4<f32>(uv.x * 2.0 - 1.0, 1.0 - uv.y * 2.0, 0.0, 1.0);
+    out.tex_coords = uv;
+    return out;
+}
+
+// Fragment shader
+
+@group(0) @binding(0)
+var t_source: texture_2d<f32>;
+@group(0) @binding(1)
+var s_source: sampler;
+
+struct PostUniform {
+    params: vec4<f32>,
+    texel_size: vec2<f32>,
+};
+
+@group(1) @binding(0)
+var<uniform> post: PostUniform;
+
+@fragment
+fn fs_main(in: VertexOutput) -> @location(0) vec4<f32> {
+    let color = textureSample(t_source, s_source, in.tex_coords);
+    let step = post.params.z / 3.0 * post.texel_size;
+
+    var sum = vec3<f32>(0.0);
+    var total = 0.0;
+
+    for (var y = -3; y <= 3; y += 1) {
+        for (var x = -3; x <= 3; x += 1) {
+            let offset = vec2<f32>(f32(x), f32(y));
+            let tap = textureSample(t_source, s_source, in.tex_coords + offset * step).rgb;
+            let weight = 1.0 / (1.0 + dot(offset, offset));
+
+            sum += max(tap - vec3<f32>(post.params.x), vec3<f32>(0.0)) * weight;
+            total += weight;
+        }
+    }
+
+    return vec4<f32>(color.rgb + sum / total * post.params.y, color.a);
+}
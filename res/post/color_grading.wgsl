@@ -0,0 +1,67 @@
+// Remaps colors through a 256x16 LUT, mixed in by x.
+
+// Vertex shader
+
+struct VertexOutput {
+    @builtin(position) clip_position: vec4<f32>,
+    @location(0) tex_coords: vec2<f32>,
+};
+
+// One triangle covering the whole viewport, no vertex buffer needed.
+@vertex
+fn vs_main(@builtin(vertex_index) index: u32) -> VertexOutput {
+    let uv = vec2<f32>(f32(index & 2u), f32((index << 1u) & 2u));
+
+    var out: VertexOutput;
+    out.clip_position = vec4<f32>(uv.x * 2.0 - 1.0, 1.0 - uv.y * 2.0, 0.0, 1.0);
+    out.tex_coords = uv;
+    return out;
+}
+
+// Fragment shader
+
+@group(0) @binding(0)
+var t_source: texture_2d<f32>;
+@group(0) @binding(1)
+var s_source: sampler;
+
+struct PostUniform {
+    params: vec4<f32>,
+    texel_size: vec2<f32>,
+};
+
+@group(1) @binding(0)
+var<uniform> post: PostUniform;
+
+@group(1) @binding(1)
+var t_lut: texture_2d<f32>;
+@group(1) @binding(2)
+var s_lut: sampler;
+
+// LUTs are authored against sRGB values, the target stores linear ones.
+fn to_srgb(color: vec3<f32>) -> vec3<f32> {
+    let low = color * 12.92;
+    let high = 1.055 * pow(color, vec3<f32>(1.0 / 2.4)) - 0.055;
+    return select(high, low, color <= vec3<f32>(0.0031308));
+}
+
+// The center of texel (r, g) in a cell, as UVs across the whole LUT.
+fn lut_uv(cell: f32, rg: vec2<f32>) -> vec2<f32> {
+    let texel = rg * 15.0 + 0.5;
+    return vec2<f32>((cell * 16.0 + texel.x) / 256.0, texel.y / 16.0);
+}
+
+@fragment
+fn fs_main(in: VertexOutput) -> @location(0) vec4<f32> {
+    let color = textureSample(t_source, s_source, in.tex_coords);
+    let srgb = clamp(to_srgb(color.rgb), vec3<f32>(0.0), vec3<f32>(1.0));
+
+    // Blue picks the cell, blended between the two nearest.
+    let blue = srgb.b * 15.0;
+    let cell = floor(blue);
+    let low = textureSample(t_lut, s_lut, lut_uv(cell, srgb.rg)).rgb;
+    let high = textureSample(t_lut, s_lut, lut_uv(min(cell + 1.0, 15.0), srgb.rg)).rgb;
+    let graded = mix(low, high, blue - cell);
+
+    return vec4<f32>(mix(color.rgb, graded, post.params.x), color.a);
+}
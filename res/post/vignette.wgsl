@@ -0,0 +1,45 @@
+// Darkens toward the corners. x is the intensity, y how far out it starts.
+
+// Vertex shader
+
+struct VertexOutput {
+    @builtin(position) clip_position: vec4<f32>,
+    @location(0) tex_coords: vec2<f32>,
+};
+
+// One triangle covering the whole viewport, no vertex buffer needed.
+@vertex
+fn vs_main(@builtin(vertex_index) index: u32) -> VertexOutput {
+    let uv = vec2<f32>(f32(index & 2u), f32((index << 1u) & 2u));
+
+    var out: VertexOutput;
+    out.clip_position = vec4<f32>(uv.x * 2.0 - 1.0, 1.0 - uv.y * 2.0, 0.0, 1.0);
+    out.tex_coords = uv;
+    return out;
+}
+
+// Fragment shader
+
+@group(0) @binding(0)
+var t_source: texture_2d<f32>;
+@group(0) @binding(1)
+var s_source: sampler;
+
+struct PostUniform {
+    params: vec4<f32>,
+    texel_size: vec2<f32>,
+};
+
+@group(1) @binding(0)
+var<uniform> post: PostUniform;
+
+@fragment
+fn fs_main(in: VertexOutput) -> @location(0) vec4<f32> {
+    let color = textureSample(t_source, s_source, in.tex_coords);
+
+    // 0 in the center and 1 in the corners.
+    let distance = length(in.tex_coords - vec2<f32>(0.5)) / 0.7071;
+    let shade = 1.0 - post.params.x * smoothstep(post.params.y, 1.0, distance);
+
+    return vec4<f32>(color.rgb * shade, color.a);
+}
@@ -0,0 +1,119 @@
+// Dissolves sprites into `edge_color` where the noise texture is under `amount`.
+
+// Vertex shader, as in sprite.wgsl
+
+struct CameraUniform {
+    view_proj: mat4x4<f32>,
+    // Zero when sprites shouldn't be snapped to the texel grid.
+    pixels_per_unit: f32,
+};
+
+@group(1) @binding(0)
+var<uniform> camera: CameraUniform;
+
+struct VertexInput {
+    @location(0) position: vec3<f32>,
+    @location(1) tex_coords: vec2<f32>,
+};
+
+struct InstanceInput {
+    @location(2) matrix_0: vec4<f32>,
+    @location(3) matrix_1: vec4<f32>,
+    @location(4) matrix_2: vec4<f32>,
+    @location(5) matrix_3: vec4<f32>,
+    // x, y, width and height of the region shown.
+    @location(6) uv_rect: vec4<f32>,
+    @location(7) tint: vec4<f32>,
+    @location(8) flash: vec4<f32>,
+    // Bit 0 flips horizontally, bit 1 vertically.
+    @location(9) flags: u32,
+};
+
+struct VertexOutput {
+    @builtin(position) clip_position: vec4<f32>,
+    @location(0) tex_coords: vec2<f32>,
+    @location(1) tint: vec4<f32>,
+    @location(2) flash: vec4<f32>,
+    // Across the sprite from 0 to 1, whatever part of its texture it shows.
+    @location(3) local: vec2<f32>,
+};
+
+@vertex
+fn vs_main(model: VertexInput, instance: InstanceInput) -> VertexOutput {
+    var transform = mat4x4<f32>(
+        instance.matrix_0,
+        instance.matrix_1,
+        instance.matrix_2,
+        instance.matrix_3,
+    );
+
+    if (camera.pixels_per_unit > 0.0) {
+        let snapped = round(transform[3].xy * camera.pixels_per_unit) / camera.pixels_per_unit;
+        transform[3] = vec4<f32>(snapped, transform[3].zw);
+    }
+
+    var out: VertexOutput;
+    out.clip_position = camera.view_proj * transform * vec4<f32>(model.position, 1.0);
+    var tex_coords = model.tex_coords;
+
+    if ((instance.flags & 1u) != 0u) {
+        tex_coords.x = 1.0 - tex_coords.x;
+    }
+
+    if ((instance.flags & 2u) != 0u) {
+        tex_coords.y = 1.0 - tex_coords.y;
+    }
+
+    out.tex_coords = instance.uv_rect.xy + tex_coords * instance.uv_rect.zw;
+    out.local = model.tex_coords;
+    out.tint = instance.tint;
+    out.flash = instance.flash;
+    return out;
+}
+
+// Fragment shader
+
+@group(0) @binding(0)
+var t_diffuse: texture_2d<f32>;
+@group(0) @binding(1)
+var s_diffuse: sampler;
+
+struct Params {
+    amount: f32,
+    edge_color: vec4<f32>,
+};
+
+@group(2) @binding(0)
+var<uniform> params: Params;
+@group(2) @binding(1)
+var s_params: sampler;
+@group(2) @binding(2)
+var t_noise: texture_2d<f32>;
+
+fn dissolve_color(in: VertexOutput) -> vec4<f32> {
+    let color = textureSample(t_diffuse, s_diffuse, in.tex_coords) * in.tint;
+    let noise = textureSample(t_noise, s_params, in.local).r;
+
+    if (noise < params.amount) {
+        discard;
+    }
+
+    // A thin band just above the cutoff glows.
+    if (noise < params.amount + 0.1) {
+        return vec4<f32>(params.edge_color.rgb, params.edge_color.a * color.a);
+    }
+
+    return color;
+}
+
+@fragment
+fn fs_main(in: VertexOutput) -> @location(0) vec4<f32> {
+    return dissolve_color(in);
+}
+
+// For multiply blending, see `sprite.wgsl`.
+@fragment
+fn fs_multiply(in: VertexOutput) -> @location(0) vec4<f32> {
+    let color = dissolve_color(in);
+    return vec4<f32>(mix(vec3<f32>(1.0), color.rgb, color.a), color.a);
+}
@@ -2,6 +2,8 @@
 
 struct CameraUniform {
     view_proj: mat4x4<f32>,
+    // Zero when sprites shouldn't be snapped to the texel grid.
+    pixels_per_unit: f32,
 };
 
 @group(1) @binding(0)
@@ -10,29 +12,57 @@ var<uniform> camera: CameraUniform;
 struct VertexInput {
     @location(0) position: vec3<f32>,
     @location(1) tex_coords: vec2<f32>,
+};
+
+struct InstanceInput {
     @location(2) matrix_0: vec4<f32>,
     @location(3) matrix_1: vec4<f32>,
     @location(4) matrix_2: vec4<f32>,
     @location(5) matrix_3: vec4<f32>,
+    // x, y, width and height of the region shown.
+    @location(6) uv_rect: vec4<f32>,
+    @location(7) tint: vec4<f32>,
+    @location(8) flash: vec4<f32>,
+    // Bit 0 flips horizontally, bit 1 vertically.
+    @location(9) flags: u32,
 };
 
 struct VertexOutput {
     @builtin(position) clip_position: vec4<f32>,
     @location(0) tex_coords: vec2<f32>,
+    @location(1) tint: vec4<f32>,
+    @location(2) flash: vec4<f32>,
 };
 
 @vertex
-fn vs_main(model: VertexInput) -> VertexOutput {
-    let transform = mat4x4<f32>(
-        model.matrix_0,
-        model.matrix_1,
-        model.matrix_2,
-        model.matrix_3,
+fn vs_main(model: VertexInput, instance: InstanceInput) -> VertexOutput {
+    var transform = mat4x4<f32>(
+        instance.matrix_0,
+        instance.matrix_1,
+        instance.matrix_2,
+        instance.matrix_3,
     );
 
+    if (camera.pixels_per_unit > 0.0) {
+        let snapped = round(transform[3].xy * camera.pixels_per_unit) / camera.pixels_per_unit;
+        transform[3] = vec4<f32>(snapped, transform[3].zw);
+    }
+
     var out: VertexOutput;
     out.clip_position = camera.view_proj * transform * vec4<f32>(model.position, 1.0);
-    out.tex_coords = model.tex_coords;
+    var tex_coords = model.tex_coords;
+
+    if ((instance.flags & 1u) != 0u) {
+        tex_coords.x = 1.0 - tex_coords.x;
+    }
+
+    if ((instance.flags & 2u) != 0u) {
+        tex_coords.y = 1.0 - tex_coords.y;
+    }
+
+    out.tex_coords = instance.uv_rect.xy + tex_coords * instance.uv_rect.zw;
+    out.tint = instance.tint;
+    out.flash = instance.flash;
     return out;
 }
 
@@ -43,7 +73,22 @@ var t_diffuse: texture_2d<f32>;
 @group(0) @binding(1)
 var s_diffuse: sampler;
 
+fn sprite_color(in: VertexOutput) -> vec4<f32> {
+    let color = textureSample(t_diffuse, s_diffuse, in.tex_coords) * in.tint;
+
+    // Only where the sprite is, so the flash keeps its silhouette.
+    let flash = in.flash.rgb * in.flash.a * color.a;
+    return vec4<f32>(min(color.rgb + flash, vec3<f32>(1.0)), color.a);
+}
+
 @fragment
 fn fs_main(in: VertexOutput) -> @location(0) vec4<f32> {
-    return textureSample(t_diffuse, s_diffuse, in.tex_coords);
+    return sprite_color(in);
+}
+
+// Multiply blending scales what's underneath by this, so transparent texels leave it alone.
+@fragment
+fn fs_multiply(in: VertexOutput) -> @location(0) vec4<f32> {
+    let color = sprite_color(in);
+    return vec4<f32>(mix(vec3<f32>(1.0), color.rgb, color.a), color.a);
 }
\ No newline at end of file
@@ -0,0 +1,145 @@
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum PlaybackMode {
+    /// Starts over from the first frame after the last.
+    Loop,
+    /// Plays forward then backward, without repeating the frames at either end.
+    PingPong,
+    /// Stops on the last frame.
+    Once,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct AnimationFrame {
+    /// Index into the material's sprite sheet.
+    pub frame: usize,
+    /// Seconds this frame is shown for.
+    pub duration: f32,
+}
+
+// Keeps a clip of zero length frames from spinning forever.
+const MIN_FRAME_DURATION: f32 = 0.001;
+
+#[derive(Clone, Debug)]
+pub struct AnimationClip {
+    pub name: String,
+    pub frames: Vec<AnimationFrame>,
+    pub mode: PlaybackMode,
+    events: Vec<(usize, String)>,
+}
+
+impl AnimationClip {
+    pub fn new(name: &str, frames: Vec<AnimationFrame>, mode: PlaybackMode) -> Self {
+        Self {
+            name: name.to_owned(),
+            frames,
+            mode,
+            events: Vec::new(),
+        }
+    }
+
+    /// A clip showing every frame for the same time.
+    pub fn uniform(name: &str, frames: &[usize], frame_duration: f32, mode: PlaybackMode) -> Self {
+        let frames = frames
+            .iter()
+            .map(|&frame| AnimationFrame {
+                frame,
+                duration: frame_duration,
+            })
+            .collect();
+
+        Self::new(name, frames, mode)
+    }
+
+    /// Fires `event` every time playback reaches the clip's `position`th frame.
+    pub fn add_event(&mut self, position: usize, event: &str) {
+        self.events.push((position, event.to_owned()));
+    }
+
+    pub fn events_at(&self, position: usize) -> impl Iterator<Item = &str> {
+        self.events
+            .iter()
+            .filter(move |(at, _)| *at == position)
+            .map(|(_, event)| event.as_str())
+    }
+}
+
+/// How far through a clip playback is.
+#[derive(Clone, Debug, Default)]
+pub struct ClipPlayer {
+    position: usize,
+    elapsed: f32,
+    reversing: bool,
+    finished: bool,
+}
+
+impl ClipPlayer {
+    /// Position in the clip's frame list, not the sprite sheet frame.
+    pub fn position(&self) -> usize {
+        self.position
+    }
+
+    /// Only `Once` clips finish.
+    pub fn is_finished(&self) -> bool {
+        self.finished
+    }
+
+    /// Moves playback on and returns the positions reached along the way, in order.
+    pub fn advance(&mut self, clip: &AnimationClip, delta_time: f32) -> Vec<usize> {
+        let mut reached = Vec::new();
+
+        if clip.frames.is_empty() || self.finished {
+            return reached;
+        }
+
+        self.elapsed += delta_time;
+
+        loop {
+            let duration = clip.frames[self.position].duration.max(MIN_FRAME_DURATION);
+
+            if self.elapsed < duration {
+                break;
+            }
+
+            self.elapsed -= duration;
+
+            match self.next_position(clip) {
+                Some(position) => {
+                    self.position = position;
+                    reached.push(position);
+                }
+                None => {
+                    self.finished = true;
+                    self.elapsed = 0.0;
+                    break;
+                }
+            }
+        }
+
+        reached
+    }
+
+    fn next_position(&mut self, clip: &AnimationClip) -> Option<usize> {
+        let last = clip.frames.len() - 1;
+
+        match clip.mode {
+            PlaybackMode::Once if self.position == last => None,
+            PlaybackMode::Once => Some(self.position + 1),
+            PlaybackMode::Loop if self.position == last => Some(0),
+            PlaybackMode::Loop => Some(self.position + 1),
+            PlaybackMode::PingPong if last == 0 => Some(0),
+            PlaybackMode::PingPong => {
+                if self.position == last {
+                    self.reversing = true;
+                } else if self.position == 0 {
+                    self.reversing = false;
+                }
+
+                if self.reversing {
+                    Some(self.position - 1)
+                } else {
+                    Some(self.position + 1)
+                }
+            }
+        }
+    }
+}
@@ -0,0 +1,76 @@
+/// Values a `Curve` can blend between.
+pub trait Lerp: Copy {
+    fn lerp(self, other: Self, t: f32) -> Self;
+}
+
+impl Lerp for f32 {
+    fn lerp(self, other: Self, t: f32) -> Self {
+        self + (other - self) * t
+    }
+}
+
+impl Lerp for cgmath::Vector2<f32> {
+    fn lerp(self, other: Self, t: f32) -> Self {
+        self + (other - self) * t
+    }
+}
+
+impl Lerp for wgpu::Color {
+    fn lerp(self, other: Self, t: f32) -> Self {
+        let t = t as f64;
+
+        wgpu::Color {
+            r: self.r + (other.r - self.r) * t,
+            g: self.g + (other.g - self.g) * t,
+            b: self.b + (other.b - self.b) * t,
+            a: self.a + (other.a - self.a) * t,
+        }
+    }
+}
+
+/// Keyed values blended linearly between keys, and held flat before the first and after the last.
+#[derive(Clone, Debug)]
+pub struct Curve<T> {
+    keys: Vec<(f32, T)>,
+}
+
+impl<T: Lerp> Curve<T> {
+    /// Keys are `(time, value)` pairs, in any order.
+    pub fn new(mut keys: Vec<(f32, T)>) -> anyhow::Result<Self> {
+        if keys.is_empty() {
+            anyhow::bail!("Curves need at least one key.");
+        }
+
+        keys.sort_by(|(a, _), (b, _)| a.total_cmp(b));
+        Ok(Self { keys })
+    }
+
+    pub fn constant(value: T) -> Self {
+        Self {
+            keys: vec![(0.0, value)],
+        }
+    }
+
+    /// From `start` at time 0 to `end` at time 1.
+    pub fn linear(start: T, end: T) -> Self {
+        Self {
+            keys: vec![(0.0, start), (1.0, end)],
+        }
+    }
+
+    pub fn keys(&self) -> &[(f32, T)] {
+        &self.keys
+    }
+
+    pub fn evaluate(&self, time: f32) -> T {
+        let next = self.keys.partition_point(|(key, _)| *key <= time);
+
+        match (self.keys.get(next.wrapping_sub(1)), self.keys.get(next)) {
+            (Some(&(start, from)), Some(&(end, to))) => {
+                from.lerp(to, (time - start) / (end - start))
+            }
+            (Some(&(_, value)), None) | (None, Some(&(_, value))) => value,
+            (None, None) => unreachable!("Curves always have a key."),
+        }
+    }
+}
@@ -0,0 +1,4 @@
+pub mod clip;
+pub mod curve;
+pub mod parameters;
+pub mod state_machine;
@@ -0,0 +1,47 @@
+use std::collections::{HashMap, HashSet};
+
+/**
+ * Values components set on an entity for its animator's state machine to react to. Anything
+ * never set reads as zero or false.
+ */
+#[derive(Clone, Debug, Default)]
+pub struct AnimationParameters {
+    floats: HashMap<String, f32>,
+    bools: HashMap<String, bool>,
+    triggers: HashSet<String>,
+}
+
+impl AnimationParameters {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn float(&self, name: &str) -> f32 {
+        self.floats.get(name).copied().unwrap_or(0.0)
+    }
+
+    pub fn set_float(&mut self, name: &str, value: f32) {
+        self.floats.insert(name.to_owned(), value);
+    }
+
+    pub fn bool(&self, name: &str) -> bool {
+        self.bools.get(name).copied().unwrap_or(false)
+    }
+
+    pub fn set_bool(&mut self, name: &str, value: bool) {
+        self.bools.insert(name.to_owned(), value);
+    }
+
+    pub fn is_triggered(&self, name: &str) -> bool {
+        self.triggers.contains(name)
+    }
+
+    /// Stays set until a transition that uses it is taken.
+    pub fn set_trigger(&mut self, name: &str) {
+        self.triggers.insert(name.to_owned());
+    }
+
+    pub fn reset_trigger(&mut self, name: &str) {
+        self.triggers.remove(name);
+    }
+}
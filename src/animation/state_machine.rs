@@ -0,0 +1,129 @@
+use super::{clip::AnimationClip, parameters::AnimationParameters};
+
+#[derive(Clone, Debug)]
+pub enum Condition {
+    /// The bool parameter has this value.
+    Bool(String, bool),
+    /// The float parameter is above the threshold.
+    Greater(String, f32),
+    /// The float parameter is below the threshold.
+    Less(String, f32),
+    /// The trigger parameter is set. Taking the transition resets it.
+    Trigger(String),
+    /// The current clip has played to its end, which only `Once` clips do.
+    Finished,
+}
+
+impl Condition {
+    fn holds(&self, parameters: &AnimationParameters, finished: bool) -> bool {
+        match self {
+            Condition::Bool(name, value) => parameters.bool(name) == *value,
+            Condition::Greater(name, threshold) => parameters.float(name) > *threshold,
+            Condition::Less(name, threshold) => parameters.float(name) < *threshold,
+            Condition::Trigger(name) => parameters.is_triggered(name),
+            Condition::Finished => finished,
+        }
+    }
+}
+
+#[derive(Clone, Debug)]
+pub struct Transition {
+    /// `None` for transitions that can be taken from any state.
+    pub from: Option<usize>,
+    pub to: usize,
+    /// All of these have to hold.
+    pub conditions: Vec<Condition>,
+}
+
+/// Named states each playing a clip, and the transitions between them.
+#[derive(Clone, Debug, Default)]
+pub struct AnimationStateMachine {
+    states: Vec<AnimationClip>,
+    transitions: Vec<Transition>,
+}
+
+impl AnimationStateMachine {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Adds a state named after its clip. The first state added is the one playback starts in.
+    pub fn add_state(&mut self, clip: AnimationClip) -> usize {
+        self.states.push(clip);
+        self.states.len() - 1
+    }
+
+    pub fn state_index(&self, name: &str) -> Option<usize> {
+        self.states.iter().position(|clip| clip.name == name)
+    }
+
+    pub fn clip(&self, state: usize) -> &AnimationClip {
+        &self.states[state]
+    }
+
+    pub fn num_states(&self) -> usize {
+        self.states.len()
+    }
+
+    /// Transitions are checked in the order they're added and the first that holds is taken.
+    pub fn add_transition(
+        &mut self,
+        from: &str,
+        to: &str,
+        conditions: Vec<Condition>,
+    ) -> anyhow::Result<()> {
+        let from = self.require_state(from)?;
+        self.add(Some(from), to, conditions)
+    }
+
+    /// A transition that can be taken from every state except the one it leads to.
+    pub fn add_transition_from_any(
+        &mut self,
+        to: &str,
+        conditions: Vec<Condition>,
+    ) -> anyhow::Result<()> {
+        self.add(None, to, conditions)
+    }
+
+    fn add(
+        &mut self,
+        from: Option<usize>,
+        to: &str,
+        conditions: Vec<Condition>,
+    ) -> anyhow::Result<()> {
+        let to = self.require_state(to)?;
+        self.transitions.push(Transition {
+            from,
+            to,
+            conditions,
+        });
+
+        Ok(())
+    }
+
+    fn require_state(&self, name: &str) -> anyhow::Result<usize> {
+        self.state_index(name)
+            .ok_or_else(|| anyhow::anyhow!("Animation state machine has no state `{name}`."))
+    }
+
+    /// The first transition out of `current` whose conditions all hold.
+    pub fn next_transition(
+        &self,
+        current: usize,
+        parameters: &AnimationParameters,
+        finished: bool,
+    ) -> Option<&Transition> {
+        self.transitions.iter().find(|transition| {
+            let applies = match transition.from {
+                Some(from) => from == current,
+                None => transition.to != current,
+            };
+
+            applies
+                && transition
+                    .conditions
+                    .iter()
+                    .all(|condition| condition.holds(parameters, finished))
+        })
+    }
+}
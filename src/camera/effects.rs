@@ -0,0 +1,271 @@
+use std::f32::consts::PI;
+
+/// Shake, punches, zoom pulses and pans layered over a camera's base transform. None of these
+/// touch the camera's position or scale, so nothing needs restoring once they finish.
+pub struct CameraEffects {
+    pub shake: ShakeSettings,
+    /// How quickly punches and kicks settle back, roughly per second.
+    pub recovery: f32,
+    trauma: f32,
+    /// Seconds of shake noise sampled so far, an `f32` would lose the fraction within hours.
+    time: f64,
+    seed: u32,
+    kick_offset: cgmath::Vector2<f32>,
+    kick_velocity: cgmath::Vector2<f32>,
+    zoom_pulses: Vec<ZoomPulse>,
+    pan: Option<Pan>,
+}
+
+#[derive(Clone, Copy, Debug)]
+pub struct ShakeSettings {
+    /// Offset in world units at full trauma.
+    pub max_offset: cgmath::Vector2<f32>,
+    /// Roll in radians at full trauma.
+    pub max_roll: f32,
+    /// How fast the noise is sampled, in cycles per second.
+    pub frequency: f32,
+    /// Trauma lost per second.
+    pub decay: f32,
+}
+
+impl Default for ShakeSettings {
+    fn default() -> Self {
+        Self {
+            max_offset: cgmath::Vector2::new(0.5, 0.5),
+            max_roll: 0.05,
+            frequency: 15.0,
+            decay: 1.0,
+        }
+    }
+}
+
+/// What the effects add to the base transform this frame.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct EffectTransform {
+    pub offset: cgmath::Vector2<f32>,
+    pub roll: f32,
+    /// Multiplies the camera's scale.
+    pub zoom: f32,
+}
+
+impl EffectTransform {
+    pub const IDENTITY: EffectTransform = EffectTransform {
+        offset: cgmath::Vector2 { x: 0.0, y: 0.0 },
+        roll: 0.0,
+        zoom: 1.0,
+    };
+}
+
+struct ZoomPulse {
+    amount: f32,
+    duration: f32,
+    elapsed: f32,
+}
+
+struct Pan {
+    target: cgmath::Vector2<f32>,
+    duration: f32,
+    hold: f32,
+    elapsed: f32,
+}
+
+impl Pan {
+    // Eases out to the target, holds, then eases back.
+    fn weight(&self) -> f32 {
+        let t = if self.elapsed < self.duration {
+            self.elapsed / self.duration
+        } else if self.elapsed < self.duration + self.hold {
+            1.0
+        } else {
+            1.0 - (self.elapsed - self.duration - self.hold) / self.duration
+        };
+
+        smoothstep(t.clamp(0.0, 1.0))
+    }
+
+    fn is_finished(&self) -> bool {
+        self.elapsed >= self.duration * 2.0 + self.hold
+    }
+}
+
+const DEFAULT_RECOVERY: f32 = 12.0;
+
+// Below these a kick is considered settled and snaps to rest.
+const KICK_REST_OFFSET: f32 = 1e-4;
+const KICK_REST_VELOCITY: f32 = 1e-3;
+
+impl Default for CameraEffects {
+    fn default() -> Self {
+        Self::new(0)
+    }
+}
+
+impl CameraEffects {
+    /// Cameras with different seeds shake differently.
+    pub fn new(seed: u32) -> Self {
+        Self {
+            shake: ShakeSettings::default(),
+            recovery: DEFAULT_RECOVERY,
+            trauma: 0.0,
+            time: 0.0,
+            seed,
+            kick_offset: cgmath::Vector2::new(0.0, 0.0),
+            kick_velocity: cgmath::Vector2::new(0.0, 0.0),
+            zoom_pulses: Vec::new(),
+            pan: None,
+        }
+    }
+
+    pub fn trauma(&self) -> f32 {
+        self.trauma
+    }
+
+    /// Adds to the trauma driving the shake, capped at one. Shake grows with its square, so small
+    /// hits barely register while big ones stack up quickly.
+    pub fn add_trauma(&mut self, amount: f32) {
+        self.trauma = (self.trauma + amount).clamp(0.0, 1.0);
+    }
+
+    /// Throws the camera in a direction with the given velocity, in world units per second. It
+    /// springs back on its own.
+    pub fn punch(&mut self, impulse: cgmath::Vector2<f32>) {
+        self.kick_velocity += impulse;
+    }
+
+    /// Knocks the camera straight to an offset, in world units. It springs back on its own.
+    pub fn kick(&mut self, offset: cgmath::Vector2<f32>) {
+        self.kick_offset += offset;
+    }
+
+    /// Zooms in by a fraction of the scale and back out over the duration. Negative amounts zoom
+    /// out instead.
+    pub fn zoom_pulse(&mut self, amount: f32, duration: f32) {
+        if duration <= 0.0 {
+            return;
+        }
+
+        self.zoom_pulses.push(ZoomPulse {
+            amount,
+            duration,
+            elapsed: 0.0,
+        });
+    }
+
+    /// Pans the view to a world position over the duration, holds it there, then pans back to
+    /// wherever the camera is by then. Replaces any pan already running.
+    pub fn pan_to(&mut self, target: cgmath::Vector2<f32>, duration: f32, hold: f32) {
+        self.pan = Some(Pan {
+            target,
+            duration: duration.max(f32::EPSILON),
+            hold: hold.max(0.0),
+            elapsed: 0.0,
+        });
+    }
+
+    pub fn is_panning(&self) -> bool {
+        self.pan.is_some()
+    }
+
+    /// Stops every effect at once.
+    pub fn clear(&mut self) {
+        self.trauma = 0.0;
+        self.kick_offset = cgmath::Vector2::new(0.0, 0.0);
+        self.kick_velocity = cgmath::Vector2::new(0.0, 0.0);
+        self.zoom_pulses.clear();
+        self.pan = None;
+    }
+
+    pub fn update(&mut self, delta_time: f32) {
+        self.time += delta_time as f64;
+        self.trauma = (self.trauma - self.shake.decay * delta_time).max(0.0);
+
+        self.update_kick(delta_time);
+
+        for pulse in &mut self.zoom_pulses {
+            pulse.elapsed += delta_time;
+        }
+        self.zoom_pulses
+            .retain(|pulse| pulse.elapsed < pulse.duration);
+
+        if let Some(pan) = &mut self.pan {
+            pan.elapsed += delta_time;
+
+            if pan.is_finished() {
+                self.pan = None;
+            }
+        }
+    }
+
+    // A critically damped spring solved exactly, so it stays stable however long the frame is.
+    fn update_kick(&mut self, delta_time: f32) {
+        let omega = self.recovery;
+        let decay = (-omega * delta_time).exp();
+
+        let x = self.kick_offset;
+        let v = self.kick_velocity;
+        let c = v + x * omega;
+
+        self.kick_offset = (x + c * delta_time) * decay;
+        self.kick_velocity = (v - c * (omega * delta_time)) * decay;
+
+        if self.kick_offset.x.abs().max(self.kick_offset.y.abs()) < KICK_REST_OFFSET
+            && self.kick_velocity.x.abs().max(self.kick_velocity.y.abs()) < KICK_REST_VELOCITY
+        {
+            self.kick_offset = cgmath::Vector2::new(0.0, 0.0);
+            self.kick_velocity = cgmath::Vector2::new(0.0, 0.0);
+        }
+    }
+
+    /// The combined effects for a camera whose base position is `base`.
+    pub fn sample(&self, base: cgmath::Vector2<f32>) -> EffectTransform {
+        let shake = self.trauma * self.trauma;
+        let t = self.time * self.shake.frequency as f64;
+
+        let mut offset = self.kick_offset;
+        offset.x += self.shake.max_offset.x * shake * noise(self.seed, t);
+        offset.y += self.shake.max_offset.y * shake * noise(self.seed.wrapping_add(1), t);
+
+        if let Some(pan) = &self.pan {
+            offset += (pan.target - base) * pan.weight();
+        }
+
+        let roll = self.shake.max_roll * shake * noise(self.seed.wrapping_add(2), t);
+
+        let zoom = self
+            .zoom_pulses
+            .iter()
+            .map(|pulse| 1.0 - pulse.amount * (PI * pulse.elapsed / pulse.duration).sin())
+            .product::<f32>()
+            .max(f32::EPSILON);
+
+        EffectTransform { offset, roll, zoom }
+    }
+}
+
+fn smoothstep(t: f32) -> f32 {
+    t * t * (3.0 - 2.0 * t)
+}
+
+// Smooth value noise in -1..1, so the shake wanders instead of jittering every frame.
+fn noise(seed: u32, x: f64) -> f32 {
+    let cell = x.floor();
+    let t = smoothstep((x - cell) as f32);
+
+    // Cells wrap around rather than saturate, the hash doesn't care which one it gets.
+    let cell = cell as i64 as i32;
+    let a = lattice(seed, cell);
+    let b = lattice(seed, cell.wrapping_add(1));
+
+    a + (b - a) * t
+}
+
+fn lattice(seed: u32, cell: i32) -> f32 {
+    let mut h = (cell as u32).wrapping_mul(0x9E37_79B9) ^ seed.wrapping_mul(0x85EB_CA6B);
+    h ^= h >> 16;
+    h = h.wrapping_mul(0x7FEB_352D);
+    h ^= h >> 15;
+    h = h.wrapping_mul(0x846C_A68B);
+    h ^= h >> 16;
+
+    (h as f32 / u32::MAX as f32) * 2.0 - 1.0
+}
@@ -0,0 +1,367 @@
+pub mod effects;
+
+use cgmath::InnerSpace;
+use effects::CameraEffects;
+
+#[rustfmt::skip]
+pub const OPENGL_TO_WGPU_MATRIX: cgmath::Matrix4<f32> = cgmath::Matrix4::new(
+    1.0, 0.0, 0.0, 0.0,
+    0.0, 1.0, 0.0, 0.0,
+    0.0, 0.0, 0.5, 0.0,
+    0.0, 0.0, 0.5, 1.0,
+);
+
+pub struct Camera {
+    pub aspect: f32,
+    pub position: cgmath::Point3<f32>,
+    pub scale: f32,
+    pub znear: f32,
+    pub zfar: f32,
+    pub min_scale: f32,
+    pub max_scale: f32,
+    pub bounds: Option<CameraBounds>,
+    pub clear: CameraClear,
+    /// Only entities sharing at least one render layer bit with this mask are drawn.
+    pub layer_mask: u32,
+    pub effects: CameraEffects,
+    follow: Option<CameraFollow>,
+    viewport: Viewport,
+    pixels_per_unit: Option<f32>,
+    /// Width over height of the whole target, the camera's own aspect until `resize` is called.
+    target_aspect: f32,
+    target_height: u32,
+}
+
+/// The part of the render target a camera draws into, as fractions of its width and height with
+/// the origin at the top left.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Viewport {
+    pub x: f32,
+    pub y: f32,
+    pub width: f32,
+    pub height: f32,
+}
+
+impl Viewport {
+    pub const FULL: Viewport = Viewport {
+        x: 0.0,
+        y: 0.0,
+        width: 1.0,
+        height: 1.0,
+    };
+
+    fn aspect(&self) -> f32 {
+        self.width / self.height
+    }
+}
+
+#[derive(Clone, Copy, Debug)]
+pub enum CameraClear {
+    /// Draw over whatever earlier cameras left in the viewport.
+    Nothing,
+    /// Fill the viewport with a color before drawing.
+    Color(wgpu::Color),
+}
+
+/// World-space rectangle the camera's view is kept inside.
+#[derive(Clone, Copy, Debug)]
+pub struct CameraBounds {
+    pub min: cgmath::Vector2<f32>,
+    pub max: cgmath::Vector2<f32>,
+}
+
+#[derive(Clone, Copy, Debug)]
+pub struct CameraFollow {
+    /// Index of the followed entity in the state's entity list, cleared when it's despawned.
+    pub target: usize,
+    /// How quickly the camera catches up, per second. Zero snaps straight to the target.
+    pub damping: f32,
+    /// Half extents of a box around the camera center the target can move in freely.
+    pub dead_zone: cgmath::Vector2<f32>,
+}
+
+const DEFAULT_MIN_SCALE: f32 = 1.0;
+const DEFAULT_MAX_SCALE: f32 = 50.0;
+
+impl Camera {
+    pub fn new(
+        aspect: f32,
+        position: cgmath::Point3<f32>,
+        scale: f32,
+        znear: f32,
+        zfar: f32,
+    ) -> Self {
+        Self {
+            aspect,
+            position,
+            scale,
+            znear,
+            zfar,
+            min_scale: DEFAULT_MIN_SCALE,
+            max_scale: DEFAULT_MAX_SCALE,
+            bounds: None,
+            clear: CameraClear::Nothing,
+            layer_mask: u32::MAX,
+            effects: CameraEffects::default(),
+            follow: None,
+            viewport: Viewport::FULL,
+            pixels_per_unit: None,
+            target_aspect: aspect,
+            target_height: 0,
+        }
+    }
+
+    fn build_view_projection_matrix(&self) -> cgmath::Matrix4<f32> {
+        let effect = self
+            .effects
+            .sample(cgmath::Vector2::new(self.position.x, self.position.y));
+
+        let scale = self.scale * effect.zoom;
+
+        let mut eye = cgmath::Point3::new(
+            self.position.x + effect.offset.x,
+            self.position.y + effect.offset.y,
+            self.position.z,
+        );
+
+        if let Some(pixels_per_unit) = self.pixels_per_unit {
+            eye.x = snap_to_texel(eye.x, scale * self.aspect, pixels_per_unit);
+            eye.y = snap_to_texel(eye.y, scale, pixels_per_unit);
+        }
+
+        // Rolling the up vector rolls the view.
+        let up = cgmath::Vector3::new(-effect.roll.sin(), effect.roll.cos(), 0.0);
+
+        let view = cgmath::Matrix4::look_at_rh(eye, (eye.x, eye.y, 0.0).into(), up);
+
+        let proj = cgmath::ortho(
+            -scale * self.aspect,
+            scale * self.aspect,
+            -scale,
+            scale,
+            self.znear,
+            self.zfar,
+        );
+
+        OPENGL_TO_WGPU_MATRIX * proj * view
+    }
+
+    pub fn resize(&mut self, width: u32, height: u32) {
+        self.target_aspect = width as f32 / height as f32;
+        self.aspect = self.target_aspect * self.viewport.aspect();
+        self.target_height = height;
+        self.fit_pixel_grid();
+        self.clamp_to_bounds();
+    }
+
+    pub fn viewport(&self) -> Viewport {
+        self.viewport
+    }
+
+    /// Viewports with no area, or that aren't finite, are errors and leave the camera unchanged.
+    pub fn set_viewport(&mut self, viewport: Viewport) -> anyhow::Result<()> {
+        let Viewport {
+            x,
+            y,
+            width,
+            height,
+        } = viewport;
+
+        if !(x.is_finite() && y.is_finite() && width.is_finite() && height.is_finite())
+            || width <= 0.0
+            || height <= 0.0
+        {
+            anyhow::bail!("Invalid camera viewport {viewport:?}");
+        }
+
+        self.viewport = viewport;
+        self.aspect = self.target_aspect * viewport.aspect();
+        self.fit_pixel_grid();
+        self.clamp_to_bounds();
+
+        Ok(())
+    }
+
+    pub fn set_position(&mut self, target: cgmath::Vector2<f32>) {
+        self.position.x = target.x;
+        self.position.y = target.y;
+        self.clamp_to_bounds();
+    }
+
+    /// Half the width and height of the visible area in world units.
+    pub fn half_extents(&self) -> cgmath::Vector2<f32> {
+        cgmath::Vector2 {
+            x: self.scale * self.aspect,
+            y: self.scale,
+        }
+    }
+
+    /// The world space area in view, including whatever the effects add this frame.
+    pub fn visible_bounds(&self) -> CameraBounds {
+        let center = cgmath::Vector2::new(self.position.x, self.position.y);
+        let effect = self.effects.sample(center);
+
+        let mut half = self.half_extents() * effect.zoom;
+
+        // A rolled view reaches into the corners of the unrolled one.
+        if effect.roll != 0.0 {
+            let radius = half.magnitude();
+            half = cgmath::Vector2::new(radius, radius);
+        }
+
+        // Snapping can shift the view by up to a texel.
+        if let Some(pixels_per_unit) = self.pixels_per_unit {
+            half += cgmath::Vector2::new(1.0, 1.0) / pixels_per_unit;
+        }
+
+        CameraBounds {
+            min: center + effect.offset - half,
+            max: center + effect.offset + half,
+        }
+    }
+
+    /**
+     * Where a world space point lands in the camera's viewport, from 0 at the top left to 1 at
+     * the bottom right. Points out of view fall outside that.
+     */
+    pub fn project(&self, point: cgmath::Vector2<f32>) -> cgmath::Vector2<f32> {
+        let clip = self.build_view_projection_matrix() * point.extend(0.0).extend(1.0);
+
+        cgmath::Vector2::new((clip.x / clip.w + 1.0) / 2.0, (1.0 - clip.y / clip.w) / 2.0)
+    }
+
+    /// Does nothing while the camera is locked to a pixel grid.
+    pub fn set_scale(&mut self, scale: f32) {
+        if self.pixels_per_unit.is_some() {
+            return;
+        }
+
+        self.scale = scale.clamp(self.min_scale, self.max_scale);
+        self.clamp_to_bounds();
+    }
+
+    /// Multiplies the visible area, so factors below one zoom in and above one zoom out.
+    pub fn zoom_by(&mut self, factor: f32) {
+        self.set_scale(self.scale * factor);
+    }
+
+    pub fn pixels_per_unit(&self) -> Option<f32> {
+        self.pixels_per_unit
+    }
+
+    /**
+     * Locks the scale so one texel covers exactly one pixel of the target last passed to `resize`,
+     * and snaps the view to the texel grid when drawing. Pass `None` to unlock it again.
+     */
+    pub fn set_pixels_per_unit(&mut self, pixels_per_unit: Option<f32>) {
+        self.pixels_per_unit = pixels_per_unit;
+        self.fit_pixel_grid();
+        self.clamp_to_bounds();
+    }
+
+    fn fit_pixel_grid(&mut self) {
+        if let Some(pixels_per_unit) = self.pixels_per_unit {
+            if self.target_height > 0 {
+                let viewport_height = self.target_height as f32 * self.viewport.height;
+                self.scale = viewport_height / (2.0 * pixels_per_unit);
+            }
+        }
+    }
+
+    pub fn follow(&mut self, follow: CameraFollow) {
+        self.follow = Some(follow);
+    }
+
+    pub fn stop_following(&mut self) {
+        self.follow = None;
+    }
+
+    pub fn following(&self) -> Option<&CameraFollow> {
+        self.follow.as_ref()
+    }
+
+    /// Advances the effects and moves toward the followed entity's position, if there is one.
+    pub fn update(&mut self, target_position: Option<cgmath::Vector2<f32>>, delta_time: f64) {
+        self.effects.update(delta_time as f32);
+
+        let (Some(follow), Some(target)) = (self.follow, target_position) else {
+            return;
+        };
+
+        let offset = target - cgmath::Vector2::new(self.position.x, self.position.y);
+
+        // Only the part of the offset outside the dead zone moves the camera.
+        let excess = cgmath::Vector2 {
+            x: offset.x - offset.x.clamp(-follow.dead_zone.x, follow.dead_zone.x),
+            y: offset.y - offset.y.clamp(-follow.dead_zone.y, follow.dead_zone.y),
+        };
+
+        let t = if follow.damping > 0.0 {
+            1.0 - (-follow.damping * delta_time as f32).exp()
+        } else {
+            1.0
+        };
+
+        self.position.x += excess.x * t;
+        self.position.y += excess.y * t;
+        self.clamp_to_bounds();
+    }
+
+    fn clamp_to_bounds(&mut self) {
+        let Some(bounds) = self.bounds else {
+            return;
+        };
+
+        let half = self.half_extents();
+
+        self.position.x = clamp_axis(self.position.x, bounds.min.x, bounds.max.x, half.x);
+        self.position.y = clamp_axis(self.position.y, bounds.min.y, bounds.max.y, half.y);
+    }
+}
+
+// Moves a view center so the view's edge sits on a texel boundary. Centering on the grid instead
+// would put edges mid-texel whenever the view is an odd number of pixels across.
+fn snap_to_texel(center: f32, half_extent: f32, pixels_per_unit: f32) -> f32 {
+    let edge = ((center - half_extent) * pixels_per_unit).round() / pixels_per_unit;
+    edge + half_extent
+}
+
+// If the view is bigger than the bounds there's nothing to clamp against, so center it.
+fn clamp_axis(position: f32, min: f32, max: f32, half: f32) -> f32 {
+    if max - min <= half * 2.0 {
+        (min + max) / 2.0
+    } else {
+        position.clamp(min + half, max - half)
+    }
+}
+
+#[repr(C)]
+#[derive(Debug, Copy, Clone, bytemuck::Pod, bytemuck::Zeroable)]
+pub struct CameraUniform {
+    view_proj: [[f32; 4]; 4],
+    /// Zero when sprites shouldn't be snapped to the texel grid.
+    pixels_per_unit: f32,
+    _padding: [f32; 3],
+}
+
+impl Default for CameraUniform {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl CameraUniform {
+    pub fn new() -> Self {
+        use cgmath::SquareMatrix;
+        Self {
+            view_proj: cgmath::Matrix4::identity().into(),
+            pixels_per_unit: 0.0,
+            _padding: [0.0; 3],
+        }
+    }
+
+    pub fn update_view_proj(&mut self, camera: &Camera) {
+        self.view_proj = camera.build_view_projection_matrix().into();
+        self.pixels_per_unit = camera.pixels_per_unit.unwrap_or(0.0);
+    }
+}
@@ -0,0 +1,132 @@
+use std::collections::HashMap;
+
+use crate::{
+    animation::{
+        clip::{AnimationClip, ClipPlayer},
+        state_machine::{AnimationStateMachine, Condition},
+    },
+    entity::Entity,
+    state::State,
+};
+
+use super::Component;
+
+type EventListener = Box<dyn Fn(&mut Entity, &mut State)>;
+
+/**
+ * Plays the clip of the current state of an animation state machine, switching the entity's
+ * sprite sheet frame as it goes. Transitions are checked every update against the entity's
+ * `animation_parameters`, so add this after the components that set them.
+ */
+pub struct Animator {
+    machine: AnimationStateMachine,
+    current: usize,
+    player: ClipPlayer,
+    started: bool,
+    listeners: HashMap<String, Vec<EventListener>>,
+}
+
+impl Animator {
+    pub fn new(machine: AnimationStateMachine) -> anyhow::Result<Self> {
+        if machine.num_states() == 0 {
+            anyhow::bail!("Animators need a state machine with at least one state.");
+        }
+
+        Ok(Self {
+            machine,
+            current: 0,
+            player: ClipPlayer::default(),
+            started: false,
+            listeners: HashMap::new(),
+        })
+    }
+
+    /// An animator that only ever plays one clip.
+    pub fn from_clip(clip: AnimationClip) -> Self {
+        let mut machine = AnimationStateMachine::new();
+        machine.add_state(clip);
+
+        Self {
+            machine,
+            current: 0,
+            player: ClipPlayer::default(),
+            started: false,
+            listeners: HashMap::new(),
+        }
+    }
+
+    /// Calls `listener` whenever the current clip reaches a frame with this event.
+    pub fn on_event(&mut self, event: &str, listener: impl Fn(&mut Entity, &mut State) + 'static) {
+        self.listeners
+            .entry(event.to_owned())
+            .or_default()
+            .push(Box::new(listener));
+    }
+
+    pub fn current_state(&self) -> &str {
+        &self.machine.clip(self.current).name
+    }
+
+    /// Jumps straight to a state, restarting its clip. Returns false if there is no such state.
+    pub fn play(&mut self, state: &str) -> bool {
+        match self.machine.state_index(state) {
+            Some(index) => {
+                self.enter(index);
+                true
+            }
+            None => false,
+        }
+    }
+
+    fn enter(&mut self, state: usize) {
+        self.current = state;
+        self.player = ClipPlayer::default();
+        self.started = false;
+    }
+}
+
+impl Component for Animator {
+    fn update(&mut self, entity: &mut Entity, state: &mut State, delta_time: f64) {
+        if let Some(transition) = self.machine.next_transition(
+            self.current,
+            &entity.animation_parameters,
+            self.player.is_finished(),
+        ) {
+            for condition in &transition.conditions {
+                if let Condition::Trigger(name) = condition {
+                    entity.animation_parameters.reset_trigger(name);
+                }
+            }
+
+            self.enter(transition.to);
+        }
+
+        let mut reached = Vec::new();
+
+        // A clip's first frame is reached as soon as it starts.
+        if !self.started {
+            self.started = true;
+            reached.push(0);
+        }
+
+        let clip = self.machine.clip(self.current);
+        reached.extend(self.player.advance(clip, delta_time as f32));
+
+        if let Some(frame) = clip.frames.get(self.player.position()) {
+            if frame.frame != entity.get_frame() {
+                entity.set_frame(frame.frame);
+                if let Err(e) = state.write_entity(entity) {
+                    log::error!("{:?}", e);
+                }
+            }
+        }
+
+        for position in reached {
+            for event in clip.events_at(position) {
+                for listener in self.listeners.get(event).into_iter().flatten() {
+                    listener(entity, state);
+                }
+            }
+        }
+    }
+}
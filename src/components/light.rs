@@ -0,0 +1,82 @@
+use crate::{
+    entity::Entity,
+    graphics::lighting::{Cone, Light},
+    state::State,
+};
+
+use super::Component;
+
+/// Lights everything within `radius` of its entity evenly in every direction.
+pub struct PointLight {
+    pub color: wgpu::Color,
+    pub intensity: f32,
+    pub radius: f32,
+    /// How sharply the light fades toward its radius, 1 is linear.
+    pub falloff: f32,
+    /// Units above the sprites, for normal maps.
+    pub height: f32,
+    /// From the entity's position.
+    pub offset: cgmath::Vector2<f32>,
+    /// Whether occluders block it, see `ShadowCaster`.
+    pub cast_shadows: bool,
+}
+
+impl PointLight {
+    pub fn new(color: wgpu::Color, radius: f32) -> Self {
+        Self {
+            color,
+            intensity: 1.0,
+            radius,
+            falloff: 1.0,
+            height: 1.0,
+            offset: cgmath::Vector2::new(0.0, 0.0),
+            cast_shadows: true,
+        }
+    }
+
+    fn light(&self, entity: &Entity, cone: Option<Cone>) -> Light {
+        Light {
+            position: entity.get_position() + self.offset,
+            color: self.color,
+            intensity: self.intensity,
+            radius: self.radius,
+            falloff: self.falloff,
+            height: self.height,
+            cone,
+            cast_shadows: self.cast_shadows,
+        }
+    }
+}
+
+impl Component for PointLight {
+    fn update(&mut self, entity: &mut Entity, state: &mut State, _delta_time: f64) {
+        state.lighting_mut().add_light(self.light(entity, None));
+    }
+}
+
+/// A point light that only shines inside a cone, like a torch or a lamp.
+pub struct SpotLight {
+    pub light: PointLight,
+    pub cone: Cone,
+}
+
+impl SpotLight {
+    /// `direction` and `angle` are in radians, see `Cone`.
+    pub fn new(color: wgpu::Color, radius: f32, direction: f32, angle: f32) -> Self {
+        Self {
+            light: PointLight::new(color, radius),
+            cone: Cone {
+                direction,
+                angle,
+                softness: 0.0,
+            },
+        }
+    }
+}
+
+impl Component for SpotLight {
+    fn update(&mut self, entity: &mut Entity, state: &mut State, _delta_time: f64) {
+        let light = self.light.light(entity, Some(self.cone));
+        state.lighting_mut().add_light(light);
+    }
+}
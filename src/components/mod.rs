@@ -1,7 +1,11 @@
+pub mod animator;
+pub mod light;
+pub mod particle_emitter;
 pub mod player_movement;
+pub mod shadow_caster;
 
 use crate::{entity::Entity, state::State};
 
 pub trait Component {
-    fn update(&self, entity: &mut Entity, state: &mut State, delta_time: f64);
+    fn update(&mut self, entity: &mut Entity, state: &mut State, delta_time: f64);
 }
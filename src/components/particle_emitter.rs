@@ -0,0 +1,275 @@
+use std::ops::Range;
+
+use cgmath::Zero;
+
+use crate::{
+    animation::curve::{Curve, Lerp},
+    entity::Entity,
+    graphics::{
+        particles::ParticleBatch, sprite::SpriteAppearance, sprite_sheet::UvRect,
+        vertex::SpriteInstance,
+    },
+    state::State,
+};
+
+use super::Component;
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum SimulationSpace {
+    /// Particles stay where they were emitted when the entity moves.
+    World,
+    /// Particles move with the entity.
+    Local,
+}
+
+/// Particles emitted all at once, `time` seconds into each cycle.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Burst {
+    pub time: f32,
+    pub count: usize,
+}
+
+struct Particle {
+    position: cgmath::Vector2<f32>,
+    velocity: cgmath::Vector2<f32>,
+    age: f32,
+    lifetime: f32,
+}
+
+/**
+ * Emits particles from its entity and draws them with one of the state's materials, in the
+ * entity's sorting layer and render layers. Each emitter's particles are drawn as instances of
+ * one batch. Particles count against the state's particle budget, see `Particles::claim`.
+ */
+pub struct ParticleEmitter {
+    pub material: usize,
+    /// Sprite sheet frame every particle shows.
+    pub frame: usize,
+    /// Particles per second while emitting.
+    pub rate: f32,
+    pub bursts: Vec<Burst>,
+    /// Seconds in one cycle, bursts repeat every cycle while looping.
+    pub duration: f32,
+    pub looping: bool,
+    /// Cleared once a cycle ends without looping. Live particles finish either way.
+    pub emitting: bool,
+    /// Seconds, picked evenly from the range for each particle.
+    pub lifetime: Range<f32>,
+    /// Initial velocity, each axis picked evenly between the two.
+    pub velocity_min: cgmath::Vector2<f32>,
+    pub velocity_max: cgmath::Vector2<f32>,
+    /// Acceleration in units per second squared.
+    pub gravity: cgmath::Vector2<f32>,
+    /// Tint over each particle's lifetime, from 0 to 1.
+    pub color: Curve<wgpu::Color>,
+    /// Size in units over each particle's lifetime, from 0 to 1.
+    pub size: Curve<f32>,
+    pub space: SimulationSpace,
+    /// This emitter's own cap, under the shared budget.
+    pub max_particles: usize,
+    particles: Vec<Particle>,
+    time: f32,
+    // Fractions of a particle carried between updates so low rates still emit.
+    pending: f32,
+    queued: usize,
+    seed: u32,
+}
+
+impl ParticleEmitter {
+    pub fn new(material: usize) -> Self {
+        Self {
+            material,
+            frame: 0,
+            rate: 10.0,
+            bursts: Vec::new(),
+            duration: 1.0,
+            looping: true,
+            emitting: true,
+            lifetime: 1.0..1.0,
+            velocity_min: cgmath::Vector2::new(-1.0, -1.0),
+            velocity_max: cgmath::Vector2::new(1.0, 1.0),
+            gravity: cgmath::Vector2::zero(),
+            color: Curve::constant(wgpu::Color::WHITE),
+            size: Curve::constant(1.0),
+            space: SimulationSpace::World,
+            max_particles: 1000,
+            particles: Vec::new(),
+            time: 0.0,
+            pending: 0.0,
+            queued: 0,
+            seed: 0x9e37_79b9,
+        }
+    }
+
+    /// Emitters with the same seed and settings emit the same particles.
+    pub fn with_seed(mut self, seed: u32) -> Self {
+        // Xorshift never leaves zero.
+        self.seed = seed.max(1);
+        self
+    }
+
+    /// Emits `count` particles on the next update, emitting or not.
+    pub fn burst(&mut self, count: usize) {
+        self.queued += count;
+    }
+
+    pub fn num_particles(&self) -> usize {
+        self.particles.len()
+    }
+
+    /// Restarts the cycle and removes every live particle.
+    pub fn restart(&mut self) {
+        self.particles.clear();
+        self.time = 0.0;
+        self.pending = 0.0;
+        self.emitting = true;
+    }
+
+    // How many particles to emit over the next `delta_time` seconds, advancing the cycle.
+    fn advance(&mut self, delta_time: f32) -> usize {
+        let mut count = std::mem::take(&mut self.queued);
+
+        if !self.emitting {
+            return count;
+        }
+
+        self.pending += self.rate * delta_time;
+        count += self.pending as usize;
+        self.pending = self.pending.fract();
+
+        let start = self.time;
+        self.time += delta_time;
+
+        count += self.bursts_between(start, self.time);
+
+        if self.time >= self.duration {
+            if self.looping && self.duration > 0.0 {
+                self.time %= self.duration;
+                count += self.bursts_between(0.0, self.time);
+            } else {
+                self.emitting = false;
+            }
+        }
+
+        count
+    }
+
+    fn bursts_between(&self, start: f32, end: f32) -> usize {
+        self.bursts
+            .iter()
+            .filter(|burst| burst.time >= start && burst.time < end.min(self.duration))
+            .map(|burst| burst.count)
+            .sum()
+    }
+
+    // Xorshift, so effects look the same every run.
+    fn random(&mut self) -> f32 {
+        self.seed ^= self.seed << 13;
+        self.seed ^= self.seed >> 17;
+        self.seed ^= self.seed << 5;
+        (self.seed >> 8) as f32 / (1 << 24) as f32
+    }
+
+    fn random_in(&mut self, range: Range<f32>) -> f32 {
+        range.start.lerp(range.end, self.random())
+    }
+
+    fn instance(
+        &self,
+        particle: &Particle,
+        origin: cgmath::Vector2<f32>,
+        uv: UvRect,
+    ) -> SpriteInstance {
+        let t = (particle.age / particle.lifetime).min(1.0);
+
+        let appearance = SpriteAppearance {
+            tint: self.color.evaluate(t),
+            ..Default::default()
+        };
+
+        SpriteInstance::new(
+            origin + particle.position,
+            cgmath::Quaternion::zero(),
+            uv,
+            &appearance,
+        )
+        .scaled(self.size.evaluate(t))
+    }
+}
+
+impl Component for ParticleEmitter {
+    fn update(&mut self, entity: &mut Entity, state: &mut State, delta_time: f64) {
+        let delta_time = delta_time as f32;
+
+        for particle in &mut self.particles {
+            particle.age += delta_time;
+            particle.velocity += self.gravity * delta_time;
+            particle.position += particle.velocity * delta_time;
+        }
+
+        self.particles
+            .retain(|particle| particle.age < particle.lifetime);
+
+        // Particles that no longer fit the budget are dropped, oldest first.
+        let kept = state.particles_mut().claim(self.particles.len());
+        self.particles.drain(..self.particles.len() - kept);
+
+        let wanted = self
+            .advance(delta_time)
+            .min(self.max_particles.saturating_sub(self.particles.len()));
+
+        let emitted = state.particles_mut().claim(wanted);
+
+        let spawn_position = match self.space {
+            SimulationSpace::World => entity.get_position(),
+            SimulationSpace::Local => cgmath::Vector2::zero(),
+        };
+
+        for _ in 0..emitted {
+            let velocity = cgmath::Vector2::new(
+                self.random_in(self.velocity_min.x..self.velocity_max.x),
+                self.random_in(self.velocity_min.y..self.velocity_max.y),
+            );
+
+            let lifetime = self.random_in(self.lifetime.clone());
+
+            self.particles.push(Particle {
+                position: spawn_position,
+                velocity,
+                age: 0.0,
+                lifetime,
+            });
+        }
+
+        if self.particles.is_empty() {
+            return;
+        }
+
+        let uv = state
+            .material(self.material)
+            .map_or(UvRect::FULL, |material| {
+                material.sprite_sheet.frame(self.frame)
+            });
+
+        let origin = match self.space {
+            SimulationSpace::World => cgmath::Vector2::zero(),
+            SimulationSpace::Local => entity.get_position(),
+        };
+
+        let batch = ParticleBatch {
+            material: self.material,
+            sorting_layer: entity.get_sorting_layer(),
+            order_in_layer: entity.get_order_in_layer(),
+            render_layers: entity.get_render_layers(),
+            y: entity.get_position().y,
+            instances: 0..0,
+        };
+
+        let instances = self
+            .particles
+            .iter()
+            .map(|particle| self.instance(particle, origin, uv));
+
+        state.particles_mut().submit(batch, instances);
+    }
+}
@@ -1,10 +1,6 @@
 use cgmath::prelude::*;
 
-use crate::{
-    entity::Entity,
-    graphics::{sprite, vertex},
-    state::State,
-};
+use crate::{entity::Entity, state::State};
 
 use super::Component;
 
@@ -13,7 +9,7 @@ pub struct PlayerMovement {
 }
 
 impl Component for PlayerMovement {
-    fn update(&self, entity: &mut Entity, state: &mut State, delta_time: f64) {
+    fn update(&mut self, entity: &mut Entity, state: &mut State, delta_time: f64) {
         let mut movement = cgmath::Vector2::<f32>::zero();
 
         if state.input.is_left_pressed() {
@@ -28,34 +24,31 @@ impl Component for PlayerMovement {
             movement.y = -0.07;
         }
 
+        let speed = if movement.is_zero() {
+            0.0
+        } else {
+            self.walk_speed
+        };
+
+        entity.animation_parameters.set_float("speed", speed);
+        entity.animation_parameters.set_bool("moving", speed > 0.0);
+
         if movement.is_zero() {
             return;
         }
 
-        // TODO: Replace this when state.wall has been moved to entities.
-        // if let Some(player_coll) = &entity.collider {
-        //     if let Some(wall) = &state.wall {
-        //         if let Some(other_coll) = &wall.collider {
-        //             if player_coll.cast(
-        //                 entity.get_position() + movement,
-        //                 &other_coll,
-        //                 wall.get_position(),
-        //             ) {
-        //                 movement = cgmath::Vector2 { x: 0.0, y: 0.0 }
-        //             }
-        //         }
-        //     }
-        // }
-
-        entity.move_by(movement * self.walk_speed * delta_time as f32);
-
-        let verts = vertex::RenderVertex::new(
-            entity.get_position(),
-            entity.get_rotation(),
-            &sprite::Sprite::get_vertices(),
-        );
-
-        state.graphics.write_entity(entity.get_id(), verts);
-        state.camera.set_position(entity.get_position());
+        let offset = movement * self.walk_speed * delta_time as f32;
+
+        match &entity.collider {
+            Some(collider) => {
+                let moved = state.move_collider(collider, entity.get_position(), offset);
+                entity.move_by(moved.position - entity.get_position());
+            }
+            None => entity.move_by(offset),
+        }
+
+        if let Err(e) = state.write_entity(entity) {
+            log::error!("{:?}", e);
+        }
     }
 }
@@ -0,0 +1,42 @@
+use crate::{entity::Entity, state::State};
+
+use super::Component;
+
+/// The shape a `ShadowCaster` blocks light with.
+#[derive(Clone, Debug, PartialEq)]
+pub enum Occluder {
+    /// The entity's collider, or nothing while it has none.
+    Collider,
+    /// Points around the entity's position.
+    Polygon(Vec<cgmath::Vector2<f32>>),
+}
+
+/// Blocks shadow casting lights with its entity's collider or a polygon.
+pub struct ShadowCaster {
+    pub occluder: Occluder,
+}
+
+impl Component for ShadowCaster {
+    fn update(&mut self, entity: &mut Entity, state: &mut State, _delta_time: f64) {
+        let position = entity.get_position();
+
+        match &self.occluder {
+            Occluder::Collider => {
+                if let Some(collider) = &entity.collider {
+                    let (left, right) = (collider.left(), collider.right());
+                    let (down, up) = (collider.down(), collider.up());
+
+                    state.lighting_mut().add_occluder(
+                        [(left, down), (right, down), (right, up), (left, up)]
+                            .map(|(x, y)| position + cgmath::Vector2::new(x, y)),
+                    );
+                }
+            }
+            Occluder::Polygon(points) => {
+                state
+                    .lighting_mut()
+                    .add_occluder(points.iter().map(|point| position + point));
+            }
+        }
+    }
+}
@@ -1,25 +1,41 @@
 pub struct Config {
     developer_mode: bool,
+    pixel_perfect: bool,
+}
+
+impl Default for Config {
+    fn default() -> Self {
+        Self::new()
+    }
 }
 
 impl Config {
     pub fn new() -> Self {
         Self {
             developer_mode: get_bool("DEV_MODE", Some(false)),
+            pixel_perfect: get_bool("PIXEL_PERFECT", Some(false)),
         }
     }
 
     pub fn developer_mode(&self) -> bool {
         self.developer_mode
     }
+
+    pub fn set_developer_mode(&mut self, developer_mode: bool) {
+        self.developer_mode = developer_mode;
+    }
+
+    pub fn pixel_perfect(&self) -> bool {
+        self.pixel_perfect
+    }
 }
 
 fn get_bool(name: &str, default: Option<bool>) -> bool {
     match std::env::var(name) {
         Ok(raw) => parse_bool(&raw),
-        Err(_) => default.expect(&format!(
-            "Environment value `{name}` is not present and no default was supplied."
-        )),
+        Err(_) => default.unwrap_or_else(|| {
+            panic!("Environment value `{name}` is not present and no default was supplied.")
+        }),
     }
 }
 
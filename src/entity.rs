@@ -1,20 +1,35 @@
 use cgmath;
 
-use crate::{components, graphics::sorting_layer, physics::collider, state::State};
+use crate::{
+    animation::parameters::AnimationParameters,
+    components,
+    graphics::{sorting_layer, sprite::SpriteAppearance},
+    physics::collider,
+    state::State,
+};
+
+pub const DEFAULT_RENDER_LAYERS: u32 = 1;
 
 pub struct Entity {
     id: usize,
+    /// Set by components to drive the entity's `Animator`.
+    pub animation_parameters: AnimationParameters,
+    /// Takes effect once the entity is next written to the renderer.
+    pub appearance: SpriteAppearance,
     pub collider: Option<collider::Collider>,
     pub components: Vec<Option<Box<dyn components::Component>>>,
+    frame: usize,
+    order_in_layer: i32,
     position: cgmath::Vector2<f32>,
+    render_layers: u32,
     rotation: cgmath::Quaternion<f32>,
     sorting_layer: sorting_layer::SortingLayer,
     pub sprite_mat: usize,
 }
 
 impl Entity {
+    /// The entity's id is assigned when it's added to `State`.
     pub fn create(
-        id: usize,
         position: cgmath::Vector2<f32>,
         rotation: cgmath::Quaternion<f32>,
         sprite_mat: usize,
@@ -22,20 +37,30 @@ impl Entity {
         collider: Option<collider::Collider>,
     ) -> Self {
         Self {
+            animation_parameters: AnimationParameters::new(),
+            appearance: SpriteAppearance::default(),
             collider,
             components: Vec::new(),
-            id,
+            frame: 0,
+            id: 0,
+            order_in_layer: 0,
             position,
+            render_layers: DEFAULT_RENDER_LAYERS,
             rotation,
             sorting_layer,
             sprite_mat,
         }
     }
 
+    /// The entity's slot in `State`, 0 until it's added.
     pub fn get_id(&self) -> usize {
         self.id
     }
 
+    pub(crate) fn set_id(&mut self, id: usize) {
+        self.id = id;
+    }
+
     pub fn get_position(&self) -> cgmath::Vector2<f32> {
         self.position
     }
@@ -44,10 +69,42 @@ impl Entity {
         self.rotation
     }
 
+    /// Bitmask of render layers, cameras only draw entities matching their `layer_mask`.
+    pub fn get_render_layers(&self) -> u32 {
+        self.render_layers
+    }
+
+    pub fn set_render_layers(&mut self, render_layers: u32) {
+        self.render_layers = render_layers;
+    }
+
+    /// Index into the material's sprite sheet.
+    pub fn get_frame(&self) -> usize {
+        self.frame
+    }
+
+    /// Takes effect once the entity is next written to the renderer.
+    pub fn set_frame(&mut self, frame: usize) {
+        self.frame = frame;
+    }
+
     pub fn get_sorting_layer(&self) -> sorting_layer::SortingLayer {
         self.sorting_layer
     }
 
+    pub fn set_sorting_layer(&mut self, sorting_layer: sorting_layer::SortingLayer) {
+        self.sorting_layer = sorting_layer;
+    }
+
+    /// Higher values draw over lower ones within the same sorting layer.
+    pub fn get_order_in_layer(&self) -> i32 {
+        self.order_in_layer
+    }
+
+    pub fn set_order_in_layer(&mut self, order_in_layer: i32) {
+        self.order_in_layer = order_in_layer;
+    }
+
     pub fn move_by(&mut self, offset: cgmath::Vector2<f32>) {
         self.position += offset;
     }
@@ -58,7 +115,7 @@ impl Entity {
 
     pub fn update(&mut self, state: &mut State, delta_time: f64) {
         for i in 0..self.components.len() {
-            if let Some(component) = self.components[i].take() {
+            if let Some(mut component) = self.components[i].take() {
                 component.update(self, state, delta_time);
                 self.components[i] = Some(component);
             }
@@ -0,0 +1,88 @@
+use std::{collections::HashMap, rc::Rc, sync::OnceLock};
+
+use anyhow::Context;
+use serde::Deserialize;
+
+use crate::resources::Resource;
+
+use super::{sprite_sheet::PixelRect, texture::TexturePage, Graphics};
+
+/// Where `build.rs` packed each texture under `res/textures`, read from `res/atlases`.
+#[derive(Debug, Deserialize)]
+pub struct AtlasManifest {
+    pub pages: Vec<String>,
+    /// Keyed by path under `res/textures`, the same name textures are loaded by.
+    pub textures: HashMap<String, AtlasEntry>,
+}
+
+#[derive(Clone, Copy, Debug, Deserialize)]
+pub struct AtlasEntry {
+    pub page: usize,
+    pub x: u32,
+    pub y: u32,
+    pub width: u32,
+    pub height: u32,
+}
+
+impl AtlasEntry {
+    pub fn rect(&self) -> PixelRect {
+        PixelRect {
+            x: self.x,
+            y: self.y,
+            width: self.width,
+            height: self.height,
+        }
+    }
+}
+
+static MANIFEST: OnceLock<Option<AtlasManifest>> = OnceLock::new();
+
+impl AtlasManifest {
+    /// The build's manifest, or `None` if it was built without one.
+    pub fn get() -> anyhow::Result<Option<&'static AtlasManifest>> {
+        if let Some(manifest) = MANIFEST.get() {
+            return Ok(manifest.as_ref());
+        }
+
+        let path = Resource::build_path(Some("atlases"), "manifest.json");
+
+        let manifest = match std::fs::read_to_string(&path) {
+            Ok(source) => Some(
+                serde_json::from_str(&source)
+                    .with_context(|| format!("Invalid atlas manifest {}", path.display()))?,
+            ),
+            Err(_) => None,
+        };
+
+        Ok(MANIFEST.get_or_init(|| manifest).as_ref())
+    }
+}
+
+impl Graphics {
+    /// Loads an atlas page the first time it's asked for, later calls share it.
+    pub fn atlas_page(
+        &self,
+        manifest: &AtlasManifest,
+        page: usize,
+    ) -> anyhow::Result<Rc<TexturePage>> {
+        if let Some(loaded) = self.atlas_pages.borrow().get(&page) {
+            return Ok(loaded.clone());
+        }
+
+        let file_name = manifest
+            .pages
+            .get(page)
+            .ok_or_else(|| anyhow::anyhow!("Atlas manifest has no page {page}."))?;
+
+        let path = Resource::build_path(Some("atlases"), file_name);
+        let bytes = std::fs::read(&path)
+            .with_context(|| format!("Couldn't read atlas page {}", path.display()))?;
+
+        let img = image::load_from_memory(&bytes)?;
+        let loaded = Rc::new(TexturePage::from_image(self, &img, file_name)?);
+
+        self.atlas_pages.borrow_mut().insert(page, loaded.clone());
+
+        Ok(loaded)
+    }
+}
@@ -0,0 +1,72 @@
+use wgpu::util::DeviceExt;
+
+use crate::{
+    camera::{Camera, CameraClear, CameraUniform},
+    ui::ui_vertex::UiRenderVertex,
+};
+
+/// The GPU side of one camera: its uniform and a quad filling its viewport for clearing.
+pub struct CameraBinding {
+    pub bind_group: wgpu::BindGroup,
+    buffer: wgpu::Buffer,
+    pub clear_vertex_buffer: wgpu::Buffer,
+    uniform: CameraUniform,
+}
+
+impl CameraBinding {
+    pub fn new(device: &wgpu::Device, layout: &wgpu::BindGroupLayout) -> Self {
+        let uniform = CameraUniform::new();
+
+        let buffer = device.create_buffer_init(&wgpu::util::BufferInitDescriptor {
+            label: Some("Camera Buffer"),
+            contents: bytemuck::cast_slice(&[uniform]),
+            usage: wgpu::BufferUsages::UNIFORM | wgpu::BufferUsages::COPY_DST,
+        });
+
+        let bind_group = device.create_bind_group(&wgpu::BindGroupDescriptor {
+            layout,
+            entries: &[wgpu::BindGroupEntry {
+                binding: 0,
+                resource: buffer.as_entire_binding(),
+            }],
+            label: Some("camera_bind_group"),
+        });
+
+        let clear_vertex_buffer = device.create_buffer(&wgpu::BufferDescriptor {
+            size: std::mem::size_of::<[UiRenderVertex; 4]>() as u64,
+            label: Some("Camera Clear Vertex Buffer"),
+            usage: wgpu::BufferUsages::VERTEX | wgpu::BufferUsages::COPY_DST,
+            mapped_at_creation: false,
+        });
+
+        Self {
+            bind_group,
+            buffer,
+            clear_vertex_buffer,
+            uniform,
+        }
+    }
+
+    pub fn write(&mut self, queue: &wgpu::Queue, camera: &Camera) {
+        self.uniform.update_view_proj(camera);
+        queue.write_buffer(&self.buffer, 0, bytemuck::cast_slice(&[self.uniform]));
+
+        if let CameraClear::Color(color) = camera.clear {
+            // The viewport maps clip space onto itself, so a full clip space quad fills it.
+            let verts = [
+                cgmath::Vector2 { x: -1.0, y: 1.0 },
+                cgmath::Vector2 { x: -1.0, y: -1.0 },
+                cgmath::Vector2 { x: 1.0, y: -1.0 },
+                cgmath::Vector2 { x: 1.0, y: 1.0 },
+            ];
+
+            let render_verts = UiRenderVertex::new(&verts, color);
+
+            queue.write_buffer(
+                &self.clear_vertex_buffer,
+                0,
+                bytemuck::cast_slice(render_verts.as_slice()),
+            );
+        }
+    }
+}
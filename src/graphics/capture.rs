@@ -0,0 +1,109 @@
+use std::sync::mpsc;
+
+use image::RgbaImage;
+
+use super::{Frame, Graphics};
+
+// Buffer to texture copies need every row padded to this many bytes.
+const ROW_ALIGNMENT: u32 = wgpu::COPY_BYTES_PER_ROW_ALIGNMENT;
+
+impl Graphics {
+    /// Renders a frame into an offscreen texture instead of the surface and reads it back.
+    pub fn render_to_image(&mut self, frame: Frame) -> anyhow::Result<RgbaImage> {
+        let width = self.surface_config.width;
+        let height = self.surface_config.height;
+
+        // Pixels are read back as four bytes each, window surfaces are usually BGRA.
+        let is_bgra = match self.surface_config.format {
+            wgpu::TextureFormat::Rgba8Unorm | wgpu::TextureFormat::Rgba8UnormSrgb => false,
+            wgpu::TextureFormat::Bgra8Unorm | wgpu::TextureFormat::Bgra8UnormSrgb => true,
+            format => anyhow::bail!("Frames can't be captured from a {format:?} surface."),
+        };
+
+        let size = wgpu::Extent3d {
+            width,
+            height,
+            depth_or_array_layers: 1,
+        };
+
+        let texture = self.device.create_texture(&wgpu::TextureDescriptor {
+            label: Some("Capture Texture"),
+            size,
+            mip_level_count: 1,
+            sample_count: 1,
+            dimension: wgpu::TextureDimension::D2,
+            format: self.surface_config.format,
+            usage: wgpu::TextureUsages::RENDER_ATTACHMENT | wgpu::TextureUsages::COPY_SRC,
+        });
+
+        let view = texture.create_view(&wgpu::TextureViewDescriptor::default());
+
+        self.render_to_view(&view, frame);
+
+        let unpadded_bytes_per_row = 4 * width;
+        let padded_bytes_per_row = unpadded_bytes_per_row.div_ceil(ROW_ALIGNMENT) * ROW_ALIGNMENT;
+
+        let buffer = self.device.create_buffer(&wgpu::BufferDescriptor {
+            label: Some("Capture Buffer"),
+            size: (padded_bytes_per_row * height) as wgpu::BufferAddress,
+            usage: wgpu::BufferUsages::COPY_DST | wgpu::BufferUsages::MAP_READ,
+            mapped_at_creation: false,
+        });
+
+        let mut encoder = self
+            .device
+            .create_command_encoder(&wgpu::CommandEncoderDescriptor {
+                label: Some("Capture Encoder"),
+            });
+
+        encoder.copy_texture_to_buffer(
+            wgpu::ImageCopyTexture {
+                aspect: wgpu::TextureAspect::All,
+                texture: &texture,
+                mip_level: 0,
+                origin: wgpu::Origin3d::ZERO,
+            },
+            wgpu::ImageCopyBuffer {
+                buffer: &buffer,
+                layout: wgpu::ImageDataLayout {
+                    offset: 0,
+                    bytes_per_row: std::num::NonZeroU32::new(padded_bytes_per_row),
+                    rows_per_image: std::num::NonZeroU32::new(height),
+                },
+            },
+            size,
+        );
+
+        self.queue.submit([encoder.finish()]);
+
+        let slice = buffer.slice(..);
+        let (sender, receiver) = mpsc::channel();
+
+        slice.map_async(wgpu::MapMode::Read, move |result| {
+            sender.send(result).ok();
+        });
+
+        self.device.poll(wgpu::Maintain::Wait);
+        receiver.recv()??;
+
+        let mut pixels = Vec::with_capacity((unpadded_bytes_per_row * height) as usize);
+
+        for row in slice
+            .get_mapped_range()
+            .chunks(padded_bytes_per_row as usize)
+        {
+            pixels.extend_from_slice(&row[..unpadded_bytes_per_row as usize]);
+        }
+
+        buffer.unmap();
+
+        if is_bgra {
+            for pixel in pixels.chunks_mut(4) {
+                pixel.swap(0, 2);
+            }
+        }
+
+        RgbaImage::from_raw(width, height, pixels)
+            .ok_or_else(|| anyhow::anyhow!("Captured frame has the wrong size."))
+    }
+}
@@ -0,0 +1,268 @@
+use std::mem;
+
+use cgmath::InnerSpace;
+use wgpu_glyph::{Section, Text};
+
+use crate::camera;
+
+use super::{pixel_perfect::Letterbox, Graphics};
+
+// Segments in a full circle.
+const CIRCLE_SEGMENTS: usize = 32;
+
+// Labels are this many pixels tall, whatever the camera's zoom.
+const LABEL_SCALE: f32 = 16.0;
+
+// Arrowheads are this long, or a third of the arrow if that's shorter.
+const ARROWHEAD_LENGTH: f32 = 0.25;
+const ARROWHEAD_ANGLE: f32 = 0.5;
+
+/// One end of a gizmo line, in world space.
+#[repr(C)]
+#[derive(Copy, Clone, Debug, PartialEq, bytemuck::Pod, bytemuck::Zeroable)]
+pub struct GizmoVertex {
+    pub position: [f32; 2],
+    pub color: [f32; 4],
+}
+
+impl GizmoVertex {
+    const ATTRIBUTES: [wgpu::VertexAttribute; 2] =
+        wgpu::vertex_attr_array![0 => Float32x2, 1 => Float32x4];
+
+    fn new(position: cgmath::Vector2<f32>, color: wgpu::Color) -> Self {
+        Self {
+            position: position.into(),
+            color: [
+                color.r as f32,
+                color.g as f32,
+                color.b as f32,
+                color.a as f32,
+            ],
+        }
+    }
+
+    pub fn desc<'a>() -> wgpu::VertexBufferLayout<'a> {
+        wgpu::VertexBufferLayout {
+            array_stride: mem::size_of::<Self>() as wgpu::BufferAddress,
+            step_mode: wgpu::VertexStepMode::Vertex,
+            attributes: &Self::ATTRIBUTES,
+        }
+    }
+}
+
+/// Text shown at a point in the world, at the same size however far the camera zooms.
+#[derive(Clone, Debug, PartialEq)]
+pub struct GizmoLabel {
+    pub position: cgmath::Vector2<f32>,
+    pub text: String,
+    pub color: wgpu::Color,
+}
+
+/**
+ * Debug shapes and labels in world space, for seeing what sprites don't show. Everything queued
+ * during a step is drawn in the frames rendered after it and cleared when the next step starts.
+ * Shapes are one pixel wide lines drawn through every camera after post effects, so they keep their
+ * colors and stay sharp in pixel perfect mode. Labels are drawn over them with the UI.
+ */
+#[derive(Default)]
+pub struct Gizmos {
+    vertices: Vec<GizmoVertex>,
+    labels: Vec<GizmoLabel>,
+}
+
+impl Gizmos {
+    pub fn line(
+        &mut self,
+        from: cgmath::Vector2<f32>,
+        to: cgmath::Vector2<f32>,
+        color: wgpu::Color,
+    ) {
+        self.vertices.push(GizmoVertex::new(from, color));
+        self.vertices.push(GizmoVertex::new(to, color));
+    }
+
+    /// The outline of an axis aligned rectangle.
+    pub fn rect(
+        &mut self,
+        min: cgmath::Vector2<f32>,
+        max: cgmath::Vector2<f32>,
+        color: wgpu::Color,
+    ) {
+        let corners = [
+            min,
+            cgmath::Vector2::new(max.x, min.y),
+            max,
+            cgmath::Vector2::new(min.x, max.y),
+        ];
+
+        self.polygon(&corners, color);
+    }
+
+    pub fn circle(&mut self, center: cgmath::Vector2<f32>, radius: f32, color: wgpu::Color) {
+        let points: Vec<_> = (0..CIRCLE_SEGMENTS)
+            .map(|i| {
+                let angle = i as f32 / CIRCLE_SEGMENTS as f32 * std::f32::consts::TAU;
+                center + cgmath::Vector2::new(angle.cos(), angle.sin()) * radius
+            })
+            .collect();
+
+        self.polygon(&points, color);
+    }
+
+    /// A closed outline through every point.
+    pub fn polygon(&mut self, points: &[cgmath::Vector2<f32>], color: wgpu::Color) {
+        for (from, to) in points.iter().zip(points.iter().cycle().skip(1)) {
+            self.line(*from, *to, color);
+        }
+    }
+
+    /// A line with a head at `to`.
+    pub fn arrow(
+        &mut self,
+        from: cgmath::Vector2<f32>,
+        to: cgmath::Vector2<f32>,
+        color: wgpu::Color,
+    ) {
+        self.line(from, to, color);
+
+        let length = (to - from).magnitude();
+
+        if length == 0.0 {
+            return;
+        }
+
+        let back = (from - to) / length * ARROWHEAD_LENGTH.min(length / 3.0);
+
+        for angle in [ARROWHEAD_ANGLE, -ARROWHEAD_ANGLE] {
+            let (sin, cos) = angle.sin_cos();
+            let side =
+                cgmath::Vector2::new(back.x * cos - back.y * sin, back.x * sin + back.y * cos);
+            self.line(to, to + side, color);
+        }
+    }
+
+    /// A plus sign `size` units across, for marking points.
+    pub fn cross(&mut self, center: cgmath::Vector2<f32>, size: f32, color: wgpu::Color) {
+        let half = size / 2.0;
+
+        self.line(
+            center - cgmath::Vector2::new(half, 0.0),
+            center + cgmath::Vector2::new(half, 0.0),
+            color,
+        );
+        self.line(
+            center - cgmath::Vector2::new(0.0, half),
+            center + cgmath::Vector2::new(0.0, half),
+            color,
+        );
+    }
+
+    /// Text with its top left corner at `position`.
+    pub fn label(
+        &mut self,
+        position: cgmath::Vector2<f32>,
+        text: impl Into<String>,
+        color: wgpu::Color,
+    ) {
+        self.labels.push(GizmoLabel {
+            position,
+            text: text.into(),
+            color,
+        });
+    }
+
+    /// Each line's two ends, one after the other.
+    pub fn vertices(&self) -> &[GizmoVertex] {
+        &self.vertices
+    }
+
+    pub fn labels(&self) -> &[GizmoLabel] {
+        &self.labels
+    }
+
+    pub fn clear(&mut self) {
+        self.vertices.clear();
+        self.labels.clear();
+    }
+}
+
+impl Graphics {
+    /// Draws the queued gizmo lines through every camera, over the world shown in `area` of `view`.
+    pub(super) fn draw_gizmos(
+        &self,
+        encoder: &mut wgpu::CommandEncoder,
+        view: &wgpu::TextureView,
+        cameras: &[camera::Camera],
+        area: Letterbox,
+    ) {
+        let count = self.gizmos.vertices.len() as u32;
+
+        if count == 0 {
+            return;
+        }
+
+        let mut render_pass = encoder.begin_render_pass(&wgpu::RenderPassDescriptor {
+            label: Some("Gizmo Render Pass"),
+            color_attachments: &[Some(wgpu::RenderPassColorAttachment {
+                view,
+                resolve_target: None,
+                ops: wgpu::Operations {
+                    load: wgpu::LoadOp::Load,
+                    store: true,
+                },
+            })],
+            depth_stencil_attachment: None,
+        });
+
+        render_pass.set_pipeline(&self.gizmo_render_pipeline);
+        render_pass.set_vertex_buffer(0, self.gizmo_vertex_buffer.buffer().slice(..));
+
+        for (camera, binding) in cameras.iter().zip(&self.camera_bindings) {
+            let viewport = camera.viewport();
+
+            render_pass.set_viewport(
+                area.x + viewport.x * area.width,
+                area.y + viewport.y * area.height,
+                viewport.width * area.width,
+                viewport.height * area.height,
+                0.0,
+                1.0,
+            );
+
+            render_pass.set_bind_group(0, &binding.bind_group, &[]);
+            render_pass.draw(0..count, 0..1);
+        }
+    }
+
+    /// Queues each label as text wherever it's in view of a camera, over the world shown in `area`.
+    pub(super) fn queue_gizmo_labels(&mut self, cameras: &[camera::Camera], area: Letterbox) {
+        for label in &self.gizmos.labels {
+            let color = [
+                label.color.r as f32,
+                label.color.g as f32,
+                label.color.b as f32,
+                label.color.a as f32,
+            ];
+
+            for camera in cameras {
+                let point = camera.project(label.position);
+
+                if !(0.0..1.0).contains(&point.x) || !(0.0..1.0).contains(&point.y) {
+                    continue;
+                }
+
+                let viewport = camera.viewport();
+                let x = area.x + (viewport.x + point.x * viewport.width) * area.width;
+                let y = area.y + (viewport.y + point.y * viewport.height) * area.height;
+
+                self.text_brush.queue(Section {
+                    screen_position: (x, y),
+                    text: vec![Text::new(&label.text)
+                        .with_color(color)
+                        .with_scale(LABEL_SCALE)],
+                    ..Section::default()
+                });
+            }
+        }
+    }
+}
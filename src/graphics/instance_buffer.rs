@@ -0,0 +1,81 @@
+use std::marker::PhantomData;
+
+// Instances allocated up front, the buffer doubles from here when a frame needs more.
+const INITIAL_CAPACITY: usize = 1024;
+
+/**
+ * A vertex buffer of per-instance data, rewritten from the start every frame. Unlike `QuadBuffer`
+ * nothing has to survive a resize, so growing just replaces the buffer.
+ */
+pub struct InstanceBuffer<T: bytemuck::Pod> {
+    label: &'static str,
+    buffer: wgpu::Buffer,
+    capacity: usize,
+    max_capacity: usize,
+    instance: PhantomData<T>,
+}
+
+impl<T: bytemuck::Pod> InstanceBuffer<T> {
+    pub fn new(device: &wgpu::Device, label: &'static str) -> Self {
+        let max_capacity = device.limits().max_buffer_size as usize / std::mem::size_of::<T>();
+        let capacity = INITIAL_CAPACITY.min(max_capacity);
+
+        Self {
+            label,
+            buffer: Self::create_buffer(device, label, capacity),
+            capacity,
+            max_capacity,
+            instance: PhantomData,
+        }
+    }
+
+    pub fn buffer(&self) -> &wgpu::Buffer {
+        &self.buffer
+    }
+
+    pub fn capacity(&self) -> usize {
+        self.capacity
+    }
+
+    /// The most instances this buffer can ever hold on this device.
+    pub fn max_capacity(&self) -> usize {
+        self.max_capacity
+    }
+
+    /// Replaces the buffer's contents, growing it if they don't fit.
+    pub fn write(
+        &mut self,
+        device: &wgpu::Device,
+        queue: &wgpu::Queue,
+        instances: &[T],
+    ) -> anyhow::Result<()> {
+        if instances.len() > self.max_capacity {
+            anyhow::bail!(
+                "{} can't hold {} instances, the limit is {}.",
+                self.label,
+                instances.len(),
+                self.max_capacity
+            );
+        }
+
+        if instances.len() > self.capacity {
+            self.capacity = instances.len().next_power_of_two().min(self.max_capacity);
+            self.buffer = Self::create_buffer(device, self.label, self.capacity);
+        }
+
+        if !instances.is_empty() {
+            queue.write_buffer(&self.buffer, 0, bytemuck::cast_slice(instances));
+        }
+
+        Ok(())
+    }
+
+    fn create_buffer(device: &wgpu::Device, label: &str, capacity: usize) -> wgpu::Buffer {
+        device.create_buffer(&wgpu::BufferDescriptor {
+            size: (capacity * std::mem::size_of::<T>()) as u64,
+            label: Some(&format!("{label} Instance Buffer")),
+            usage: wgpu::BufferUsages::VERTEX | wgpu::BufferUsages::COPY_DST,
+            mapped_at_creation: false,
+        })
+    }
+}
@@ -0,0 +1,721 @@
+use std::{mem, ops::Range, rc::Rc};
+
+use crate::{camera, resources::Resource};
+
+use super::{
+    instance_buffer::InstanceBuffer,
+    lighting::{Light, Lighting},
+    material::Material,
+    pipeline::{self, BlendMode},
+    render_target::RenderTarget,
+    sprite::DrawSprite,
+    texture::TexturePage,
+    vertex, Graphics,
+};
+
+// Normals are stored as they are, not as colors.
+const NORMAL_FORMAT: wgpu::TextureFormat = wgpu::TextureFormat::Rgba8Unorm;
+const STENCIL_FORMAT: wgpu::TextureFormat = wgpu::TextureFormat::Depth24PlusStencil8;
+
+// Pointing straight out of the screen.
+const FLAT_NORMAL: wgpu::Color = wgpu::Color {
+    r: 0.5,
+    g: 0.5,
+    b: 1.0,
+    a: 1.0,
+};
+
+/// One light as `light.wgsl` reads it.
+#[repr(C)]
+#[derive(Copy, Clone, Debug, bytemuck::Pod, bytemuck::Zeroable)]
+pub struct LightInstance {
+    /// Position, radius and height.
+    center: [f32; 4],
+    /// Color times intensity, and the falloff.
+    color: [f32; 4],
+    /// Direction, and the cosines of the cone's edge and of where it's fully lit.
+    cone: [f32; 4],
+}
+
+impl LightInstance {
+    const ATTRIBUTES: [wgpu::VertexAttribute; 3] =
+        wgpu::vertex_attr_array![0 => Float32x4, 1 => Float32x4, 2 => Float32x4];
+
+    pub fn new(light: &Light) -> Self {
+        let cone = match light.cone {
+            Some(cone) => {
+                let outer = cone.angle.cos();
+                let inner = (cone.angle - cone.softness)
+                    .max(0.0)
+                    .cos()
+                    .max(outer + 0.0001);
+                [cone.direction.cos(), cone.direction.sin(), outer, inner]
+            }
+            // Past the smallest cosine, so every direction is fully lit.
+            None => [0.0, 0.0, -2.0, -1.5],
+        };
+
+        Self {
+            center: [
+                light.position.x,
+                light.position.y,
+                light.radius,
+                light.height,
+            ],
+            color: [
+                (light.color.r * light.intensity as f64) as f32,
+                (light.color.g * light.intensity as f64) as f32,
+                (light.color.b * light.intensity as f64) as f32,
+                light.falloff,
+            ],
+            cone,
+        }
+    }
+
+    fn desc<'a>() -> wgpu::VertexBufferLayout<'a> {
+        wgpu::VertexBufferLayout {
+            array_stride: mem::size_of::<Self>() as wgpu::BufferAddress,
+            step_mode: wgpu::VertexStepMode::Instance,
+            attributes: &Self::ATTRIBUTES,
+        }
+    }
+}
+
+const SHADOW_ATTRIBUTES: [wgpu::VertexAttribute; 1] = wgpu::vertex_attr_array![0 => Float32x3];
+
+// Shadow vertices are `[x, y, w]`, see `Lighting::shadow_triangles`.
+fn shadow_vertex_desc<'a>() -> wgpu::VertexBufferLayout<'a> {
+    wgpu::VertexBufferLayout {
+        array_stride: mem::size_of::<[f32; 3]>() as wgpu::BufferAddress,
+        step_mode: wgpu::VertexStepMode::Vertex,
+        attributes: &SHADOW_ATTRIBUTES,
+    }
+}
+
+struct LightTargets {
+    light_map: RenderTarget,
+    normals: wgpu::TextureView,
+    normals_bind_group: wgpu::BindGroup,
+    stencil: wgpu::TextureView,
+}
+
+/**
+ * Draws lighting in three passes over the world, each through every camera's viewport:
+ *
+ * - sprites' normals into a target, flat for sprites without a `NormalMap`,
+ * - the ambient level and then each light added into the light map, with the shadows of its
+ *   occluders masked out in the stencil buffer first,
+ * - the light map multiplied over the world.
+ */
+pub struct LightPass {
+    normal_pipeline: wgpu::RenderPipeline,
+    shadow_pipeline: wgpu::RenderPipeline,
+    light_pipeline: wgpu::RenderPipeline,
+    composite_pipeline: wgpu::RenderPipeline,
+    normal_map_layout: wgpu::BindGroupLayout,
+    normals_layout: wgpu::BindGroupLayout,
+    flat_normal_map: wgpu::BindGroup,
+    light_instances: InstanceBuffer<LightInstance>,
+    shadow_vertices: InstanceBuffer<[f32; 3]>,
+    // Each light drawn this frame and its slice of `shadow_vertices`.
+    shadows: Vec<Range<u32>>,
+    targets: Option<LightTargets>,
+}
+
+impl LightPass {
+    /// Takes the texture and camera bind group layouts sprites are drawn with.
+    pub fn new(
+        device: &wgpu::Device,
+        queue: &wgpu::Queue,
+        config: &wgpu::SurfaceConfiguration,
+        sampler: &wgpu::Sampler,
+        [texture_layout, camera_layout]: [&wgpu::BindGroupLayout; 2],
+    ) -> anyhow::Result<Self> {
+        let normal_map_layout = device.create_bind_group_layout(&wgpu::BindGroupLayoutDescriptor {
+            entries: &[
+                wgpu::BindGroupLayoutEntry {
+                    binding: 0,
+                    visibility: wgpu::ShaderStages::FRAGMENT,
+                    ty: wgpu::BindingType::Texture {
+                        multisampled: false,
+                        view_dimension: wgpu::TextureViewDimension::D2,
+                        sample_type: wgpu::TextureSampleType::Float { filterable: true },
+                    },
+                    count: None,
+                },
+                wgpu::BindGroupLayoutEntry {
+                    binding: 1,
+                    visibility: wgpu::ShaderStages::FRAGMENT,
+                    ty: wgpu::BindingType::Sampler(wgpu::SamplerBindingType::Filtering),
+                    count: None,
+                },
+                wgpu::BindGroupLayoutEntry {
+                    binding: 2,
+                    visibility: wgpu::ShaderStages::FRAGMENT,
+                    ty: wgpu::BindingType::Buffer {
+                        ty: wgpu::BufferBindingType::Uniform,
+                        has_dynamic_offset: false,
+                        min_binding_size: None,
+                    },
+                    count: None,
+                },
+            ],
+            label: Some("normal_map_bind_group_layout"),
+        });
+
+        let normals_layout = device.create_bind_group_layout(&wgpu::BindGroupLayoutDescriptor {
+            entries: &[wgpu::BindGroupLayoutEntry {
+                binding: 0,
+                visibility: wgpu::ShaderStages::FRAGMENT,
+                ty: wgpu::BindingType::Texture {
+                    multisampled: false,
+                    view_dimension: wgpu::TextureViewDimension::D2,
+                    sample_type: wgpu::TextureSampleType::Float { filterable: false },
+                },
+                count: None,
+            }],
+            label: Some("normals_bind_group_layout"),
+        });
+
+        let shader = |name: &str| {
+            pipeline::compile_shader(
+                device,
+                &format!("lighting/{name}"),
+                &Resource::build_path(Some("lighting"), name),
+            )
+        };
+
+        let normal_shader = shader("normal.wgsl")?;
+        let shadow_shader = shader("shadow.wgsl")?;
+        let light_shader = shader("light.wgsl")?;
+
+        let blit_shader = pipeline::compile_shader(
+            device,
+            "blit.wgsl",
+            &Resource::build_path(None, "blit.wgsl"),
+        )?;
+
+        let normal_pipeline = create_pipeline(
+            device,
+            "Normal",
+            &normal_shader,
+            &[texture_layout, camera_layout, &normal_map_layout],
+            &[vertex::Vertex::desc(), vertex::SpriteInstance::desc()],
+            wgpu::ColorTargetState {
+                format: NORMAL_FORMAT,
+                blend: Some(wgpu::BlendState::ALPHA_BLENDING),
+                write_mask: wgpu::ColorWrites::ALL,
+            },
+            None,
+        )?;
+
+        let shadow_pipeline = create_pipeline(
+            device,
+            "Shadow",
+            &shadow_shader,
+            &[&normals_layout, camera_layout],
+            &[shadow_vertex_desc()],
+            wgpu::ColorTargetState {
+                format: config.format,
+                blend: None,
+                write_mask: wgpu::ColorWrites::empty(),
+            },
+            Some(stencil_state(
+                wgpu::CompareFunction::Always,
+                wgpu::StencilOperation::Replace,
+            )),
+        )?;
+
+        let additive = wgpu::BlendComponent {
+            src_factor: wgpu::BlendFactor::One,
+            dst_factor: wgpu::BlendFactor::One,
+            operation: wgpu::BlendOperation::Add,
+        };
+
+        // Lights skip pixels in their own shadows, which hold their stencil reference.
+        let light_pipeline = create_pipeline(
+            device,
+            "Light",
+            &light_shader,
+            &[&normals_layout, camera_layout],
+            &[LightInstance::desc()],
+            wgpu::ColorTargetState {
+                format: config.format,
+                blend: Some(wgpu::BlendState {
+                    color: additive,
+                    alpha: additive,
+                }),
+                write_mask: wgpu::ColorWrites::ALL,
+            },
+            Some(stencil_state(
+                wgpu::CompareFunction::NotEqual,
+                wgpu::StencilOperation::Keep,
+            )),
+        )?;
+
+        let composite_pipeline = create_pipeline(
+            device,
+            "Light Composite",
+            &blit_shader,
+            &[texture_layout],
+            &[],
+            wgpu::ColorTargetState {
+                format: config.format,
+                blend: Some(wgpu::BlendState {
+                    color: wgpu::BlendComponent {
+                        src_factor: wgpu::BlendFactor::Dst,
+                        dst_factor: wgpu::BlendFactor::Zero,
+                        operation: wgpu::BlendOperation::Add,
+                    },
+                    alpha: wgpu::BlendComponent {
+                        src_factor: wgpu::BlendFactor::Zero,
+                        dst_factor: wgpu::BlendFactor::One,
+                        operation: wgpu::BlendOperation::Add,
+                    },
+                }),
+                write_mask: wgpu::ColorWrites::ALL,
+            },
+            None,
+        )?;
+
+        let flat_normal_map = create_flat_normal_map(device, queue, sampler, &normal_map_layout);
+
+        Ok(Self {
+            normal_pipeline,
+            shadow_pipeline,
+            light_pipeline,
+            composite_pipeline,
+            normal_map_layout,
+            normals_layout,
+            flat_normal_map,
+            light_instances: InstanceBuffer::new(device, "Light"),
+            shadow_vertices: InstanceBuffer::new(device, "Shadow Vertex"),
+            shadows: Vec::new(),
+            targets: None,
+        })
+    }
+
+    /// The layout of `NormalMap::bind_group`.
+    pub fn normal_map_layout(&self) -> &wgpu::BindGroupLayout {
+        &self.normal_map_layout
+    }
+
+    pub fn targets_fit(&self, width: u32, height: u32) -> bool {
+        self.targets.as_ref().is_some_and(|targets| {
+            targets.light_map.width == width && targets.light_map.height == height
+        })
+    }
+
+    /// Makes the light map the target lights are drawn into, the world's size.
+    pub fn set_targets(&mut self, device: &wgpu::Device, light_map: RenderTarget) {
+        let size = wgpu::Extent3d {
+            width: light_map.width,
+            height: light_map.height,
+            depth_or_array_layers: 1,
+        };
+
+        let create_view = |label: &str, format: wgpu::TextureFormat, usage| {
+            device
+                .create_texture(&wgpu::TextureDescriptor {
+                    label: Some(label),
+                    size,
+                    mip_level_count: 1,
+                    sample_count: 1,
+                    dimension: wgpu::TextureDimension::D2,
+                    format,
+                    usage,
+                })
+                .create_view(&wgpu::TextureViewDescriptor::default())
+        };
+
+        let normals = create_view(
+            "Normals Texture",
+            NORMAL_FORMAT,
+            wgpu::TextureUsages::RENDER_ATTACHMENT | wgpu::TextureUsages::TEXTURE_BINDING,
+        );
+
+        let stencil = create_view(
+            "Light Stencil Texture",
+            STENCIL_FORMAT,
+            wgpu::TextureUsages::RENDER_ATTACHMENT,
+        );
+
+        let normals_bind_group = device.create_bind_group(&wgpu::BindGroupDescriptor {
+            layout: &self.normals_layout,
+            entries: &[wgpu::BindGroupEntry {
+                binding: 0,
+                resource: wgpu::BindingResource::TextureView(&normals),
+            }],
+            label: Some("Normals Bind Group"),
+        });
+
+        self.targets = Some(LightTargets {
+            light_map,
+            normals,
+            normals_bind_group,
+            stencil,
+        });
+    }
+
+    /// Uploads this frame's lights and the shadows of those that cast them.
+    pub fn write(
+        &mut self,
+        device: &wgpu::Device,
+        queue: &wgpu::Queue,
+        lighting: &Lighting,
+    ) -> anyhow::Result<()> {
+        let instances: Vec<_> = lighting.lights().iter().map(LightInstance::new).collect();
+
+        let mut vertices = Vec::new();
+        let mut shadows = Vec::with_capacity(instances.len());
+
+        for light in lighting.lights() {
+            let start = vertices.len() as u32;
+
+            if light.cast_shadows {
+                lighting.shadow_triangles(light.position, &mut vertices);
+            }
+
+            shadows.push(start..vertices.len() as u32);
+        }
+
+        // Nothing is lit unless everything fits.
+        self.shadows.clear();
+        self.light_instances.write(device, queue, &instances)?;
+        self.shadow_vertices.write(device, queue, &vertices)?;
+        self.shadows = shadows;
+
+        Ok(())
+    }
+}
+
+impl Graphics {
+    /// Draws the frame's lights over the world already drawn into `view`, see `LightPass`.
+    pub(super) fn draw_lighting(
+        &self,
+        encoder: &mut wgpu::CommandEncoder,
+        view: &wgpu::TextureView,
+        cameras: &[camera::Camera],
+        materials: &[Material],
+        ambient: wgpu::Color,
+    ) {
+        let lights = &self.light_pass;
+
+        let Some(targets) = &lights.targets else {
+            return;
+        };
+
+        let (width, height) = (
+            targets.light_map.width as f32,
+            targets.light_map.height as f32,
+        );
+
+        let viewports = cameras
+            .iter()
+            .zip(&self.camera_bindings)
+            .map(|(camera, binding)| {
+                let viewport = camera.viewport();
+
+                let rect = [
+                    viewport.x * width,
+                    viewport.y * height,
+                    viewport.width * width,
+                    viewport.height * height,
+                ];
+
+                (rect, binding)
+            });
+
+        {
+            let mut render_pass = encoder.begin_render_pass(&wgpu::RenderPassDescriptor {
+                label: Some("Normal Render Pass"),
+                color_attachments: &[Some(wgpu::RenderPassColorAttachment {
+                    view: &targets.normals,
+                    resolve_target: None,
+                    ops: wgpu::Operations {
+                        load: wgpu::LoadOp::Clear(FLAT_NORMAL),
+                        store: true,
+                    },
+                })],
+                depth_stencil_attachment: None,
+            });
+
+            // With no normal maps every sprite is flat, which clearing already covers.
+            if materials
+                .iter()
+                .any(|material| material.normal_map.is_some())
+            {
+                for (index, ([x, y, w, h], binding)) in viewports.clone().enumerate() {
+                    render_pass.set_viewport(x, y, w, h, 0.0, 1.0);
+                    self.draw_normals(&mut render_pass, index, &binding.bind_group, materials);
+                }
+            }
+        }
+
+        let ambient = wgpu::Color { a: 1.0, ..ambient };
+
+        // Each camera's stencil values start over, in case viewports overlap.
+        for (index, ([x, y, w, h], binding)) in viewports.enumerate() {
+            let load = match index {
+                0 => wgpu::LoadOp::Clear(ambient),
+                _ => wgpu::LoadOp::Load,
+            };
+
+            let mut render_pass = encoder.begin_render_pass(&wgpu::RenderPassDescriptor {
+                label: Some("Light Render Pass"),
+                color_attachments: &[Some(wgpu::RenderPassColorAttachment {
+                    view: &targets.light_map.view,
+                    resolve_target: None,
+                    ops: wgpu::Operations { load, store: true },
+                })],
+                depth_stencil_attachment: Some(wgpu::RenderPassDepthStencilAttachment {
+                    view: &targets.stencil,
+                    depth_ops: Some(wgpu::Operations {
+                        load: wgpu::LoadOp::Clear(1.0),
+                        store: false,
+                    }),
+                    stencil_ops: Some(wgpu::Operations {
+                        load: wgpu::LoadOp::Clear(0),
+                        store: false,
+                    }),
+                }),
+            });
+
+            render_pass.set_viewport(x, y, w, h, 0.0, 1.0);
+            render_pass.set_bind_group(0, &targets.normals_bind_group, &[]);
+            render_pass.set_bind_group(1, &binding.bind_group, &[]);
+
+            for (i, shadow) in lights.shadows.iter().enumerate() {
+                render_pass.set_stencil_reference(i as u32 + 1);
+
+                if !shadow.is_empty() {
+                    render_pass.set_pipeline(&lights.shadow_pipeline);
+                    render_pass.set_vertex_buffer(0, lights.shadow_vertices.buffer().slice(..));
+                    render_pass.draw(shadow.clone(), 0..1);
+                }
+
+                render_pass.set_pipeline(&lights.light_pipeline);
+                render_pass.set_vertex_buffer(0, lights.light_instances.buffer().slice(..));
+                render_pass.draw(0..6, i as u32..i as u32 + 1);
+            }
+        }
+
+        let mut render_pass = encoder.begin_render_pass(&wgpu::RenderPassDescriptor {
+            label: Some("Light Composite Render Pass"),
+            color_attachments: &[Some(wgpu::RenderPassColorAttachment {
+                view,
+                resolve_target: None,
+                ops: wgpu::Operations {
+                    load: wgpu::LoadOp::Load,
+                    store: true,
+                },
+            })],
+            depth_stencil_attachment: None,
+        });
+
+        render_pass.set_pipeline(&lights.composite_pipeline);
+        render_pass.set_bind_group(0, &targets.light_map.bind_group, &[]);
+        render_pass.draw(0..3, 0..1);
+    }
+
+    // Draws one camera's queued sprites into the normals target.
+    fn draw_normals<'a>(
+        &'a self,
+        render_pass: &mut wgpu::RenderPass<'a>,
+        camera: usize,
+        camera_bind_group: &'a wgpu::BindGroup,
+        materials: &'a [Material],
+    ) {
+        render_pass.set_pipeline(&self.light_pass.normal_pipeline);
+        render_pass.set_bind_group(1, camera_bind_group, &[]);
+        render_pass.set_vertex_buffer(0, self.sprite_quad_vertex_buffer.slice(..));
+        render_pass.set_vertex_buffer(1, self.sprite_instance_buffer.buffer().slice(..));
+        render_pass.set_index_buffer(
+            self.sprite_quad_index_buffer.slice(..),
+            wgpu::IndexFormat::Uint32,
+        );
+
+        let mut bound_page: Option<&Rc<TexturePage>> = None;
+        let mut bound_chunk = None;
+
+        for batch in self.render_queue.batches(camera) {
+            let material = &materials[batch.material];
+
+            // Glows and shadows don't change the surface under them.
+            if matches!(
+                material.blend_mode,
+                BlendMode::Additive | BlendMode::Multiply
+            ) {
+                continue;
+            }
+
+            if batch.chunk != bound_chunk {
+                let instances = match batch.chunk.and_then(|i| self.tile_chunks.get(i)) {
+                    Some(chunk) => &chunk.buffer,
+                    None => self.sprite_instance_buffer.buffer(),
+                };
+
+                render_pass.set_vertex_buffer(1, instances.slice(..));
+                bound_chunk = batch.chunk;
+            }
+
+            let page = &material.diffuse_texture.page;
+
+            if !bound_page.is_some_and(|bound| Rc::ptr_eq(bound, page)) {
+                render_pass.set_bind_group(0, &page.bind_group, &[]);
+                bound_page = Some(page);
+            }
+
+            let normal_map = match &material.normal_map {
+                Some(normal_map) => &normal_map.bind_group,
+                None => &self.light_pass.flat_normal_map,
+            };
+
+            render_pass.set_bind_group(2, normal_map, &[]);
+            render_pass.draw_sprites(batch);
+        }
+    }
+}
+
+fn stencil_state(
+    compare: wgpu::CompareFunction,
+    pass_op: wgpu::StencilOperation,
+) -> wgpu::DepthStencilState {
+    let face = wgpu::StencilFaceState {
+        compare,
+        fail_op: wgpu::StencilOperation::Keep,
+        depth_fail_op: wgpu::StencilOperation::Keep,
+        pass_op,
+    };
+
+    wgpu::DepthStencilState {
+        format: STENCIL_FORMAT,
+        depth_write_enabled: false,
+        depth_compare: wgpu::CompareFunction::Always,
+        stencil: wgpu::StencilState {
+            front: face,
+            back: face,
+            read_mask: 0xff,
+            write_mask: 0xff,
+        },
+        bias: wgpu::DepthBiasState::default(),
+    }
+}
+
+fn create_pipeline(
+    device: &wgpu::Device,
+    label: &str,
+    shader: &wgpu::ShaderModule,
+    bind_group_layouts: &[&wgpu::BindGroupLayout],
+    buffers: &[wgpu::VertexBufferLayout],
+    target: wgpu::ColorTargetState,
+    depth_stencil: Option<wgpu::DepthStencilState>,
+) -> anyhow::Result<wgpu::RenderPipeline> {
+    let layout = device.create_pipeline_layout(&wgpu::PipelineLayoutDescriptor {
+        label: Some(&format!("{label} Render Pipeline Layout")),
+        bind_group_layouts,
+        push_constant_ranges: &[],
+    });
+
+    device.push_error_scope(wgpu::ErrorFilter::Validation);
+
+    let pipeline = device.create_render_pipeline(&wgpu::RenderPipelineDescriptor {
+        label: Some(&format!("{label} Render Pipeline")),
+        layout: Some(&layout),
+        vertex: wgpu::VertexState {
+            module: shader,
+            entry_point: "vs_main",
+            buffers,
+        },
+        fragment: Some(wgpu::FragmentState {
+            module: shader,
+            entry_point: "fs_main",
+            targets: &[Some(target)],
+        }),
+        // Shadows are built without caring which way their triangles wind.
+        primitive: wgpu::PrimitiveState {
+            topology: wgpu::PrimitiveTopology::TriangleList,
+            cull_mode: None,
+            ..Default::default()
+        },
+        depth_stencil,
+        multisample: wgpu::MultisampleState::default(),
+        multiview: None,
+    });
+
+    if let Some(error) = pollster::block_on(device.pop_error_scope()) {
+        anyhow::bail!("Couldn't create the {label} render pipeline: {error}");
+    }
+
+    Ok(pipeline)
+}
+
+// A one texel normal map pointing straight out, for sprites without one.
+fn create_flat_normal_map(
+    device: &wgpu::Device,
+    queue: &wgpu::Queue,
+    sampler: &wgpu::Sampler,
+    layout: &wgpu::BindGroupLayout,
+) -> wgpu::BindGroup {
+    let size = wgpu::Extent3d {
+        width: 1,
+        height: 1,
+        depth_or_array_layers: 1,
+    };
+
+    let texture = device.create_texture(&wgpu::TextureDescriptor {
+        label: Some("Flat Normal Map Texture"),
+        size,
+        mip_level_count: 1,
+        sample_count: 1,
+        dimension: wgpu::TextureDimension::D2,
+        format: NORMAL_FORMAT,
+        usage: wgpu::TextureUsages::TEXTURE_BINDING | wgpu::TextureUsages::COPY_DST,
+    });
+
+    queue.write_texture(
+        wgpu::ImageCopyTexture {
+            aspect: wgpu::TextureAspect::All,
+            texture: &texture,
+            mip_level: 0,
+            origin: wgpu::Origin3d::ZERO,
+        },
+        &[128, 128, 255, 255],
+        wgpu::ImageDataLayout {
+            offset: 0,
+            bytes_per_row: std::num::NonZeroU32::new(4),
+            rows_per_image: std::num::NonZeroU32::new(1),
+        },
+        size,
+    );
+
+    let view = texture.create_view(&wgpu::TextureViewDescriptor::default());
+
+    let region = wgpu::util::DeviceExt::create_buffer_init(
+        device,
+        &wgpu::util::BufferInitDescriptor {
+            label: Some("Flat Normal Map Region Buffer"),
+            contents: bytemuck::cast_slice(&[0.0f32, 0.0, 1.0, 1.0]),
+            usage: wgpu::BufferUsages::UNIFORM,
+        },
+    );
+
+    device.create_bind_group(&wgpu::BindGroupDescriptor {
+        layout,
+        entries: &[
+            wgpu::BindGroupEntry {
+                binding: 0,
+                resource: wgpu::BindingResource::TextureView(&view),
+            },
+            wgpu::BindGroupEntry {
+                binding: 1,
+                resource: wgpu::BindingResource::Sampler(sampler),
+            },
+            wgpu::BindGroupEntry {
+                binding: 2,
+                resource: region.as_entire_binding(),
+            },
+        ],
+        label: Some("Flat Normal Map Bind Group"),
+    })
+}
@@ -0,0 +1,143 @@
+use std::ops::Range;
+
+use cgmath::InnerSpace;
+
+/// Lights drawn per frame, each one masks its shadows with its own stencil value.
+pub const MAX_LIGHTS: usize = 255;
+
+/// Where a spot light shines, in radians.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Cone {
+    /// Counter-clockwise from the positive x axis.
+    pub direction: f32,
+    /// From the direction to the cone's edge.
+    pub angle: f32,
+    /// How far inside the edge the light starts fading out.
+    pub softness: f32,
+}
+
+/// One light for one frame, as components submit them.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Light {
+    pub position: cgmath::Vector2<f32>,
+    pub color: wgpu::Color,
+    pub intensity: f32,
+    /// Units out from the position the light reaches.
+    pub radius: f32,
+    /// How sharply the light fades toward its radius, 1 is linear.
+    pub falloff: f32,
+    /// Units above the sprites, lower lights hit normal-mapped sprites at steeper angles.
+    pub height: f32,
+    /// Spot lights only shine inside their cone.
+    pub cone: Option<Cone>,
+    pub cast_shadows: bool,
+}
+
+/**
+ * The ambient light level and the lights and occluders submitted each step, gathered for the
+ * renderer. The world is drawn unlit until an ambient level is set. Lit frames start from it,
+ * add every light, and multiply the result over the sprites before post effects and the UI.
+ */
+#[derive(Default)]
+pub struct Lighting {
+    ambient: Option<wgpu::Color>,
+    lights: Vec<Light>,
+    points: Vec<cgmath::Vector2<f32>>,
+    occluders: Vec<Range<usize>>,
+}
+
+impl Lighting {
+    pub fn ambient(&self) -> Option<wgpu::Color> {
+        self.ambient
+    }
+
+    /// Switches lighting on at the given level, or off with `None`.
+    pub fn set_ambient(&mut self, ambient: Option<wgpu::Color>) {
+        self.ambient = ambient;
+    }
+
+    pub fn is_enabled(&self) -> bool {
+        self.ambient.is_some()
+    }
+
+    /// Adds a light for this step. Returns false once `MAX_LIGHTS` have been added.
+    pub fn add_light(&mut self, light: Light) -> bool {
+        if self.lights.len() >= MAX_LIGHTS {
+            return false;
+        }
+
+        self.lights.push(light);
+        true
+    }
+
+    /**
+     * Adds a polygon that blocks shadow casting lights for this step, in world space and either
+     * winding. Polygons with fewer than three points cast nothing.
+     */
+    pub fn add_occluder(&mut self, points: impl IntoIterator<Item = cgmath::Vector2<f32>>) {
+        let start = self.points.len();
+        self.points.extend(points);
+
+        if self.points.len() - start < 3 {
+            self.points.truncate(start);
+            return;
+        }
+
+        self.occluders.push(start..self.points.len());
+    }
+
+    pub fn lights(&self) -> &[Light] {
+        &self.lights
+    }
+
+    pub fn occluders(&self) -> impl Iterator<Item = &[cgmath::Vector2<f32>]> {
+        self.occluders
+            .iter()
+            .map(|range| &self.points[range.clone()])
+    }
+
+    /// Removes this step's lights and occluders, keeping the ambient level.
+    pub fn clear(&mut self) {
+        self.lights.clear();
+        self.points.clear();
+        self.occluders.clear();
+    }
+
+    /**
+     * Triangles covering everything the occluders hide from a point, as `[x, y, w]` vertices.
+     * Each edge facing away from the point is stretched out to infinity, with `w` 0 at the far
+     * end, so the occluders themselves stay lit.
+     */
+    pub fn shadow_triangles(&self, from: cgmath::Vector2<f32>, out: &mut Vec<[f32; 3]>) {
+        for polygon in self.occluders() {
+            // Positive for counter-clockwise polygons, whose outside is right of each edge.
+            let winding = polygon
+                .iter()
+                .zip(polygon.iter().cycle().skip(1))
+                .map(|(a, b)| a.x * b.y - b.x * a.y)
+                .sum::<f32>()
+                .signum();
+
+            for (a, b) in polygon.iter().zip(polygon.iter().cycle().skip(1)) {
+                let edge = b - a;
+                let normal = cgmath::Vector2::new(edge.y, -edge.x) * winding;
+
+                if normal.dot(a - from) <= 0.0 {
+                    continue;
+                }
+
+                let far_a = a - from;
+                let far_b = b - from;
+
+                out.extend_from_slice(&[
+                    [a.x, a.y, 1.0],
+                    [b.x, b.y, 1.0],
+                    [far_b.x, far_b.y, 0.0],
+                    [a.x, a.y, 1.0],
+                    [far_b.x, far_b.y, 0.0],
+                    [far_a.x, far_a.y, 0.0],
+                ]);
+            }
+        }
+    }
+}
@@ -1,19 +1,58 @@
-use super::{texture, Graphics};
+use super::{
+    normal_map::NormalMap,
+    pipeline::{BlendMode, PipelineKey},
+    shader::MaterialShader,
+    sprite_sheet::SpriteSheet,
+    texture,
+};
 
 pub struct Material {
     pub name: String,
     pub diffuse_texture: texture::Texture,
-    pub bind_group: wgpu::BindGroup,
+    /// Frames entities using this material can show. Starts as one frame covering the texture.
+    pub sprite_sheet: SpriteSheet,
+    /// Draws with `sprite.wgsl` when there's none.
+    pub shader: Option<MaterialShader>,
+    /// Takes effect from the next frame, each new mode creates its pipeline on first use.
+    pub blend_mode: BlendMode,
+    /// Only read when lighting is on, sprites are lit as if flat without one.
+    pub normal_map: Option<NormalMap>,
 }
 
 impl Material {
-    pub fn new(name: String, graphics: &Graphics, diffuse_texture: texture::Texture) -> Self {
-        let bind_group = graphics.create_texture_bind_group(&name, &diffuse_texture);
+    pub fn new(name: String, diffuse_texture: texture::Texture) -> Self {
+        let mut sprite_sheet = SpriteSheet::single(diffuse_texture.width, diffuse_texture.height);
+        sprite_sheet.set_region(diffuse_texture.region);
 
         Self {
             name,
-            bind_group,
             diffuse_texture,
+            sprite_sheet,
+            shader: None,
+            blend_mode: BlendMode::default(),
+            normal_map: None,
         }
     }
+
+    pub fn with_shader(mut self, shader: MaterialShader) -> Self {
+        self.shader = Some(shader);
+        self
+    }
+
+    pub fn with_normal_map(mut self, normal_map: NormalMap) -> Self {
+        self.normal_map = Some(normal_map);
+        self
+    }
+
+    /// The pipeline this material is drawn with.
+    pub fn pipeline_key(&self) -> PipelineKey {
+        PipelineKey {
+            shader: self.shader.as_ref().map(|shader| shader.name().clone()),
+            blend: self.blend_mode,
+        }
+    }
+
+    pub fn bind_group(&self) -> &wgpu::BindGroup {
+        &self.diffuse_texture.page.bind_group
+    }
 }
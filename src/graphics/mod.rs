@@ -1,12 +1,34 @@
+pub mod atlas;
+pub mod camera_binding;
+pub mod capture;
+pub mod gizmos;
+pub mod instance_buffer;
+pub mod light_pass;
+pub mod lighting;
 pub mod material;
+pub mod normal_map;
+pub mod particles;
 pub mod pipeline;
+pub mod pixel_perfect;
+pub mod post_process;
+pub mod quad_buffer;
+pub mod render_queue;
+pub mod render_target;
+pub mod shader;
 pub mod sorting_layer;
 pub mod sprite;
+pub mod sprite_sheet;
 pub mod texture;
+pub mod tile_chunk;
 pub mod vertex;
 
-use std::collections::HashMap;
+use std::{
+    cell::RefCell,
+    collections::{HashMap, HashSet},
+    rc::Rc,
+};
 
+use anyhow::Context;
 use cgmath::Zero;
 use image::{ImageBuffer, Rgba};
 use wgpu::{util::DeviceExt, Sampler, TextureView};
@@ -21,36 +43,79 @@ use crate::{
     ui::{canvas::Canvas, element::DrawElement, style::Style, ui_vertex::UiRenderVertex},
 };
 
-use self::pipeline::{create_sprite_render_pipeline, create_ui_render_pipeline};
+use self::camera_binding::CameraBinding;
+use self::gizmos::{GizmoVertex, Gizmos};
+use self::instance_buffer::InstanceBuffer;
+use self::light_pass::LightPass;
+use self::pipeline::{
+    create_blit_render_pipeline, create_clear_render_pipeline, create_gizmo_render_pipeline,
+    create_ui_render_pipeline, PipelineCache, PipelineKey,
+};
+use self::pixel_perfect::{Letterbox, PixelPerfect, PixelPerfectTarget};
+use self::post_process::{PostEffect, PostProcess};
+use self::quad_buffer::QuadBuffer;
+use self::render_queue::{RenderQueue, RenderStats};
+use self::render_target::RenderTarget;
 use self::sprite::DrawSprite;
-use self::texture::Texture;
+use self::texture::TexturePage;
+use self::tile_chunk::TileChunks;
+use self::vertex::SpriteInstance;
+
+/// Everything from the state that a frame is drawn from.
+pub struct Frame<'a> {
+    pub cameras: &'a [camera::Camera],
+    pub entities: &'a [Option<entity::Entity>],
+    pub materials: &'a [material::Material],
+    pub sorting_layers: &'a sorting_layer::SortingLayers,
+    pub particles: &'a particles::Particles,
+    pub lighting: &'a lighting::Lighting,
+    pub ui_canvas: &'a mut Canvas,
+    pub config: &'a Config,
+    pub styles: &'a HashMap<String, Style>,
+}
 
 // TODO: How many of these fields don't need to be here?
 pub struct Graphics {
-    camera_bind_group: wgpu::BindGroup,
-    camera_buffer: wgpu::Buffer,
-    camera_uniform: camera::CameraUniform,
+    atlas_pages: RefCell<HashMap<usize, Rc<TexturePage>>>,
+    blit_render_pipeline: wgpu::RenderPipeline,
+    camera_bind_group_layout: wgpu::BindGroupLayout,
+    camera_bindings: Vec<CameraBinding>,
     clear_color: wgpu::Color,
+    clear_render_pipeline: wgpu::RenderPipeline,
     device: wgpu::Device,
+    /// Per-frame uploads that failed last frame, so each failure is logged once, not every frame.
+    failing_uploads: HashSet<&'static str>,
+    gizmos: Gizmos,
+    gizmo_render_pipeline: wgpu::RenderPipeline,
+    gizmo_vertex_buffer: InstanceBuffer<GizmoVertex>,
+    light_pass: LightPass,
     ui_render_pipeline: wgpu::RenderPipeline,
+    pipelines: PipelineCache,
+    pixel_perfect: Option<PixelPerfectTarget>,
+    post_process: PostProcess,
     queue: wgpu::Queue,
-    sprite_index_buffer: wgpu::Buffer,
-    sprite_render_pipeline: wgpu::RenderPipeline,
-    sprite_vertex_buffer: wgpu::Buffer,
+    render_queue: RenderQueue,
+    render_stats: RenderStats,
+    /// Each entity's latest instance, by entity id. Batched into `sprite_instance_buffer` per frame.
+    sprite_instances: Vec<SpriteInstance>,
+    sprite_instance_buffer: InstanceBuffer<SpriteInstance>,
+    sprite_quad_index_buffer: wgpu::Buffer,
+    sprite_quad_vertex_buffer: wgpu::Buffer,
     staging_belt: wgpu::util::StagingBelt,
-    surface: wgpu::Surface,
+    surface: Option<wgpu::Surface>,
     surface_config: wgpu::SurfaceConfiguration,
     text_brush: GlyphBrush<()>,
     texture_bind_group_layout: wgpu::BindGroupLayout,
     texture_sampler: Sampler,
-    ui_index_buffer: wgpu::Buffer,
-    ui_vertex_buffer: wgpu::Buffer,
+    tile_chunks: TileChunks,
+    ui_quads: QuadBuffer<UiRenderVertex>,
 }
 
-const MAX_ENTITIES: usize = 24000;
+// Fills the window around the upscaled world in pixel perfect mode.
+const LETTERBOX_COLOR: wgpu::Color = wgpu::Color::BLACK;
 
 impl Graphics {
-    pub async fn new(window: &Window, camera: &camera::Camera) -> Self {
+    pub async fn new(window: &Window) -> Self {
         let size = window.inner_size();
 
         let instance = wgpu::Instance::new(wgpu::Backends::all());
@@ -65,17 +130,7 @@ impl Graphics {
             .await
             .unwrap();
 
-        let (device, queue) = adapter
-            .request_device(
-                &wgpu::DeviceDescriptor {
-                    features: wgpu::Features::empty(),
-                    limits: wgpu::Limits::default(),
-                    label: None,
-                },
-                None,
-            )
-            .await
-            .unwrap();
+        let (device, queue) = request_device(&adapter).await.unwrap();
 
         let surface_config = wgpu::SurfaceConfiguration {
             usage: wgpu::TextureUsages::RENDER_ATTACHMENT,
@@ -88,54 +143,68 @@ impl Graphics {
 
         surface.configure(&device, &surface_config);
 
-        let sprite_vertex_buffer = device.create_buffer(&wgpu::BufferDescriptor {
-            size: std::mem::size_of::<[vertex::RenderVertex; MAX_ENTITIES * 4]>() as u64,
-            label: Some("Sprite Vertex Buffer"),
-            usage: wgpu::BufferUsages::VERTEX | wgpu::BufferUsages::COPY_DST,
-            mapped_at_creation: false,
-        });
-
-        let ui_vertex_buffer = device.create_buffer(&wgpu::BufferDescriptor {
-            size: std::mem::size_of::<[UiRenderVertex; MAX_ENTITIES * 4]>() as u64,
-            label: Some("UI Vertex Buffer"),
-            usage: wgpu::BufferUsages::VERTEX | wgpu::BufferUsages::COPY_DST,
-            mapped_at_creation: false,
-        });
-
-        let mut indices = Vec::<u32>::new();
+        Self::create(device, queue, Some(surface), surface_config).await
+    }
 
-        for i in 0..MAX_ENTITIES {
-            let new_indices = sprite::Sprite::get_indices()
-                .into_iter()
-                .map(|idx| idx + (4 * i as u32));
+    /**
+     * Creates a renderer with no window or surface. Frames can only be read back with
+     * `render_to_image`. Pass `force_fallback_adapter` to get a software adapter, which gives
+     * stable output across machines.
+     */
+    pub async fn new_offscreen(
+        width: u32,
+        height: u32,
+        force_fallback_adapter: bool,
+    ) -> anyhow::Result<Self> {
+        let instance = wgpu::Instance::new(wgpu::Backends::all());
 
-            indices.extend(new_indices);
-        }
+        let adapter = instance
+            .request_adapter(&wgpu::RequestAdapterOptions {
+                power_preference: wgpu::PowerPreference::default(),
+                compatible_surface: None,
+                force_fallback_adapter,
+            })
+            .await
+            .ok_or_else(|| anyhow::anyhow!("No suitable graphics adapter found."))?;
 
-        let sprite_index_buffer = device.create_buffer_init(&wgpu::util::BufferInitDescriptor {
-            label: Some("Sprite Index Buffer"),
-            contents: bytemuck::cast_slice(indices.as_slice()),
-            usage: wgpu::BufferUsages::INDEX | wgpu::BufferUsages::COPY_DST,
-        });
+        let (device, queue) = request_device(&adapter).await?;
 
-        // TODO: Should the ui share an index buffer with sprites? If not, should we be using the same
-        // max size?
+        // Only the format and size matter when there's no surface to configure.
+        let surface_config = wgpu::SurfaceConfiguration {
+            usage: wgpu::TextureUsages::RENDER_ATTACHMENT,
+            format: wgpu::TextureFormat::Rgba8UnormSrgb,
+            width,
+            height,
+            alpha_mode: wgpu::CompositeAlphaMode::Auto,
+            present_mode: wgpu::PresentMode::Fifo,
+        };
 
-        let mut indices = Vec::<u32>::new();
+        Ok(Self::create(device, queue, None, surface_config).await)
+    }
 
-        for i in 0..MAX_ENTITIES {
-            let new_indices = sprite::Sprite::get_indices()
-                .into_iter()
-                .map(|idx| idx + (4 * i as u32));
+    async fn create(
+        device: wgpu::Device,
+        queue: wgpu::Queue,
+        surface: Option<wgpu::Surface>,
+        surface_config: wgpu::SurfaceConfiguration,
+    ) -> Self {
+        let sprite_quad_vertex_buffer =
+            device.create_buffer_init(&wgpu::util::BufferInitDescriptor {
+                label: Some("Sprite Quad Vertex Buffer"),
+                contents: bytemuck::cast_slice(&sprite::Sprite::get_vertices()),
+                usage: wgpu::BufferUsages::VERTEX,
+            });
 
-            indices.extend(new_indices);
-        }
+        let sprite_quad_index_buffer =
+            device.create_buffer_init(&wgpu::util::BufferInitDescriptor {
+                label: Some("Sprite Quad Index Buffer"),
+                contents: bytemuck::cast_slice(&sprite::Sprite::get_indices()),
+                usage: wgpu::BufferUsages::INDEX,
+            });
 
-        let ui_index_buffer = device.create_buffer_init(&wgpu::util::BufferInitDescriptor {
-            label: Some("UI Index Buffer"),
-            contents: bytemuck::cast_slice(indices.as_slice()),
-            usage: wgpu::BufferUsages::INDEX | wgpu::BufferUsages::COPY_DST,
-        });
+        let sprite_instance_buffer = InstanceBuffer::new(&device, "Sprite");
+        let ui_quads = QuadBuffer::new(&device, "UI");
+        let gizmo_vertex_buffer = InstanceBuffer::new(&device, "Gizmo Vertex");
 
         let texture_bind_group_layout =
             device.create_bind_group_layout(&wgpu::BindGroupLayoutDescriptor {
@@ -175,14 +244,48 @@ impl Graphics {
                 label: Some("camera_bind_group_layout"),
             });
 
-        let sprite_render_pipeline = create_sprite_render_pipeline(
+        let mut pipelines = PipelineCache::new(&device).await;
+
+        pipelines
+            .prepare(
+                &device,
+                &surface_config,
+                [&texture_bind_group_layout, &camera_bind_group_layout],
+                &PipelineKey::default(),
+            )
+            .unwrap();
+
+        let ui_render_pipeline = create_ui_render_pipeline(&device, &surface_config, &[]).await;
+        let clear_render_pipeline = create_clear_render_pipeline(&device, &surface_config).await;
+
+        let blit_render_pipeline =
+            create_blit_render_pipeline(&device, &surface_config, &[&texture_bind_group_layout])
+                .await;
+
+        let gizmo_render_pipeline =
+            create_gizmo_render_pipeline(&device, &surface_config, &[&camera_bind_group_layout])
+                .await;
+
+        let post_process = PostProcess::new(&device, &queue);
+
+        let texture_sampler = device.create_sampler(&wgpu::SamplerDescriptor {
+            address_mode_u: wgpu::AddressMode::ClampToEdge,
+            address_mode_v: wgpu::AddressMode::ClampToEdge,
+            address_mode_w: wgpu::AddressMode::ClampToEdge,
+            mag_filter: wgpu::FilterMode::Nearest,
+            min_filter: wgpu::FilterMode::Nearest,
+            mipmap_filter: wgpu::FilterMode::Nearest,
+            ..Default::default()
+        });
+
+        let light_pass = LightPass::new(
             &device,
+            &queue,
             &surface_config,
-            &[&texture_bind_group_layout, &camera_bind_group_layout],
+            &texture_sampler,
+            [&texture_bind_group_layout, &camera_bind_group_layout],
         )
-        .await;
-
-        let ui_render_pipeline = create_ui_render_pipeline(&device, &surface_config, &[]).await;
+        .unwrap();
 
         let clear_color = wgpu::Color {
             r: 0.0,
@@ -191,24 +294,6 @@ impl Graphics {
             a: 1.0,
         };
 
-        let mut camera_uniform = camera::CameraUniform::new();
-        camera_uniform.update_view_proj(&camera);
-
-        let camera_buffer = device.create_buffer_init(&wgpu::util::BufferInitDescriptor {
-            label: Some("Camera Buffer"),
-            contents: bytemuck::cast_slice(&[camera_uniform]),
-            usage: wgpu::BufferUsages::UNIFORM | wgpu::BufferUsages::COPY_DST,
-        });
-
-        let camera_bind_group = device.create_bind_group(&wgpu::BindGroupDescriptor {
-            layout: &camera_bind_group_layout,
-            entries: &[wgpu::BindGroupEntry {
-                binding: 0,
-                resource: camera_buffer.as_entire_binding(),
-            }],
-            label: Some("camera_bind_group"),
-        });
-
         let inconsolata = Resource::load_font("Inconsolata-Regular.ttf")
             .await
             .unwrap();
@@ -216,123 +301,307 @@ impl Graphics {
         let text_brush =
             GlyphBrushBuilder::using_font(inconsolata).build(&device, surface_config.format);
 
-        let texture_sampler = device.create_sampler(&wgpu::SamplerDescriptor {
-            address_mode_u: wgpu::AddressMode::ClampToEdge,
-            address_mode_v: wgpu::AddressMode::ClampToEdge,
-            address_mode_w: wgpu::AddressMode::ClampToEdge,
-            mag_filter: wgpu::FilterMode::Nearest,
-            min_filter: wgpu::FilterMode::Nearest,
-            mipmap_filter: wgpu::FilterMode::Nearest,
-            ..Default::default()
-        });
-
         Graphics {
-            camera_bind_group,
-            camera_buffer,
-            camera_uniform,
+            atlas_pages: RefCell::new(HashMap::new()),
+            blit_render_pipeline,
+            camera_bind_group_layout,
+            camera_bindings: Vec::new(),
             clear_color,
+            clear_render_pipeline,
             device,
+            failing_uploads: HashSet::new(),
+            gizmos: Gizmos::default(),
+            gizmo_render_pipeline,
+            gizmo_vertex_buffer,
+            light_pass,
+            pipelines,
+            pixel_perfect: None,
+            post_process,
             queue,
-            sprite_index_buffer,
-            sprite_render_pipeline,
-            sprite_vertex_buffer,
+            render_queue: RenderQueue::new(),
+            render_stats: RenderStats::default(),
+            sprite_instances: Vec::new(),
+            sprite_instance_buffer,
+            sprite_quad_index_buffer,
+            sprite_quad_vertex_buffer,
             staging_belt: wgpu::util::StagingBelt::new(1024),
             surface,
             surface_config,
             texture_bind_group_layout,
             texture_sampler,
             text_brush,
-            ui_index_buffer,
+            tile_chunks: TileChunks::default(),
+            ui_quads,
             ui_render_pipeline,
-            ui_vertex_buffer,
         }
     }
 
-    pub fn render(
-        &mut self,
-        entities: &Vec<Option<entity::Entity>>,
-        materials: &Vec<material::Material>,
-        ui_canvas: &mut Canvas,
-        config: &Config,
-        styles: &HashMap<String, Style>,
-    ) -> Result<(), wgpu::SurfaceError> {
-        let output = self.surface.get_current_texture()?;
+    pub fn render(&mut self, frame: Frame) -> Result<(), wgpu::SurfaceError> {
+        // Offscreen renderers have nothing to present to.
+        let output = match &self.surface {
+            Some(surface) => surface.get_current_texture()?,
+            None => return Ok(()),
+        };
 
         let view = output
             .texture
             .create_view(&wgpu::TextureViewDescriptor::default());
 
+        self.render_to_view(&view, frame);
+
+        output.present();
+
+        Ok(())
+    }
+
+    fn render_to_view(&mut self, view: &TextureView, frame: Frame) {
         let mut encoder = self
             .device
             .create_command_encoder(&wgpu::CommandEncoderDescriptor {
                 label: Some("Render Encoder"),
             });
 
+        let Frame {
+            cameras,
+            entities,
+            materials,
+            sorting_layers,
+            particles,
+            lighting,
+            ui_canvas,
+            config,
+            styles,
+        } = frame;
+
+        self.write_cameras(cameras);
+
+        self.render_queue.build(
+            cameras,
+            entities,
+            &self.sprite_instances,
+            &self.tile_chunks,
+            particles,
+            sorting_layers,
+        );
+
+        // Only a material's first frame can fail, its batches are skipped after that.
+        for material in materials {
+            let key = material.pipeline_key();
+
+            if let Err(e) = self.pipelines.prepare(
+                &self.device,
+                &self.surface_config,
+                [
+                    &self.texture_bind_group_layout,
+                    &self.camera_bind_group_layout,
+                ],
+                &key,
+            ) {
+                log::error!("Error preparing material {}: {:?}", material.name, e);
+            }
+        }
+
+        let written = self.sprite_instance_buffer.write(
+            &self.device,
+            &self.queue,
+            self.render_queue.instances(),
+        );
+
+        if !self.check_upload("sprite instances", written) {
+            self.render_queue.clear();
+        }
+
+        let target_width = self.surface_config.width as f32;
+        let target_height = self.surface_config.height as f32;
+
+        // In pixel perfect mode the world is drawn at the reference resolution and upscaled below.
+        let (world_width, world_height) = match &self.pixel_perfect {
+            Some(target) => (target.target.width, target.target.height),
+            None => (self.surface_config.width, self.surface_config.height),
+        };
+
+        // Post effects need the world in a texture, pixel perfect mode already draws it into one.
+        let post_processing = self.post_process.is_active();
+
+        if post_processing {
+            if !self.post_process.targets_fit(world_width, world_height) {
+                let targets = [
+                    self.create_render_target("Post Effect A", world_width, world_height),
+                    self.create_render_target("Post Effect B", world_width, world_height),
+                ];
+
+                self.post_process.set_targets(targets);
+            }
+
+            self.post_process
+                .write_uniforms(&self.queue, world_width, world_height);
+        }
+
+        if lighting.is_enabled() {
+            if !self.light_pass.targets_fit(world_width, world_height) {
+                let light_map = self.create_render_target("Light Map", world_width, world_height);
+                self.light_pass.set_targets(&self.device, light_map);
+            }
+
+            let written = self.light_pass.write(&self.device, &self.queue, lighting);
+            self.check_upload("lights", written);
+        }
+
+        // Gizmos that didn't fit are dropped, so only a step's first frame can fail.
+        if !self.gizmos.vertices().is_empty() {
+            let written =
+                self.gizmo_vertex_buffer
+                    .write(&self.device, &self.queue, self.gizmos.vertices());
+
+            if !self.check_upload("gizmos", written) {
+                self.gizmos.clear();
+            }
+        }
+
+        let world_target = match &self.pixel_perfect {
+            Some(target) => Some((&target.target, None)),
+            None if post_processing => self.post_process.target(0).map(|target| (target, Some(0))),
+            None => None,
+        };
+
+        let world_view = world_target.map_or(view, |(target, _)| &target.view);
+
+        let stats = {
+            let mut render_pass = encoder.begin_render_pass(&wgpu::RenderPassDescriptor {
+                label: Some("World Render Pass"),
+                color_attachments: &[Some(wgpu::RenderPassColorAttachment {
+                    view: world_view,
+                    resolve_target: None,
+                    ops: wgpu::Operations {
+                        load: wgpu::LoadOp::Clear(self.clear_color),
+                        store: true,
+                    },
+                })],
+                depth_stencil_attachment: None,
+            });
+
+            self.draw_world(
+                &mut render_pass,
+                cameras,
+                materials,
+                (world_width as f32, world_height as f32),
+            )
+        };
+
+        self.render_stats = stats;
+
+        if let Some(ambient) = lighting.ambient() {
+            self.draw_lighting(&mut encoder, world_view, cameras, materials, ambient);
+        }
+
+        // Where the world ends up in the view, the letterbox in pixel perfect mode.
+        let world_area = match &self.pixel_perfect {
+            Some(target) => target
+                .settings
+                .letterbox(self.surface_config.width, self.surface_config.height),
+            None => Letterbox {
+                x: 0.0,
+                y: 0.0,
+                width: target_width,
+                height: target_height,
+            },
+        };
+
+        let composite = world_target.map(|(target, index)| {
+            self.post_process
+                .apply(&mut encoder, &target.bind_group, index)
+        });
+
+        if let Some(bind_group) = composite {
+            let mut render_pass = encoder.begin_render_pass(&wgpu::RenderPassDescriptor {
+                label: Some("Composite Render Pass"),
+                color_attachments: &[Some(wgpu::RenderPassColorAttachment {
+                    view,
+                    resolve_target: None,
+                    ops: wgpu::Operations {
+                        load: wgpu::LoadOp::Clear(LETTERBOX_COLOR),
+                        store: true,
+                    },
+                })],
+                depth_stencil_attachment: None,
+            });
+
+            render_pass.set_viewport(
+                world_area.x,
+                world_area.y,
+                world_area.width,
+                world_area.height,
+                0.0,
+                1.0,
+            );
+
+            render_pass.set_pipeline(&self.blit_render_pipeline);
+            render_pass.set_bind_group(0, bind_group, &[]);
+            render_pass.draw(0..3, 0..1);
+        }
+
+        // Gizmos go over the finished world, post effects would tint and distort them.
+        self.draw_gizmos(&mut encoder, view, cameras, world_area);
+
         let mut render_pass = encoder.begin_render_pass(&wgpu::RenderPassDescriptor {
-            label: Some("Render Pass"),
+            label: Some("UI Render Pass"),
             color_attachments: &[Some(wgpu::RenderPassColorAttachment {
-                view: &view,
+                view,
                 resolve_target: None,
                 ops: wgpu::Operations {
-                    load: wgpu::LoadOp::Clear(self.clear_color),
+                    load: wgpu::LoadOp::Load,
                     store: true,
                 },
             })],
             depth_stencil_attachment: None,
         });
 
-        render_pass.set_pipeline(&self.sprite_render_pipeline);
-        render_pass.set_bind_group(1, &self.camera_bind_group, &[]);
-
-        render_pass.set_vertex_buffer(0, self.sprite_vertex_buffer.slice(..));
+        render_pass.set_pipeline(&self.ui_render_pipeline);
+        render_pass.set_vertex_buffer(0, self.ui_quads.vertex_buffer().slice(..));
         render_pass.set_index_buffer(
-            self.sprite_index_buffer.slice(..),
+            self.ui_quads.index_buffer().slice(..),
             wgpu::IndexFormat::Uint32,
         );
 
-        let grouped = group_by_sorting_layer(entities);
-
-        if let Some(background) = grouped.get(&sorting_layer::SortingLayer::Background) {
-            for (entity_id, sprite_mat_id) in background {
-                let material = &materials[*sprite_mat_id];
-                render_pass.draw_sprite(&material, *entity_id);
-            }
-        }
-
-        if let Some(foreground) = grouped.get(&sorting_layer::SortingLayer::Foreground) {
-            for (entity_id, sprite_mat_id) in foreground {
-                let material = &materials[*sprite_mat_id];
-                render_pass.draw_sprite(&material, *entity_id);
-            }
-        }
+        let focused = ui_canvas.focused();
+        let element = ui_canvas.root();
 
-        render_pass.set_pipeline(&self.ui_render_pipeline);
-        render_pass.set_vertex_buffer(0, self.ui_vertex_buffer.slice(..));
-        render_pass.set_index_buffer(self.ui_index_buffer.slice(..), wgpu::IndexFormat::Uint32);
+        let bounds = cgmath::Vector2 {
+            x: self.surface_config.width as f32,
+            y: self.surface_config.height as f32,
+        };
 
+        // Outside developer mode only text fields are drawn, so players can still type in them.
         if config.developer_mode() {
-            let element = ui_canvas.root();
-
             render_pass.draw_element(
                 element,
                 &mut self.text_brush,
-                cgmath::Vector2 {
-                    x: self.surface_config.width as f32,
-                    y: self.surface_config.height as f32,
-                },
+                bounds,
+                cgmath::Vector2::zero(),
+                styles,
+                focused,
+            );
+        } else {
+            render_pass.draw_text_fields(
+                element,
+                &mut self.text_brush,
+                bounds,
                 cgmath::Vector2::zero(),
                 styles,
+                focused,
             );
         }
 
         drop(render_pass);
 
+        self.queue_gizmo_labels(cameras, world_area);
+
         self.text_brush
             .draw_queued(
                 &self.device,
                 &mut self.staging_belt,
                 &mut encoder,
-                &view,
+                view,
                 self.surface_config.width,
                 self.surface_config.height,
             )
@@ -341,47 +610,296 @@ impl Graphics {
         self.staging_belt.finish();
         self.queue.submit([encoder.finish()]);
 
-        output.present();
-
         self.staging_belt.recall();
+    }
 
-        Ok(())
+    /// Draws every camera's queued sprites into its viewport of a target of the given size.
+    fn draw_world<'a>(
+        &'a self,
+        render_pass: &mut wgpu::RenderPass<'a>,
+        cameras: &[camera::Camera],
+        materials: &'a [material::Material],
+        (target_width, target_height): (f32, f32),
+    ) -> RenderStats {
+        let mut stats = RenderStats::default();
+
+        for (index, (camera, binding)) in cameras.iter().zip(&self.camera_bindings).enumerate() {
+            let viewport = camera.viewport();
+
+            render_pass.set_viewport(
+                viewport.x * target_width,
+                viewport.y * target_height,
+                viewport.width * target_width,
+                viewport.height * target_height,
+                0.0,
+                1.0,
+            );
+
+            if let camera::CameraClear::Color(_) = camera.clear {
+                render_pass.set_pipeline(&self.clear_render_pipeline);
+                render_pass.set_vertex_buffer(0, binding.clear_vertex_buffer.slice(..));
+                render_pass.set_index_buffer(
+                    self.ui_quads.index_buffer().slice(..),
+                    wgpu::IndexFormat::Uint32,
+                );
+                render_pass.draw_indexed(0..6, 0, 0..1);
+                stats.draw_calls += 1;
+            }
+
+            let mut bound_key = None;
+            let mut bound_params = None;
+
+            render_pass.set_bind_group(1, &binding.bind_group, &[]);
+
+            render_pass.set_vertex_buffer(0, self.sprite_quad_vertex_buffer.slice(..));
+            render_pass.set_vertex_buffer(1, self.sprite_instance_buffer.buffer().slice(..));
+            render_pass.set_index_buffer(
+                self.sprite_quad_index_buffer.slice(..),
+                wgpu::IndexFormat::Uint32,
+            );
+
+            // Materials packed into the same atlas page share a bind group.
+            let mut bound_page: Option<&Rc<TexturePage>> = None;
+            let mut bound_chunk = None;
+
+            for batch in self.render_queue.batches(index) {
+                if batch.chunk != bound_chunk {
+                    let instances = match batch.chunk.and_then(|i| self.tile_chunks.get(i)) {
+                        Some(chunk) => &chunk.buffer,
+                        None => self.sprite_instance_buffer.buffer(),
+                    };
+
+                    render_pass.set_vertex_buffer(1, instances.slice(..));
+                    bound_chunk = batch.chunk;
+                }
+
+                let material = &materials[batch.material];
+                let key = material.pipeline_key();
+
+                // Materials whose pipeline failed to build are skipped.
+                let Some(pipeline) = self.pipelines.get(&key) else {
+                    continue;
+                };
+
+                if bound_key.as_ref() != Some(&key) {
+                    render_pass.set_pipeline(pipeline);
+                    bound_key = Some(key);
+                    bound_params = None;
+                }
+
+                if let Some(shader) = &material.shader {
+                    if bound_params != Some(batch.material) {
+                        render_pass.set_bind_group(2, shader.bind_group(), &[]);
+                        bound_params = Some(batch.material);
+                        stats.binds += 1;
+                    }
+                }
+
+                let page = &material.diffuse_texture.page;
+
+                if !bound_page.is_some_and(|bound| Rc::ptr_eq(bound, page)) {
+                    render_pass.set_bind_group(0, &page.bind_group, &[]);
+                    bound_page = Some(page);
+                    stats.binds += 1;
+                }
+
+                render_pass.draw_sprites(batch);
+                stats.draw_calls += 1;
+                stats.sprites += batch.instances.len();
+            }
+        }
+
+        stats
     }
 
     pub fn resize(&mut self, new_size: winit::dpi::PhysicalSize<u32>) {
         self.surface_config.width = new_size.width;
         self.surface_config.height = new_size.height;
-        self.surface.configure(&self.device, &self.surface_config);
+
+        if let Some(surface) = &self.surface {
+            surface.configure(&self.device, &self.surface_config);
+        }
     }
 
-    pub fn write_camera(&mut self, camera: &camera::Camera) {
-        self.camera_uniform.update_view_proj(camera);
+    pub fn gizmos(&self) -> &Gizmos {
+        &self.gizmos
+    }
 
-        self.queue.write_buffer(
-            &self.camera_buffer,
-            0,
-            bytemuck::cast_slice(&[self.camera_uniform]),
-        );
+    /// Debug shapes and labels for the frames after this step, see `Gizmos`.
+    pub fn gizmos_mut(&mut self) -> &mut Gizmos {
+        &mut self.gizmos
+    }
+
+    /// Counts from the last rendered frame's world pass.
+    pub fn render_stats(&self) -> RenderStats {
+        self.render_stats
     }
 
-    pub fn write_entity(&mut self, id: usize, verts: Vec<vertex::RenderVertex>) {
-        let offset = std::mem::size_of::<vertex::RenderVertex>() * 4 * id;
+    /**
+     * Returns whether a per-frame upload succeeded, logging its error when it starts failing. The
+     * same data usually fails again the next frame, so it isn't logged again until it's recovered.
+     */
+    fn check_upload(&mut self, what: &'static str, result: anyhow::Result<()>) -> bool {
+        match result {
+            Ok(()) => {
+                self.failing_uploads.remove(what);
+                true
+            }
+            Err(e) => {
+                if self.failing_uploads.insert(what) {
+                    log::error!("Error writing {}: {:?}", what, e);
+                }
 
-        self.queue.write_buffer(
-            &self.sprite_vertex_buffer,
-            offset as wgpu::BufferAddress,
-            bytemuck::cast_slice(verts.as_slice()),
-        );
+                false
+            }
+        }
     }
 
-    pub fn write_ui_element(&mut self, id: usize, verts: Vec<UiRenderVertex>) {
-        let offset = std::mem::size_of::<UiRenderVertex>() * 4 * id;
+    pub fn pixel_perfect(&self) -> Option<PixelPerfect> {
+        self.pixel_perfect.as_ref().map(|target| target.settings)
+    }
 
-        self.queue.write_buffer(
-            &self.ui_vertex_buffer,
-            offset as wgpu::BufferAddress,
-            bytemuck::cast_slice(verts.as_slice()),
-        );
+    /// Switches pixel perfect rendering on or off, see `PixelPerfect`.
+    pub fn set_pixel_perfect(&mut self, settings: Option<PixelPerfect>) {
+        self.pixel_perfect = settings.map(|settings| PixelPerfectTarget {
+            settings,
+            target: self.create_render_target(
+                "Pixel Perfect",
+                settings.reference_width,
+                settings.reference_height,
+            ),
+        });
+    }
+
+    /// Adds an effect to the end of the post processing chain, enabled, and returns its index.
+    pub fn add_post_effect(&mut self, effect: PostEffect) -> anyhow::Result<usize> {
+        let pass = self.create_post_pass(effect)?;
+        self.post_process.passes_mut().push(pass);
+        Ok(self.post_process.passes().len() - 1)
+    }
+
+    /// Replaces an effect, keeping its place in the chain and whether it's enabled.
+    pub fn set_post_effect(&mut self, index: usize, effect: PostEffect) -> anyhow::Result<()> {
+        let Some(enabled) = self.post_effect_enabled(index) else {
+            anyhow::bail!("There's no post effect {index}.");
+        };
+
+        let mut pass = self.create_post_pass(effect)?;
+        pass.enabled = enabled;
+        self.post_process.passes_mut()[index] = pass;
+
+        Ok(())
+    }
+
+    pub fn set_post_effect_enabled(&mut self, index: usize, enabled: bool) -> anyhow::Result<()> {
+        let Some(pass) = self.post_process.passes_mut().get_mut(index) else {
+            anyhow::bail!("There's no post effect {index}.");
+        };
+
+        pass.enabled = enabled;
+        Ok(())
+    }
+
+    pub fn post_effect(&self, index: usize) -> Option<&PostEffect> {
+        self.post_process
+            .passes()
+            .get(index)
+            .map(|pass| pass.effect())
+    }
+
+    pub fn post_effect_enabled(&self, index: usize) -> Option<bool> {
+        self.post_process
+            .passes()
+            .get(index)
+            .map(|pass| pass.enabled)
+    }
+
+    fn create_post_pass(&mut self, effect: PostEffect) -> anyhow::Result<post_process::PostPass> {
+        let lut = match &effect {
+            PostEffect::ColorGrading { lut, .. } => Some(
+                Resource::read_texture_page(self, lut)
+                    .with_context(|| format!("Couldn't read color grading LUT {lut}"))?,
+            ),
+            _ => None,
+        };
+
+        self.post_process.create_pass(
+            &self.device,
+            &self.surface_config,
+            &self.texture_bind_group_layout,
+            effect,
+            lut,
+        )
+    }
+
+    /// A texture of the surface's format that can be drawn into and then sampled.
+    pub fn create_render_target(&self, label: &str, width: u32, height: u32) -> RenderTarget {
+        let texture = self.device.create_texture(&wgpu::TextureDescriptor {
+            label: Some(&format!("{label} Texture")),
+            size: wgpu::Extent3d {
+                width,
+                height,
+                depth_or_array_layers: 1,
+            },
+            mip_level_count: 1,
+            sample_count: 1,
+            dimension: wgpu::TextureDimension::D2,
+            format: self.surface_config.format,
+            usage: wgpu::TextureUsages::RENDER_ATTACHMENT | wgpu::TextureUsages::TEXTURE_BINDING,
+        });
+
+        let view = texture.create_view(&wgpu::TextureViewDescriptor::default());
+        let bind_group = self.create_texture_bind_group(label, &view);
+
+        RenderTarget {
+            texture,
+            view,
+            bind_group,
+            width,
+            height,
+        }
+    }
+
+    /// Uploads every camera's uniform for the frame being drawn, with a binding for each camera.
+    fn write_cameras(&mut self, cameras: &[camera::Camera]) {
+        while self.camera_bindings.len() < cameras.len() {
+            let binding = CameraBinding::new(&self.device, &self.camera_bind_group_layout);
+            self.camera_bindings.push(binding);
+        }
+
+        self.camera_bindings.truncate(cameras.len());
+
+        for (camera, binding) in cameras.iter().zip(&mut self.camera_bindings) {
+            binding.write(&self.queue, camera);
+        }
+    }
+
+    /// Stores the instance an entity is drawn with. Fails if there could never be room to draw it.
+    pub fn write_entity(&mut self, id: usize, instance: SpriteInstance) -> anyhow::Result<()> {
+        let max = self.sprite_instance_buffer.max_capacity();
+
+        if id >= max {
+            anyhow::bail!("Sprite instances are full, entity {id} is past the limit of {max}.");
+        }
+
+        if id >= self.sprite_instances.len() {
+            self.sprite_instances
+                .resize(id + 1, bytemuck::Zeroable::zeroed());
+        }
+
+        self.sprite_instances[id] = instance;
+
+        Ok(())
+    }
+
+    /// Fails if the UI buffer can't grow to fit the slot.
+    pub fn write_ui_element(
+        &mut self,
+        id: usize,
+        verts: Vec<UiRenderVertex>,
+    ) -> anyhow::Result<()> {
+        self.ui_quads
+            .write(&self.device, &self.queue, id, verts.as_slice())
     }
 
     pub fn create_texture(
@@ -389,6 +907,16 @@ impl Graphics {
         label: &str,
         size: wgpu::Extent3d,
         image: ImageBuffer<Rgba<u8>, Vec<u8>>,
+    ) -> (wgpu::Texture, TextureView) {
+        self.create_texture_with_format(label, size, image, wgpu::TextureFormat::Rgba8UnormSrgb)
+    }
+
+    pub fn create_texture_with_format(
+        &self,
+        label: &str,
+        size: wgpu::Extent3d,
+        image: ImageBuffer<Rgba<u8>, Vec<u8>>,
+        format: wgpu::TextureFormat,
     ) -> (wgpu::Texture, TextureView) {
         let texture = self.device.create_texture(&wgpu::TextureDescriptor {
             label: Some(label),
@@ -396,7 +924,7 @@ impl Graphics {
             mip_level_count: 1,
             sample_count: 1,
             dimension: wgpu::TextureDimension::D2,
-            format: wgpu::TextureFormat::Rgba8UnormSrgb,
+            format,
             usage: wgpu::TextureUsages::TEXTURE_BINDING | wgpu::TextureUsages::COPY_DST,
         });
 
@@ -421,17 +949,13 @@ impl Graphics {
         (texture, view)
     }
 
-    pub fn create_texture_bind_group(
-        &self,
-        name: &str,
-        diffuse_texture: &Texture,
-    ) -> wgpu::BindGroup {
+    pub fn create_texture_bind_group(&self, name: &str, view: &TextureView) -> wgpu::BindGroup {
         self.device.create_bind_group(&wgpu::BindGroupDescriptor {
             layout: &self.texture_bind_group_layout,
             entries: &[
                 wgpu::BindGroupEntry {
                     binding: 0,
-                    resource: wgpu::BindingResource::TextureView(&diffuse_texture.view),
+                    resource: wgpu::BindingResource::TextureView(view),
                 },
                 wgpu::BindGroupEntry {
                     binding: 1,
@@ -443,26 +967,17 @@ impl Graphics {
     }
 }
 
-fn group_by_sorting_layer(
-    entities: &Vec<Option<entity::Entity>>,
-) -> HashMap<sorting_layer::SortingLayer, Vec<(usize, usize)>> {
-    entities
-        .into_iter()
-        .fold(HashMap::new(), |mut acc, entity| {
-            if let Some(entity) = entity {
-                let layer = entity.get_sorting_layer();
-                let value = (entity.get_id(), entity.sprite_mat);
-
-                match acc.get_mut(&layer) {
-                    Some(layer_vec) => layer_vec.push(value),
-                    None => {
-                        let mut layer_vec = Vec::new();
-                        layer_vec.push(value);
-                        acc.insert(layer, layer_vec);
-                    }
-                }
-            }
-
-            acc
-        })
+async fn request_device(
+    adapter: &wgpu::Adapter,
+) -> Result<(wgpu::Device, wgpu::Queue), wgpu::RequestDeviceError> {
+    adapter
+        .request_device(
+            &wgpu::DeviceDescriptor {
+                features: wgpu::Features::empty(),
+                limits: wgpu::Limits::default(),
+                label: None,
+            },
+            None,
+        )
+        .await
 }
@@ -0,0 +1,85 @@
+use anyhow::*;
+use image::GenericImageView;
+use wgpu::util::DeviceExt;
+
+use super::{sprite_sheet::UvRect, Graphics};
+
+/**
+ * A normal map for a material, lighting its sprites as if they had depth. It's the size of the
+ * material's texture and laid out the same, with red pointing right, green up and blue out of
+ * the screen. Sprites without one are lit as if they were flat.
+ */
+pub struct NormalMap {
+    pub texture: wgpu::Texture,
+    pub view: wgpu::TextureView,
+    pub bind_group: wgpu::BindGroup,
+    pub width: u32,
+    pub height: u32,
+    // Kept alive for the bind group.
+    _region: wgpu::Buffer,
+}
+
+impl NormalMap {
+    /// `region` is where the material's texture lies on its page, so frames find their normals.
+    pub fn from_image(
+        graphics: &Graphics,
+        img: &image::DynamicImage,
+        label: &str,
+        region: UvRect,
+    ) -> Result<Self> {
+        let buffer = img.to_rgba8();
+        let (width, height) = img.dimensions();
+
+        let size = wgpu::Extent3d {
+            width,
+            height,
+            depth_or_array_layers: 1,
+        };
+
+        // Normals aren't colors, so they're read back as they were stored.
+        let (texture, view) = graphics.create_texture_with_format(
+            label,
+            size,
+            buffer,
+            wgpu::TextureFormat::Rgba8Unorm,
+        );
+
+        let region = graphics
+            .device
+            .create_buffer_init(&wgpu::util::BufferInitDescriptor {
+                label: Some(&format!("{label} Region Buffer")),
+                contents: bytemuck::cast_slice(&[region.x, region.y, region.width, region.height]),
+                usage: wgpu::BufferUsages::UNIFORM,
+            });
+
+        let bind_group = graphics
+            .device
+            .create_bind_group(&wgpu::BindGroupDescriptor {
+                layout: graphics.light_pass.normal_map_layout(),
+                entries: &[
+                    wgpu::BindGroupEntry {
+                        binding: 0,
+                        resource: wgpu::BindingResource::TextureView(&view),
+                    },
+                    wgpu::BindGroupEntry {
+                        binding: 1,
+                        resource: wgpu::BindingResource::Sampler(&graphics.texture_sampler),
+                    },
+                    wgpu::BindGroupEntry {
+                        binding: 2,
+                        resource: region.as_entire_binding(),
+                    },
+                ],
+                label: Some(&format!("{label} Normal Map Bind Group")),
+            });
+
+        Ok(Self {
+            texture,
+            view,
+            bind_group,
+            width,
+            height,
+            _region: region,
+        })
+    }
+}
@@ -0,0 +1,95 @@
+use std::ops::Range;
+
+use super::{sorting_layer::SortingLayer, vertex::SpriteInstance};
+
+/// Live particles allowed across every emitter unless changed with `Particles::set_budget`.
+pub const DEFAULT_PARTICLE_BUDGET: usize = 10_000;
+
+/// One emitter's particles this frame, drawn like a sprite with one instance per particle.
+#[derive(Clone, Debug, PartialEq)]
+pub struct ParticleBatch {
+    pub material: usize,
+    pub sorting_layer: SortingLayer,
+    pub order_in_layer: i32,
+    pub render_layers: u32,
+    /// Where the emitter is, for Y-sorted layers.
+    pub y: f32,
+    /// This batch's slice of `Particles::instances`.
+    pub instances: Range<usize>,
+}
+
+/**
+ * The particles emitters submit each step, gathered for the renderer, and the budget they share.
+ * Emitters claim room for their particles with `claim` before simulating them, so the budget is
+ * handed out in update order.
+ */
+pub struct Particles {
+    instances: Vec<SpriteInstance>,
+    batches: Vec<ParticleBatch>,
+    budget: usize,
+    claimed: usize,
+}
+
+impl Default for Particles {
+    fn default() -> Self {
+        Self {
+            instances: Vec::new(),
+            batches: Vec::new(),
+            budget: DEFAULT_PARTICLE_BUDGET,
+            claimed: 0,
+        }
+    }
+}
+
+impl Particles {
+    pub fn budget(&self) -> usize {
+        self.budget
+    }
+
+    /// Takes effect from the next step, emitters over it drop their oldest particles.
+    pub fn set_budget(&mut self, budget: usize) {
+        self.budget = budget;
+    }
+
+    /// Particles claimed so far this step.
+    pub fn claimed(&self) -> usize {
+        self.claimed
+    }
+
+    /// Claims room for up to `count` particles this step and returns how many fit the budget.
+    pub fn claim(&mut self, count: usize) -> usize {
+        let granted = count.min(self.budget.saturating_sub(self.claimed));
+        self.claimed += granted;
+        granted
+    }
+
+    /// Adds a batch of instances, `instances` in the batch is filled in here.
+    pub fn submit(
+        &mut self,
+        mut batch: ParticleBatch,
+        instances: impl IntoIterator<Item = SpriteInstance>,
+    ) {
+        let start = self.instances.len();
+        self.instances.extend(instances);
+        batch.instances = start..self.instances.len();
+
+        if !batch.instances.is_empty() {
+            self.batches.push(batch);
+        }
+    }
+
+    /// Empties the lists and returns the budget, at the start of every step.
+    pub fn clear(&mut self) {
+        self.instances.clear();
+        self.batches.clear();
+        self.claimed = 0;
+    }
+
+    pub fn instances(&self) -> &[SpriteInstance] {
+        &self.instances
+    }
+
+    pub fn batches(&self) -> &[ParticleBatch] {
+        &self.batches
+    }
+}
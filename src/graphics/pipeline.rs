@@ -1,29 +1,324 @@
+use std::{cell::RefCell, collections::HashMap, path::Path, rc::Rc};
+
+use anyhow::Context;
+use serde::Deserialize;
+
 use crate::{resources::Resource, ui::ui_vertex};
 
-use super::vertex;
+use super::{gizmos::GizmoVertex, vertex};
+
+/// How a pipeline's output is combined with what's already drawn.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Hash, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum BlendMode {
+    /// Straight alpha, the default for sprites and UI.
+    #[default]
+    Alpha,
+    /// Adds the color weighted by its alpha, for glows and sparks.
+    Additive,
+    /**
+     * Multiplies what's underneath by the color, faded toward white by transparency, for shadows.
+     * Shaders drawn with it need an `fs_multiply` entry point that returns that faded color, as
+     * `sprite.wgsl`'s does.
+     */
+    Multiply,
+    /// Alpha blending for textures whose color is already multiplied by their alpha.
+    Premultiplied,
+}
+
+impl BlendMode {
+    fn state(self) -> wgpu::BlendState {
+        match self {
+            BlendMode::Alpha => wgpu::BlendState::ALPHA_BLENDING,
+            BlendMode::Additive => wgpu::BlendState {
+                color: wgpu::BlendComponent {
+                    src_factor: wgpu::BlendFactor::SrcAlpha,
+                    dst_factor: wgpu::BlendFactor::One,
+                    operation: wgpu::BlendOperation::Add,
+                },
+                alpha: wgpu::BlendComponent::OVER,
+            },
+            BlendMode::Multiply => wgpu::BlendState {
+                color: wgpu::BlendComponent {
+                    src_factor: wgpu::BlendFactor::Dst,
+                    dst_factor: wgpu::BlendFactor::Zero,
+                    operation: wgpu::BlendOperation::Add,
+                },
+                alpha: wgpu::BlendComponent::OVER,
+            },
+            BlendMode::Premultiplied => wgpu::BlendState::PREMULTIPLIED_ALPHA_BLENDING,
+        }
+    }
+
+    // Blending can't weigh the source color by its alpha before multiplying, so the shader does.
+    fn fragment_entry_point(self) -> &'static str {
+        match self {
+            BlendMode::Multiply => "fs_multiply",
+            _ => "fs_main",
+        }
+    }
+}
+
+/// Identifies one sprite pipeline in a `PipelineCache`.
+#[derive(Clone, Debug, Default, PartialEq, Eq, Hash)]
+pub struct PipelineKey {
+    /// A custom shader under `res/shaders`, or `None` for `sprite.wgsl`.
+    pub shader: Option<Rc<str>>,
+    pub blend: BlendMode,
+}
+
+/// A custom sprite shader and the layout of the material parameters it reads at group 2.
+pub struct CustomShader {
+    module: wgpu::ShaderModule,
+    pub params_layout: wgpu::BindGroupLayout,
+    /// Extra textures it samples, after the uniform and sampler bindings.
+    pub textures: usize,
+}
+
+/**
+ * Sprite pipelines, created the first time a material needs one, so each blend mode a shader is
+ * drawn with gets its own variant on demand. Custom shaders are compiled once when the first
+ * material using them is created and shared by every pipeline drawing with them.
+ */
+pub struct PipelineCache {
+    sprite_shader: wgpu::ShaderModule,
+    shaders: RefCell<HashMap<Rc<str>, Rc<CustomShader>>>,
+    /// `None` for keys whose pipeline failed to build, so they aren't rebuilt every frame.
+    pipelines: HashMap<PipelineKey, Option<wgpu::RenderPipeline>>,
+}
+
+impl PipelineCache {
+    pub async fn new(device: &wgpu::Device) -> Self {
+        let sprite_shader = Resource::load_string("sprite.wgsl").await.unwrap();
+        let sprite_shader = device.create_shader_module(wgpu::ShaderModuleDescriptor {
+            label: Some("Sprite Shader"),
+            source: wgpu::ShaderSource::Wgsl(sprite_shader.into()),
+        });
+
+        Self {
+            sprite_shader,
+            shaders: RefCell::new(HashMap::new()),
+            pipelines: HashMap::new(),
+        }
+    }
+
+    /**
+     * Compiles a shader under `res/shaders`, or returns it if it already was. Every material using
+     * a shader has to declare the same number of texture parameters, since they share pipelines.
+     */
+    pub fn load_shader(
+        &self,
+        device: &wgpu::Device,
+        name: &str,
+        textures: usize,
+    ) -> anyhow::Result<Rc<CustomShader>> {
+        if let Some(shader) = self.shaders.borrow().get(name) {
+            if shader.textures != textures {
+                anyhow::bail!(
+                    "Shader {name} was loaded with {} texture parameters, not {textures}.",
+                    shader.textures
+                );
+            }
+
+            return Ok(shader.clone());
+        }
+
+        let path = Resource::build_path(Some("shaders"), name);
+        let module = compile_shader(device, name, &path)?;
+
+        let shader = Rc::new(CustomShader {
+            module,
+            params_layout: create_params_layout(device, name, textures),
+            textures,
+        });
+
+        self.shaders
+            .borrow_mut()
+            .insert(Rc::from(name), shader.clone());
+
+        Ok(shader)
+    }
+
+    /**
+     * Creates the pipeline for a key the first time it's prepared. `layouts` are the texture and
+     * camera bind group layouts every sprite pipeline starts with. Only that first attempt returns
+     * an error, a pipeline that failed isn't tried again and `get` never returns it.
+     */
+    pub fn prepare(
+        &mut self,
+        device: &wgpu::Device,
+        config: &wgpu::SurfaceConfiguration,
+        layouts: [&wgpu::BindGroupLayout; 2],
+        key: &PipelineKey,
+    ) -> anyhow::Result<()> {
+        if self.pipelines.contains_key(key) {
+            return Ok(());
+        }
+
+        let (pipeline, result) = match self.create(device, config, layouts, key) {
+            Ok(pipeline) => (Some(pipeline), Ok(())),
+            Err(e) => (None, Err(e)),
+        };
+
+        self.pipelines.insert(key.clone(), pipeline);
+        result
+    }
+
+    fn create(
+        &self,
+        device: &wgpu::Device,
+        config: &wgpu::SurfaceConfiguration,
+        layouts: [&wgpu::BindGroupLayout; 2],
+        key: &PipelineKey,
+    ) -> anyhow::Result<wgpu::RenderPipeline> {
+        let custom = match &key.shader {
+            Some(name) => Some(
+                self.shaders
+                    .borrow()
+                    .get(name)
+                    .cloned()
+                    .with_context(|| format!("Shader {name} was never loaded"))?,
+            ),
+            None => None,
+        };
+
+        let (module, label) = match (&custom, &key.shader) {
+            (Some(shader), Some(name)) => (
+                &shader.module,
+                format!("{name} {:?} Render Pipeline", key.blend),
+            ),
+            _ => (
+                &self.sprite_shader,
+                format!("Sprite {:?} Render Pipeline", key.blend),
+            ),
+        };
+
+        let mut bind_group_layouts = layouts.to_vec();
+
+        if let Some(shader) = &custom {
+            bind_group_layouts.push(&shader.params_layout);
+        }
+
+        let layout = device.create_pipeline_layout(&wgpu::PipelineLayoutDescriptor {
+            label: Some(&format!("{label} Layout")),
+            bind_group_layouts: &bind_group_layouts,
+            push_constant_ranges: &[],
+        });
+
+        device.push_error_scope(wgpu::ErrorFilter::Validation);
+
+        let pipeline = new(
+            module,
+            layout,
+            device,
+            config,
+            &label,
+            &[vertex::Vertex::desc(), vertex::SpriteInstance::desc()],
+            Some(key.blend),
+        );
+
+        if let Some(error) = pollster::block_on(device.pop_error_scope()) {
+            anyhow::bail!("Couldn't create the {label}: {error}");
+        }
+
+        Ok(pipeline)
+    }
+
+    /// Only pipelines that were prepared successfully.
+    pub fn get(&self, key: &PipelineKey) -> Option<&wgpu::RenderPipeline> {
+        self.pipelines.get(key)?.as_ref()
+    }
+}
+
+/// Reads and compiles a WGSL file, failing on invalid shaders rather than panicking.
+pub fn compile_shader(
+    device: &wgpu::Device,
+    label: &str,
+    path: &Path,
+) -> anyhow::Result<wgpu::ShaderModule> {
+    let source =
+        std::fs::read_to_string(path).with_context(|| format!("Couldn't read shader {label}"))?;
+
+    device.push_error_scope(wgpu::ErrorFilter::Validation);
+
+    let module = device.create_shader_module(wgpu::ShaderModuleDescriptor {
+        label: Some(label),
+        source: wgpu::ShaderSource::Wgsl(source.into()),
+    });
+
+    if let Some(error) = pollster::block_on(device.pop_error_scope()) {
+        anyhow::bail!("Invalid shader {label}: {error}");
+    }
+
+    Ok(module)
+}
+
+// A uniform buffer, the sampler and then each texture.
+fn create_params_layout(
+    device: &wgpu::Device,
+    name: &str,
+    textures: usize,
+) -> wgpu::BindGroupLayout {
+    let mut entries = vec![
+        wgpu::BindGroupLayoutEntry {
+            binding: 0,
+            visibility: wgpu::ShaderStages::VERTEX | wgpu::ShaderStages::FRAGMENT,
+            ty: wgpu::BindingType::Buffer {
+                ty: wgpu::BufferBindingType::Uniform,
+                has_dynamic_offset: false,
+                min_binding_size: None,
+            },
+            count: None,
+        },
+        wgpu::BindGroupLayoutEntry {
+            binding: 1,
+            visibility: wgpu::ShaderStages::FRAGMENT,
+            ty: wgpu::BindingType::Sampler(wgpu::SamplerBindingType::Filtering),
+            count: None,
+        },
+    ];
+
+    entries.extend((0..textures).map(|i| wgpu::BindGroupLayoutEntry {
+        binding: 2 + i as u32,
+        visibility: wgpu::ShaderStages::FRAGMENT,
+        ty: wgpu::BindingType::Texture {
+            multisampled: false,
+            view_dimension: wgpu::TextureViewDimension::D2,
+            sample_type: wgpu::TextureSampleType::Float { filterable: true },
+        },
+        count: None,
+    }));
+
+    device.create_bind_group_layout(&wgpu::BindGroupLayoutDescriptor {
+        entries: &entries,
+        label: Some(&format!("{name} Params Bind Group Layout")),
+    })
+}
 
 fn new(
-    shader: wgpu::ShaderModule,
+    shader: &wgpu::ShaderModule,
     layout: wgpu::PipelineLayout,
     device: &wgpu::Device,
     config: &wgpu::SurfaceConfiguration,
     label: &str,
-    buffer_layout: wgpu::VertexBufferLayout,
+    buffers: &[wgpu::VertexBufferLayout],
+    // Without blending the output replaces what's there.
+    blend: Option<BlendMode>,
 ) -> wgpu::RenderPipeline {
     device.create_render_pipeline(&wgpu::RenderPipelineDescriptor {
         label: Some(label),
         layout: Some(&layout),
         vertex: wgpu::VertexState {
-            module: &shader,
+            module: shader,
             entry_point: "vs_main",
-            buffers: &[buffer_layout],
+            buffers,
         },
         fragment: Some(wgpu::FragmentState {
-            module: &shader,
-            entry_point: "fs_main",
+            module: shader,
+            entry_point: blend.map_or("fs_main", BlendMode::fragment_entry_point),
             targets: &[Some(wgpu::ColorTargetState {
                 format: config.format,
-                blend: Some(wgpu::BlendState::ALPHA_BLENDING),
+                blend: blend.map(BlendMode::state),
                 write_mask: wgpu::ColorWrites::ALL,
             })],
         }),
@@ -46,57 +341,163 @@ fn new(
     })
 }
 
-pub async fn create_sprite_render_pipeline(
+pub async fn create_ui_render_pipeline(
     device: &wgpu::Device,
     config: &wgpu::SurfaceConfiguration,
     bind_group_layouts: &[&wgpu::BindGroupLayout],
 ) -> wgpu::RenderPipeline {
-    let sprite_shader = Resource::load_string("sprite.wgsl").await.unwrap();
+    create_color_render_pipeline(
+        device,
+        config,
+        bind_group_layouts,
+        "UI",
+        Some(BlendMode::Alpha),
+    )
+    .await
+}
+
+/// Fills a camera's viewport with its clear color, replacing whatever's there, alpha included.
+pub async fn create_clear_render_pipeline(
+    device: &wgpu::Device,
+    config: &wgpu::SurfaceConfiguration,
+) -> wgpu::RenderPipeline {
+    create_color_render_pipeline(device, config, &[], "Camera Clear", None).await
+}
+
+// Draws flat colored `UiRenderVertex` quads.
+async fn create_color_render_pipeline(
+    device: &wgpu::Device,
+    config: &wgpu::SurfaceConfiguration,
+    bind_group_layouts: &[&wgpu::BindGroupLayout],
+    name: &str,
+    blend: Option<BlendMode>,
+) -> wgpu::RenderPipeline {
+    let sprite_shader = Resource::load_string("color.wgsl").await.unwrap();
     let sprite_shader = device.create_shader_module(wgpu::ShaderModuleDescriptor {
-        label: Some("Sprite Shader"),
+        label: Some("Primitive Shader"),
         source: wgpu::ShaderSource::Wgsl(sprite_shader.into()),
     });
 
     let layout = device.create_pipeline_layout(&wgpu::PipelineLayoutDescriptor {
-        label: Some("Sprite Render Pipeline Layout"),
+        label: Some(&format!("{name} Render Pipeline Layout")),
         bind_group_layouts,
         push_constant_ranges: &[],
     });
 
     new(
-        sprite_shader,
+        &sprite_shader,
         layout,
         device,
         config,
-        "Sprite Render Pipeline",
-        vertex::RenderVertex::desc(),
+        &format!("{name} Render Pipeline"),
+        &[ui_vertex::UiRenderVertex::desc()],
+        blend,
     )
 }
 
-// TODO: Generalize this with the above function.
-pub async fn create_ui_render_pipeline(
+/// Draws a texture over the whole viewport, used to upscale the pixel perfect target.
+pub async fn create_blit_render_pipeline(
     device: &wgpu::Device,
     config: &wgpu::SurfaceConfiguration,
     bind_group_layouts: &[&wgpu::BindGroupLayout],
 ) -> wgpu::RenderPipeline {
-    let sprite_shader = Resource::load_string("color.wgsl").await.unwrap();
-    let sprite_shader = device.create_shader_module(wgpu::ShaderModuleDescriptor {
-        label: Some("Primitive Shader"),
-        source: wgpu::ShaderSource::Wgsl(sprite_shader.into()),
+    let blit_shader = Resource::load_string("blit.wgsl").await.unwrap();
+    let blit_shader = device.create_shader_module(wgpu::ShaderModuleDescriptor {
+        label: Some("Blit Shader"),
+        source: wgpu::ShaderSource::Wgsl(blit_shader.into()),
     });
 
     let layout = device.create_pipeline_layout(&wgpu::PipelineLayoutDescriptor {
-        label: Some("UI Render Pipeline Layout"),
+        label: Some("Blit Render Pipeline Layout"),
         bind_group_layouts,
         push_constant_ranges: &[],
     });
 
     new(
-        sprite_shader,
+        &blit_shader,
         layout,
         device,
         config,
-        "UI Render Pipeline",
-        ui_vertex::UiRenderVertex::desc(),
+        "Blit Render Pipeline",
+        &[],
+        Some(BlendMode::Alpha),
     )
 }
+
+/// Draws `Gizmos` lines in world space, taking the camera bind group layout.
+pub async fn create_gizmo_render_pipeline(
+    device: &wgpu::Device,
+    config: &wgpu::SurfaceConfiguration,
+    bind_group_layouts: &[&wgpu::BindGroupLayout],
+) -> wgpu::RenderPipeline {
+    let gizmo_shader = Resource::load_string("gizmo.wgsl").await.unwrap();
+    let gizmo_shader = device.create_shader_module(wgpu::ShaderModuleDescriptor {
+        label: Some("Gizmo Shader"),
+        source: wgpu::ShaderSource::Wgsl(gizmo_shader.into()),
+    });
+
+    let layout = device.create_pipeline_layout(&wgpu::PipelineLayoutDescriptor {
+        label: Some("Gizmo Render Pipeline Layout"),
+        bind_group_layouts,
+        push_constant_ranges: &[],
+    });
+
+    device.create_render_pipeline(&wgpu::RenderPipelineDescriptor {
+        label: Some("Gizmo Render Pipeline"),
+        layout: Some(&layout),
+        vertex: wgpu::VertexState {
+            module: &gizmo_shader,
+            entry_point: "vs_main",
+            buffers: &[GizmoVertex::desc()],
+        },
+        fragment: Some(wgpu::FragmentState {
+            module: &gizmo_shader,
+            entry_point: "fs_main",
+            targets: &[Some(wgpu::ColorTargetState {
+                format: config.format,
+                blend: Some(BlendMode::Alpha.state()),
+                write_mask: wgpu::ColorWrites::ALL,
+            })],
+        }),
+        primitive: wgpu::PrimitiveState {
+            topology: wgpu::PrimitiveTopology::LineList,
+            ..Default::default()
+        },
+        depth_stencil: None,
+        multisample: wgpu::MultisampleState::default(),
+        multiview: None,
+    })
+}
+
+/// A full screen post effect pass, see `PostProcess`.
+pub fn create_post_render_pipeline(
+    device: &wgpu::Device,
+    config: &wgpu::SurfaceConfiguration,
+    shader: &wgpu::ShaderModule,
+    bind_group_layouts: &[&wgpu::BindGroupLayout],
+    label: &str,
+) -> anyhow::Result<wgpu::RenderPipeline> {
+    let layout = device.create_pipeline_layout(&wgpu::PipelineLayoutDescriptor {
+        label: Some(&format!("{label} Render Pipeline Layout")),
+        bind_group_layouts,
+        push_constant_ranges: &[],
+    });
+
+    device.push_error_scope(wgpu::ErrorFilter::Validation);
+
+    let pipeline = new(
+        shader,
+        layout,
+        device,
+        config,
+        &format!("{label} Render Pipeline"),
+        &[],
+        Some(BlendMode::Alpha),
+    );
+
+    if let Some(error) = pollster::block_on(device.pop_error_scope()) {
+        anyhow::bail!("Couldn't create the {label} render pipeline: {error}");
+    }
+
+    Ok(pipeline)
+}
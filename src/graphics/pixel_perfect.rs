@@ -0,0 +1,59 @@
+use super::render_target::RenderTarget;
+
+/// Renders the world at a fixed low resolution and scales it up by whole pixels, so every texel
+/// covers the same square of screen pixels however the window is sized.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct PixelPerfect {
+    pub reference_width: u32,
+    pub reference_height: u32,
+    /// Texels per world unit. Sprites are one unit across, so this is usually their texture size.
+    pub pixels_per_unit: f32,
+}
+
+/// Where the upscaled world lands in the window, in pixels.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Letterbox {
+    pub x: f32,
+    pub y: f32,
+    pub width: f32,
+    pub height: f32,
+}
+
+impl PixelPerfect {
+    /**
+     * The largest whole number the reference resolution can be multiplied by and still fit the
+     * target. Targets smaller than the reference get a fractional scale instead, which can't be
+     * pixel perfect but at least shows everything.
+     */
+    pub fn upscale(&self, target_width: u32, target_height: u32) -> f32 {
+        let fit = (target_width as f32 / self.reference_width as f32)
+            .min(target_height as f32 / self.reference_height as f32);
+
+        if fit >= 1.0 {
+            fit.floor()
+        } else {
+            fit
+        }
+    }
+
+    /// The upscaled world centered in the target, with bars filling whatever is left over.
+    pub fn letterbox(&self, target_width: u32, target_height: u32) -> Letterbox {
+        let upscale = self.upscale(target_width, target_height);
+
+        let width = self.reference_width as f32 * upscale;
+        let height = self.reference_height as f32 * upscale;
+
+        Letterbox {
+            x: ((target_width as f32 - width) / 2.0).floor(),
+            y: ((target_height as f32 - height) / 2.0).floor(),
+            width,
+            height,
+        }
+    }
+}
+
+/// The low resolution texture the world is drawn into before being upscaled.
+pub struct PixelPerfectTarget {
+    pub settings: PixelPerfect,
+    pub target: RenderTarget,
+}
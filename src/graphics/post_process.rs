@@ -0,0 +1,350 @@
+use std::collections::HashMap;
+
+use crate::resources::Resource;
+
+use super::{pipeline, render_target::RenderTarget, texture::TexturePage};
+
+/// A full screen pass the world goes through after it's drawn and before the UI.
+#[derive(Clone, Debug, PartialEq)]
+pub enum PostEffect {
+    /// Darkens toward the corners from `radius` out, as a fraction of the way to a corner.
+    Vignette { intensity: f32, radius: f32 },
+    /**
+     * Remaps colors through a lookup texture under `res/textures`, mixed in by `intensity`. LUTs
+     * are 16 cells of 16x16 texels side by side, with red across each cell, green down it and
+     * blue across the cells.
+     */
+    ColorGrading { lut: String, intensity: f32 },
+    /// Adds a blur, `radius` texels wide, of everything brighter than `threshold`.
+    Bloom {
+        threshold: f32,
+        intensity: f32,
+        radius: f32,
+    },
+    /// Bulges the image like an old screen and darkens every other row by `scanlines`.
+    Crt { curvature: f32, scanlines: f32 },
+    /// A shader under `res/shaders`, see `PostProcess` for what it's given.
+    Custom { shader: String, params: [f32; 4] },
+}
+
+impl PostEffect {
+    // Where the effect's shader is under `res`.
+    fn shader(&self) -> (&str, &str) {
+        match self {
+            PostEffect::Vignette { .. } => ("post", "vignette.wgsl"),
+            PostEffect::ColorGrading { .. } => ("post", "color_grading.wgsl"),
+            PostEffect::Bloom { .. } => ("post", "bloom.wgsl"),
+            PostEffect::Crt { .. } => ("post", "crt.wgsl"),
+            PostEffect::Custom { shader, .. } => ("shaders", shader),
+        }
+    }
+
+    fn params(&self) -> [f32; 4] {
+        match self {
+            PostEffect::Vignette { intensity, radius } => [*intensity, *radius, 0.0, 0.0],
+            PostEffect::ColorGrading { intensity, .. } => [*intensity, 0.0, 0.0, 0.0],
+            PostEffect::Bloom {
+                threshold,
+                intensity,
+                radius,
+            } => [*threshold, *intensity, *radius, 0.0],
+            PostEffect::Crt {
+                curvature,
+                scanlines,
+            } => [*curvature, *scanlines, 0.0, 0.0],
+            PostEffect::Custom { params, .. } => *params,
+        }
+    }
+}
+
+#[repr(C)]
+#[derive(Copy, Clone, Debug, bytemuck::Pod, bytemuck::Zeroable)]
+struct PostUniform {
+    params: [f32; 4],
+    /// One over the target's size, to step between texels.
+    texel_size: [f32; 2],
+    _padding: [f32; 2],
+}
+
+/**
+ * One effect in the chain with its uniform and extra texture. Its shader and texture are fixed
+ * when it's created, so changing the effect means building a new pass.
+ */
+pub struct PostPass {
+    effect: PostEffect,
+    pub enabled: bool,
+    shader: String,
+    buffer: wgpu::Buffer,
+    bind_group: wgpu::BindGroup,
+    // Kept alive for the bind group.
+    _lut: Option<TexturePage>,
+}
+
+/**
+ * The chain of post effects, each drawing the previous one's output into one of two targets in
+ * turn. Effect shaders draw one triangle over the viewport, like `blit.wgsl`, and are given:
+ *
+ * - group 0, the image so far at binding 0 and its sampler at binding 1,
+ * - group 1, a uniform with the effect's four parameters and the size of a texel at binding 0,
+ *   an extra texture at binding 1, white unless the effect has one, and a linear sampler for it
+ *   at binding 2.
+ */
+pub struct PostProcess {
+    passes: Vec<PostPass>,
+    pipelines: HashMap<String, wgpu::RenderPipeline>,
+    layout: wgpu::BindGroupLayout,
+    sampler: wgpu::Sampler,
+    blank: wgpu::TextureView,
+    targets: Vec<RenderTarget>,
+}
+
+impl PostProcess {
+    pub fn new(device: &wgpu::Device, queue: &wgpu::Queue) -> Self {
+        let layout = device.create_bind_group_layout(&wgpu::BindGroupLayoutDescriptor {
+            entries: &[
+                wgpu::BindGroupLayoutEntry {
+                    binding: 0,
+                    visibility: wgpu::ShaderStages::FRAGMENT,
+                    ty: wgpu::BindingType::Buffer {
+                        ty: wgpu::BufferBindingType::Uniform,
+                        has_dynamic_offset: false,
+                        min_binding_size: None,
+                    },
+                    count: None,
+                },
+                wgpu::BindGroupLayoutEntry {
+                    binding: 1,
+                    visibility: wgpu::ShaderStages::FRAGMENT,
+                    ty: wgpu::BindingType::Texture {
+                        multisampled: false,
+                        view_dimension: wgpu::TextureViewDimension::D2,
+                        sample_type: wgpu::TextureSampleType::Float { filterable: true },
+                    },
+                    count: None,
+                },
+                wgpu::BindGroupLayoutEntry {
+                    binding: 2,
+                    visibility: wgpu::ShaderStages::FRAGMENT,
+                    ty: wgpu::BindingType::Sampler(wgpu::SamplerBindingType::Filtering),
+                    count: None,
+                },
+            ],
+            label: Some("post_effect_bind_group_layout"),
+        });
+
+        let sampler = device.create_sampler(&wgpu::SamplerDescriptor {
+            address_mode_u: wgpu::AddressMode::ClampToEdge,
+            address_mode_v: wgpu::AddressMode::ClampToEdge,
+            address_mode_w: wgpu::AddressMode::ClampToEdge,
+            mag_filter: wgpu::FilterMode::Linear,
+            min_filter: wgpu::FilterMode::Linear,
+            mipmap_filter: wgpu::FilterMode::Nearest,
+            ..Default::default()
+        });
+
+        let size = wgpu::Extent3d {
+            width: 1,
+            height: 1,
+            depth_or_array_layers: 1,
+        };
+
+        let blank = device.create_texture(&wgpu::TextureDescriptor {
+            label: Some("Post Effect Blank Texture"),
+            size,
+            mip_level_count: 1,
+            sample_count: 1,
+            dimension: wgpu::TextureDimension::D2,
+            format: wgpu::TextureFormat::Rgba8UnormSrgb,
+            usage: wgpu::TextureUsages::TEXTURE_BINDING | wgpu::TextureUsages::COPY_DST,
+        });
+
+        queue.write_texture(
+            wgpu::ImageCopyTexture {
+                aspect: wgpu::TextureAspect::All,
+                texture: &blank,
+                mip_level: 0,
+                origin: wgpu::Origin3d::ZERO,
+            },
+            &[255; 4],
+            wgpu::ImageDataLayout {
+                offset: 0,
+                bytes_per_row: std::num::NonZeroU32::new(4),
+                rows_per_image: std::num::NonZeroU32::new(1),
+            },
+            size,
+        );
+
+        Self {
+            passes: Vec::new(),
+            pipelines: HashMap::new(),
+            layout,
+            sampler,
+            blank: blank.create_view(&wgpu::TextureViewDescriptor::default()),
+            targets: Vec::new(),
+        }
+    }
+
+    /**
+     * Builds a pass for an effect, compiling its shader the first time it's used. `source_layout`
+     * is the layout of the image the pass reads at group 0.
+     */
+    pub fn create_pass(
+        &mut self,
+        device: &wgpu::Device,
+        config: &wgpu::SurfaceConfiguration,
+        source_layout: &wgpu::BindGroupLayout,
+        effect: PostEffect,
+        lut: Option<TexturePage>,
+    ) -> anyhow::Result<PostPass> {
+        let (dir, file_name) = effect.shader();
+        let shader = format!("{dir}/{file_name}");
+
+        if !self.pipelines.contains_key(&shader) {
+            let path = Resource::build_path(Some(dir), file_name);
+            let module = pipeline::compile_shader(device, &shader, &path)?;
+
+            let pipeline = pipeline::create_post_render_pipeline(
+                device,
+                config,
+                &module,
+                &[source_layout, &self.layout],
+                &shader,
+            )?;
+
+            self.pipelines.insert(shader.clone(), pipeline);
+        }
+
+        let buffer = device.create_buffer(&wgpu::BufferDescriptor {
+            label: Some(&format!("{shader} Uniform Buffer")),
+            size: std::mem::size_of::<PostUniform>() as u64,
+            usage: wgpu::BufferUsages::UNIFORM | wgpu::BufferUsages::COPY_DST,
+            mapped_at_creation: false,
+        });
+
+        let extra = lut.as_ref().map_or(&self.blank, |page| &page.view);
+
+        let bind_group = device.create_bind_group(&wgpu::BindGroupDescriptor {
+            layout: &self.layout,
+            entries: &[
+                wgpu::BindGroupEntry {
+                    binding: 0,
+                    resource: buffer.as_entire_binding(),
+                },
+                wgpu::BindGroupEntry {
+                    binding: 1,
+                    resource: wgpu::BindingResource::TextureView(extra),
+                },
+                wgpu::BindGroupEntry {
+                    binding: 2,
+                    resource: wgpu::BindingResource::Sampler(&self.sampler),
+                },
+            ],
+            label: Some(&format!("{shader} Bind Group")),
+        });
+
+        Ok(PostPass {
+            effect,
+            enabled: true,
+            shader,
+            buffer,
+            bind_group,
+            _lut: lut,
+        })
+    }
+}
+
+impl PostPass {
+    pub fn effect(&self) -> &PostEffect {
+        &self.effect
+    }
+}
+
+impl PostProcess {
+    pub fn passes(&self) -> &[PostPass] {
+        &self.passes
+    }
+
+    pub fn passes_mut(&mut self) -> &mut Vec<PostPass> {
+        &mut self.passes
+    }
+
+    /// Whether the world has to be drawn into a target for the chain.
+    pub fn is_active(&self) -> bool {
+        self.passes.iter().any(|pass| pass.enabled)
+    }
+
+    pub fn targets_fit(&self, width: u32, height: u32) -> bool {
+        self.targets
+            .first()
+            .is_some_and(|target| target.width == width && target.height == height)
+    }
+
+    /// The two targets passes take turns drawing into, the world's size.
+    pub fn set_targets(&mut self, targets: [RenderTarget; 2]) {
+        self.targets = targets.into();
+    }
+
+    pub fn target(&self, index: usize) -> Option<&RenderTarget> {
+        self.targets.get(index)
+    }
+
+    /// Uploads every enabled effect's parameters for targets of the given size.
+    pub fn write_uniforms(&self, queue: &wgpu::Queue, width: u32, height: u32) {
+        for pass in self.passes.iter().filter(|pass| pass.enabled) {
+            let uniform = PostUniform {
+                params: pass.effect.params(),
+                texel_size: [1.0 / width as f32, 1.0 / height as f32],
+                _padding: [0.0; 2],
+            };
+
+            queue.write_buffer(&pass.buffer, 0, bytemuck::cast_slice(&[uniform]));
+        }
+    }
+
+    /**
+     * Runs every enabled effect over `source` and returns the bind group of the result.
+     * `source_target` is which of the chain's own targets `source` is, if it's one of them, so
+     * the first pass draws into the other.
+     */
+    pub fn apply<'a>(
+        &'a self,
+        encoder: &mut wgpu::CommandEncoder,
+        source: &'a wgpu::BindGroup,
+        source_target: Option<usize>,
+    ) -> &'a wgpu::BindGroup {
+        let mut source = source;
+        let mut current = source_target;
+
+        for pass in self.passes.iter().filter(|pass| pass.enabled) {
+            let next = match current {
+                Some(0) => 1,
+                _ => 0,
+            };
+
+            let target = &self.targets[next];
+
+            let mut render_pass = encoder.begin_render_pass(&wgpu::RenderPassDescriptor {
+                label: Some("Post Effect Render Pass"),
+                color_attachments: &[Some(wgpu::RenderPassColorAttachment {
+                    view: &target.view,
+                    resolve_target: None,
+                    ops: wgpu::Operations {
+                        load: wgpu::LoadOp::Clear(wgpu::Color::BLACK),
+                        store: true,
+                    },
+                })],
+                depth_stencil_attachment: None,
+            });
+
+            render_pass.set_pipeline(&self.pipelines[&pass.shader]);
+            render_pass.set_bind_group(0, source, &[]);
+            render_pass.set_bind_group(1, &pass.bind_group, &[]);
+            render_pass.draw(0..3, 0..1);
+
+            source = &target.bind_group;
+            current = Some(next);
+        }
+
+        source
+    }
+}
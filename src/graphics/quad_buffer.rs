@@ -0,0 +1,157 @@
+use std::marker::PhantomData;
+
+use wgpu::util::DeviceExt;
+
+use super::sprite;
+
+// Quads allocated up front, the buffer doubles from here as slots past the end are written.
+const INITIAL_CAPACITY: usize = 1024;
+
+/**
+ * A vertex buffer of quads addressed by slot, with a matching index buffer. Writing past the end
+ * reallocates both and copies the existing quads across, up to the largest buffer the device
+ * allows.
+ */
+pub struct QuadBuffer<V: bytemuck::Pod> {
+    label: &'static str,
+    vertex_buffer: wgpu::Buffer,
+    index_buffer: wgpu::Buffer,
+    capacity: usize,
+    max_capacity: usize,
+    vertex: PhantomData<V>,
+}
+
+impl<V: bytemuck::Pod> QuadBuffer<V> {
+    pub fn new(device: &wgpu::Device, label: &'static str) -> Self {
+        let max_by_size = device.limits().max_buffer_size as usize / Self::quad_size();
+        // Indices are u32, so no quad can start past this.
+        let max_by_index = u32::MAX as usize / 4;
+        let max_capacity = max_by_size.min(max_by_index);
+
+        let capacity = INITIAL_CAPACITY.min(max_capacity);
+
+        Self {
+            label,
+            vertex_buffer: Self::create_vertex_buffer(device, label, capacity),
+            index_buffer: Self::create_index_buffer(device, label, capacity),
+            capacity,
+            max_capacity,
+            vertex: PhantomData,
+        }
+    }
+
+    pub fn vertex_buffer(&self) -> &wgpu::Buffer {
+        &self.vertex_buffer
+    }
+
+    pub fn index_buffer(&self) -> &wgpu::Buffer {
+        &self.index_buffer
+    }
+
+    pub fn capacity(&self) -> usize {
+        self.capacity
+    }
+
+    /// The most quads this buffer can ever hold on this device.
+    pub fn max_capacity(&self) -> usize {
+        self.max_capacity
+    }
+
+    /// Writes one quad's four vertices into a slot, growing the buffer if it's past the end.
+    pub fn write(
+        &mut self,
+        device: &wgpu::Device,
+        queue: &wgpu::Queue,
+        slot: usize,
+        verts: &[V],
+    ) -> anyhow::Result<()> {
+        if verts.len() != 4 {
+            anyhow::bail!("{} quads take 4 vertices, got {}.", self.label, verts.len());
+        }
+
+        if slot >= self.capacity {
+            self.grow(device, queue, slot + 1)?;
+        }
+
+        queue.write_buffer(
+            &self.vertex_buffer,
+            (slot * Self::quad_size()) as wgpu::BufferAddress,
+            bytemuck::cast_slice(verts),
+        );
+
+        Ok(())
+    }
+
+    fn grow(
+        &mut self,
+        device: &wgpu::Device,
+        queue: &wgpu::Queue,
+        required: usize,
+    ) -> anyhow::Result<()> {
+        if required > self.max_capacity {
+            anyhow::bail!(
+                "{} is full, slot {} is past the limit of {} quads.",
+                self.label,
+                required - 1,
+                self.max_capacity
+            );
+        }
+
+        let capacity = (self.capacity * 2).max(required).min(self.max_capacity);
+        let vertex_buffer = Self::create_vertex_buffer(device, self.label, capacity);
+
+        // Writes queued for the old buffer land before this copy runs.
+        let mut encoder = device.create_command_encoder(&wgpu::CommandEncoderDescriptor {
+            label: Some("Quad Buffer Grow Encoder"),
+        });
+
+        encoder.copy_buffer_to_buffer(
+            &self.vertex_buffer,
+            0,
+            &vertex_buffer,
+            0,
+            (self.capacity * Self::quad_size()) as wgpu::BufferAddress,
+        );
+
+        queue.submit([encoder.finish()]);
+
+        self.vertex_buffer = vertex_buffer;
+        self.index_buffer = Self::create_index_buffer(device, self.label, capacity);
+        self.capacity = capacity;
+
+        Ok(())
+    }
+
+    fn quad_size() -> usize {
+        std::mem::size_of::<V>() * 4
+    }
+
+    fn create_vertex_buffer(device: &wgpu::Device, label: &str, capacity: usize) -> wgpu::Buffer {
+        device.create_buffer(&wgpu::BufferDescriptor {
+            size: (capacity * Self::quad_size()) as u64,
+            label: Some(&format!("{label} Vertex Buffer")),
+            usage: wgpu::BufferUsages::VERTEX
+                | wgpu::BufferUsages::COPY_DST
+                | wgpu::BufferUsages::COPY_SRC,
+            mapped_at_creation: false,
+        })
+    }
+
+    fn create_index_buffer(device: &wgpu::Device, label: &str, capacity: usize) -> wgpu::Buffer {
+        let mut indices = Vec::<u32>::with_capacity(capacity * 6);
+
+        for i in 0..capacity {
+            let new_indices = sprite::Sprite::get_indices()
+                .into_iter()
+                .map(|idx| idx + (4 * i as u32));
+
+            indices.extend(new_indices);
+        }
+
+        device.create_buffer_init(&wgpu::util::BufferInitDescriptor {
+            label: Some(&format!("{label} Index Buffer")),
+            contents: bytemuck::cast_slice(indices.as_slice()),
+            usage: wgpu::BufferUsages::INDEX,
+        })
+    }
+}
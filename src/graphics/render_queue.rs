@@ -0,0 +1,238 @@
+use std::{cmp::Ordering, ops::Range};
+
+use crate::{camera::Camera, entity::Entity};
+
+use super::{
+    particles::Particles,
+    sorting_layer::{SortingLayer, SortingLayers},
+    tile_chunk::TileChunks,
+    vertex::SpriteInstance,
+};
+
+/// A run of instances sharing a material, drawn with one call.
+#[derive(Clone, Debug, PartialEq)]
+pub struct SpriteBatch {
+    pub material: usize,
+    pub instances: Range<u32>,
+    /// Drawn from this tile chunk's buffer rather than the queue's instances.
+    pub chunk: Option<usize>,
+}
+
+/// How much work the last frame's world pass took.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct RenderStats {
+    pub draw_calls: usize,
+    /// Material bind groups set, runs on the same atlas page share one.
+    pub binds: usize,
+    pub sprites: usize,
+}
+
+// What a queue entry draws, an entity's instance, a tile chunk with its instance count or a
+// particle batch.
+#[derive(Clone, Copy)]
+enum Source {
+    Entity(usize),
+    Chunk(usize, u32),
+    Particles(usize),
+}
+
+struct SortKey {
+    layer: SortingLayer,
+    order_in_layer: i32,
+    /// Zero outside Y-sorted layers.
+    y: f32,
+    material: usize,
+}
+
+impl SortKey {
+    // Layer first, then order in layer, then height on Y-sorted layers, then material so runs of
+    // one material stay together.
+    fn cmp(&self, other: &Self) -> Ordering {
+        self.layer
+            .cmp(&other.layer)
+            .then(self.order_in_layer.cmp(&other.order_in_layer))
+            .then(other.y.total_cmp(&self.y))
+            .then(self.material.cmp(&other.material))
+    }
+}
+
+/**
+ * The sprites each camera draws this frame, sorted and split into batches. Kept between frames so
+ * its lists are reused rather than reallocated.
+ */
+#[derive(Default)]
+pub struct RenderQueue {
+    instances: Vec<SpriteInstance>,
+    batches: Vec<SpriteBatch>,
+    /// Each camera's slice of `batches`.
+    camera_batches: Vec<Range<usize>>,
+    sorted: Vec<(SortKey, Source)>,
+}
+
+impl RenderQueue {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /**
+     * Rebuilds the queue from every entity, looking up their instances by entity id, every tile
+     * chunk in view and every particle batch. Chunks draw under sprites they tie with, and
+     * particles over them.
+     */
+    pub fn build(
+        &mut self,
+        cameras: &[Camera],
+        entities: &[Option<Entity>],
+        instances: &[SpriteInstance],
+        chunks: &TileChunks,
+        particles: &Particles,
+        layers: &SortingLayers,
+    ) {
+        self.clear();
+
+        for camera in cameras {
+            let view = camera.visible_bounds();
+
+            self.sorted.clear();
+            self.sorted.extend(
+                chunks
+                    .chunks()
+                    .iter()
+                    .enumerate()
+                    .filter(|(_, chunk)| {
+                        chunk.instances > 0
+                            && chunk.render_layers & camera.layer_mask != 0
+                            && chunk.bounds.min.x < view.max.x
+                            && chunk.bounds.max.x > view.min.x
+                            && chunk.bounds.min.y < view.max.y
+                            && chunk.bounds.max.y > view.min.y
+                    })
+                    .map(|(index, chunk)| {
+                        let y = if layers.is_y_sorted(chunk.sorting_layer) {
+                            chunk.bounds.min.y
+                        } else {
+                            0.0
+                        };
+
+                        let key = SortKey {
+                            layer: chunk.sorting_layer,
+                            order_in_layer: chunk.order_in_layer,
+                            y,
+                            material: chunk.material,
+                        };
+
+                        (key, Source::Chunk(index, chunk.instances))
+                    }),
+            );
+
+            self.sorted.extend(
+                entities
+                    .iter()
+                    .enumerate()
+                    .filter_map(|(index, entity)| Some((index, entity.as_ref()?)))
+                    .filter(|(_, entity)| entity.get_render_layers() & camera.layer_mask != 0)
+                    .map(|(index, entity)| {
+                        let layer = entity.get_sorting_layer();
+
+                        let y = if layers.is_y_sorted(layer) {
+                            entity.get_position().y
+                        } else {
+                            0.0
+                        };
+
+                        let key = SortKey {
+                            layer,
+                            order_in_layer: entity.get_order_in_layer(),
+                            y,
+                            material: entity.sprite_mat,
+                        };
+
+                        (key, Source::Entity(index))
+                    }),
+            );
+
+            self.sorted.extend(
+                particles
+                    .batches()
+                    .iter()
+                    .enumerate()
+                    .filter(|(_, batch)| batch.render_layers & camera.layer_mask != 0)
+                    .map(|(index, batch)| {
+                        let y = if layers.is_y_sorted(batch.sorting_layer) {
+                            batch.y
+                        } else {
+                            0.0
+                        };
+
+                        let key = SortKey {
+                            layer: batch.sorting_layer,
+                            order_in_layer: batch.order_in_layer,
+                            y,
+                            material: batch.material,
+                        };
+
+                        (key, Source::Particles(index))
+                    }),
+            );
+
+            // Stable, so ties keep their spawn order.
+            self.sorted.sort_by(|(a, _), (b, _)| a.cmp(b));
+
+            let first_batch = self.batches.len();
+
+            for (SortKey { material, .. }, source) in &self.sorted {
+                let added = match *source {
+                    Source::Entity(id) => std::slice::from_ref(&instances[id]),
+                    Source::Particles(batch) => {
+                        &particles.instances()[particles.batches()[batch].instances.clone()]
+                    }
+                    Source::Chunk(chunk, count) => {
+                        self.batches.push(SpriteBatch {
+                            material: *material,
+                            instances: 0..count,
+                            chunk: Some(chunk),
+                        });
+
+                        continue;
+                    }
+                };
+
+                let start = self.instances.len() as u32;
+                self.instances.extend_from_slice(added);
+                let end = self.instances.len() as u32;
+
+                match self.batches[first_batch..].last_mut() {
+                    Some(batch) if batch.material == *material && batch.chunk.is_none() => {
+                        batch.instances.end = end
+                    }
+                    _ => self.batches.push(SpriteBatch {
+                        material: *material,
+                        instances: start..end,
+                        chunk: None,
+                    }),
+                }
+            }
+
+            self.camera_batches.push(first_batch..self.batches.len());
+        }
+    }
+
+    pub fn clear(&mut self) {
+        self.instances.clear();
+        self.batches.clear();
+        self.camera_batches.clear();
+    }
+
+    /// Every camera's instances, in draw order.
+    pub fn instances(&self) -> &[SpriteInstance] {
+        &self.instances
+    }
+
+    /// The batches drawn by the camera at this index, empty if it has none.
+    pub fn batches(&self, camera: usize) -> &[SpriteBatch] {
+        match self.camera_batches.get(camera) {
+            Some(range) => &self.batches[range.clone()],
+            None => &[],
+        }
+    }
+}
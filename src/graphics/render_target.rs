@@ -0,0 +1,8 @@
+/// A texture the world or a post effect draws into, bound so the next pass can sample it.
+pub struct RenderTarget {
+    pub texture: wgpu::Texture,
+    pub view: wgpu::TextureView,
+    pub bind_group: wgpu::BindGroup,
+    pub width: u32,
+    pub height: u32,
+}
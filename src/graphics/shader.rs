@@ -0,0 +1,230 @@
+use std::rc::Rc;
+
+use wgpu::util::DeviceExt;
+
+use super::{pipeline::CustomShader, texture::TexturePage, Graphics};
+
+/// A value a custom shader reads from its material.
+#[derive(Clone)]
+pub enum ShaderValue {
+    Float(f32),
+    Vector2(cgmath::Vector2<f32>),
+    Vector4(cgmath::Vector4<f32>),
+    /// A `vec4<f32>` in the shader.
+    Color(wgpu::Color),
+    Texture(Rc<TexturePage>),
+}
+
+impl ShaderValue {
+    // Size and alignment in the uniform buffer, following WGSL's layout rules.
+    fn layout(&self) -> Option<(usize, usize)> {
+        match self {
+            ShaderValue::Float(_) => Some((4, 4)),
+            ShaderValue::Vector2(_) => Some((8, 8)),
+            ShaderValue::Vector4(_) | ShaderValue::Color(_) => Some((16, 16)),
+            ShaderValue::Texture(_) => None,
+        }
+    }
+
+    fn floats(&self) -> Vec<f32> {
+        match self {
+            ShaderValue::Float(value) => vec![*value],
+            ShaderValue::Vector2(value) => vec![value.x, value.y],
+            ShaderValue::Vector4(value) => vec![value.x, value.y, value.z, value.w],
+            ShaderValue::Color(color) => {
+                vec![
+                    color.r as f32,
+                    color.g as f32,
+                    color.b as f32,
+                    color.a as f32,
+                ]
+            }
+            ShaderValue::Texture(_) => Vec::new(),
+        }
+    }
+}
+
+pub struct ShaderParam {
+    pub name: String,
+    pub value: ShaderValue,
+}
+
+/**
+ * A custom shader under `res/shaders` and the parameters a material passes it. Shaders take the
+ * same vertex input and groups 0 and 1 as `sprite.wgsl`, and read the parameters at group 2:
+ *
+ * - binding 0, a uniform struct with every non-texture parameter in the order they're declared,
+ * - binding 1, a sampler for the textures,
+ * - binding 2 onwards, each texture parameter in the order they're declared.
+ */
+pub struct MaterialShader {
+    name: Rc<str>,
+    shader: Rc<CustomShader>,
+    params: Vec<ShaderParam>,
+    // Each parameter's byte offset in the uniform, textures have none.
+    offsets: Vec<Option<usize>>,
+    uniform: Vec<u8>,
+    buffer: wgpu::Buffer,
+    bind_group: wgpu::BindGroup,
+}
+
+impl MaterialShader {
+    pub fn new(graphics: &Graphics, name: &str, params: Vec<ShaderParam>) -> anyhow::Result<Self> {
+        for (i, param) in params.iter().enumerate() {
+            if params[..i].iter().any(|other| other.name == param.name) {
+                anyhow::bail!("Shader parameter {} is declared twice.", param.name);
+            }
+        }
+
+        let textures = params
+            .iter()
+            .filter(|param| param.value.layout().is_none())
+            .count();
+
+        let shader = graphics
+            .pipelines
+            .load_shader(&graphics.device, name, textures)?;
+
+        let mut offsets = Vec::with_capacity(params.len());
+        let mut size: usize = 0;
+
+        for param in &params {
+            offsets.push(param.value.layout().map(|(param_size, align)| {
+                let offset = size.next_multiple_of(align);
+                size = offset + param_size;
+                offset
+            }));
+        }
+
+        // Uniform structs are padded to 16 bytes, and bindings can't be empty.
+        let mut uniform = vec![0; size.next_multiple_of(16).max(16)];
+
+        for (param, offset) in params.iter().zip(&offsets) {
+            if let Some(offset) = offset {
+                write_uniform(&mut uniform, *offset, &param.value);
+            }
+        }
+
+        let buffer = graphics
+            .device
+            .create_buffer_init(&wgpu::util::BufferInitDescriptor {
+                label: Some(&format!("{name} Params Buffer")),
+                contents: &uniform,
+                usage: wgpu::BufferUsages::UNIFORM | wgpu::BufferUsages::COPY_DST,
+            });
+
+        let bind_group = create_bind_group(graphics, name, &shader, &buffer, &params);
+
+        Ok(Self {
+            name: Rc::from(name),
+            shader,
+            params,
+            offsets,
+            uniform,
+            buffer,
+            bind_group,
+        })
+    }
+
+    pub fn name(&self) -> &Rc<str> {
+        &self.name
+    }
+
+    pub fn params(&self) -> &[ShaderParam] {
+        &self.params
+    }
+
+    pub fn param(&self, name: &str) -> Option<&ShaderValue> {
+        self.params
+            .iter()
+            .find(|param| param.name == name)
+            .map(|param| &param.value)
+    }
+
+    /// Changes a parameter from the next frame on. It has to keep the type it was declared with.
+    pub fn set_param(
+        &mut self,
+        graphics: &Graphics,
+        name: &str,
+        value: ShaderValue,
+    ) -> anyhow::Result<()> {
+        let Some(index) = self.params.iter().position(|param| param.name == name) else {
+            anyhow::bail!("Shader {} has no parameter {name}.", self.name);
+        };
+
+        let param = &mut self.params[index];
+
+        if std::mem::discriminant(&param.value) != std::mem::discriminant(&value) {
+            anyhow::bail!(
+                "Shader parameter {name} can't change type, it was declared as a different one."
+            );
+        }
+
+        param.value = value;
+
+        match self.offsets[index] {
+            Some(offset) => {
+                write_uniform(&mut self.uniform, offset, &param.value);
+                graphics.queue.write_buffer(&self.buffer, 0, &self.uniform);
+            }
+            None => {
+                self.bind_group = create_bind_group(
+                    graphics,
+                    &self.name,
+                    &self.shader,
+                    &self.buffer,
+                    &self.params,
+                );
+            }
+        }
+
+        Ok(())
+    }
+
+    pub fn bind_group(&self) -> &wgpu::BindGroup {
+        &self.bind_group
+    }
+}
+
+fn write_uniform(uniform: &mut [u8], offset: usize, value: &ShaderValue) {
+    let floats = value.floats();
+    let bytes: &[u8] = bytemuck::cast_slice(&floats);
+    uniform[offset..offset + bytes.len()].copy_from_slice(bytes);
+}
+
+fn create_bind_group(
+    graphics: &Graphics,
+    name: &str,
+    shader: &CustomShader,
+    buffer: &wgpu::Buffer,
+    params: &[ShaderParam],
+) -> wgpu::BindGroup {
+    let mut entries = vec![
+        wgpu::BindGroupEntry {
+            binding: 0,
+            resource: buffer.as_entire_binding(),
+        },
+        wgpu::BindGroupEntry {
+            binding: 1,
+            resource: wgpu::BindingResource::Sampler(&graphics.texture_sampler),
+        },
+    ];
+
+    let textures = params.iter().filter_map(|param| match &param.value {
+        ShaderValue::Texture(page) => Some(page),
+        _ => None,
+    });
+
+    entries.extend(textures.enumerate().map(|(i, page)| wgpu::BindGroupEntry {
+        binding: 2 + i as u32,
+        resource: wgpu::BindingResource::TextureView(&page.view),
+    }));
+
+    graphics
+        .device
+        .create_bind_group(&wgpu::BindGroupDescriptor {
+            layout: &shader.params_layout,
+            entries: &entries,
+            label: Some(&format!("{name} Params Bind Group")),
+        })
+}
@@ -1,5 +1,80 @@
-#[derive(Clone, Copy, PartialEq, Eq, Hash)]
-pub enum SortingLayer {
-    Background,
-    Foreground,
+use serde::Deserialize;
+
+/// A handle to one of the layers in `SortingLayers`. Layers compare in draw order.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct SortingLayer(usize);
+
+impl SortingLayer {
+    /// Position in the declared order, the first layer is drawn first.
+    pub fn index(&self) -> usize {
+        self.0
+    }
+}
+
+#[derive(Clone, Debug, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct SortingLayerSettings {
+    pub name: String,
+    /// Sprites lower on screen draw over higher ones, for top-down scenes.
+    #[serde(default)]
+    pub y_sort: bool,
+}
+
+/// The sorting layers a project declares, drawn in the order they're declared.
+#[derive(Clone, Debug)]
+pub struct SortingLayers {
+    layers: Vec<SortingLayerSettings>,
+}
+
+impl SortingLayers {
+    pub fn new(layers: Vec<SortingLayerSettings>) -> anyhow::Result<Self> {
+        if layers.is_empty() {
+            anyhow::bail!("At least one sorting layer must be declared.");
+        }
+
+        for (i, layer) in layers.iter().enumerate() {
+            if layers[..i].iter().any(|other| other.name == layer.name) {
+                anyhow::bail!("Sorting layer `{}` is declared twice.", layer.name);
+            }
+        }
+
+        Ok(Self { layers })
+    }
+
+    pub fn get(&self, name: &str) -> Option<SortingLayer> {
+        self.layers
+            .iter()
+            .position(|layer| layer.name == name)
+            .map(SortingLayer)
+    }
+
+    /// The layer drawn first.
+    pub fn first(&self) -> SortingLayer {
+        SortingLayer(0)
+    }
+
+    /// The layer drawn last.
+    pub fn last(&self) -> SortingLayer {
+        SortingLayer(self.layers.len() - 1)
+    }
+
+    pub fn settings(&self, layer: SortingLayer) -> Option<&SortingLayerSettings> {
+        self.layers.get(layer.0)
+    }
+
+    pub fn settings_mut(&mut self, layer: SortingLayer) -> Option<&mut SortingLayerSettings> {
+        self.layers.get_mut(layer.0)
+    }
+
+    pub fn is_y_sorted(&self, layer: SortingLayer) -> bool {
+        self.settings(layer).is_some_and(|settings| settings.y_sort)
+    }
+
+    pub fn len(&self) -> usize {
+        self.layers.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.layers.is_empty()
+    }
 }
@@ -1,4 +1,4 @@
-use super::{material, vertex};
+use super::{render_queue::SpriteBatch, vertex};
 
 // TODO: Why is this an object with associated functions instead of just free functions?
 pub struct Sprite;
@@ -8,6 +8,7 @@ impl Sprite {
         [0, 1, 3, 1, 2, 3]
     }
 
+    /// The unit quad every sprite instance is drawn with, instances pick the part of the texture.
     pub fn get_vertices() -> [vertex::Vertex; 4] {
         [
             vertex::Vertex {
@@ -30,18 +31,39 @@ impl Sprite {
     }
 }
 
-pub trait DrawSprite<'a> {
-    fn draw_sprite(&mut self, material: &'a material::Material, entity_id: usize);
+/// How a sprite's texture is colored and oriented when drawn.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct SpriteAppearance {
+    /// Multiplied with the texture.
+    pub tint: wgpu::Color,
+    /// Scales the tinted alpha, for fading in and out.
+    pub opacity: f32,
+    /// Added over the tinted texture, weighted by its alpha, for hit flashes. Transparent adds
+    /// nothing.
+    pub flash: wgpu::Color,
+    pub flip_x: bool,
+    pub flip_y: bool,
 }
 
-impl<'a, 'b> DrawSprite<'b> for wgpu::RenderPass<'a>
-where
-    'b: 'a,
-{
-    fn draw_sprite(&mut self, material: &'b material::Material, entity_id: usize) {
-        let index_start = entity_id as u32 * 6;
-        let index_end = index_start + 6;
-        self.set_bind_group(0, &material.bind_group, &[]);
-        self.draw_indexed(index_start..index_end, 0, 0..1);
+impl Default for SpriteAppearance {
+    fn default() -> Self {
+        Self {
+            tint: wgpu::Color::WHITE,
+            opacity: 1.0,
+            flash: wgpu::Color::TRANSPARENT,
+            flip_x: false,
+            flip_y: false,
+        }
+    }
+}
+
+/// Draws with whatever material bind group and buffers are already set.
+pub trait DrawSprite {
+    fn draw_sprites(&mut self, batch: &SpriteBatch);
+}
+
+impl<'a> DrawSprite for wgpu::RenderPass<'a> {
+    fn draw_sprites(&mut self, batch: &SpriteBatch) {
+        self.draw_indexed(0..6, 0, batch.instances.clone());
     }
 }
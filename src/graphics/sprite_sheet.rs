@@ -0,0 +1,212 @@
+use std::collections::HashMap;
+
+/// A region of a texture in 0..1 texture coordinates, with the origin at the top left.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct UvRect {
+    pub x: f32,
+    pub y: f32,
+    pub width: f32,
+    pub height: f32,
+}
+
+impl UvRect {
+    pub const FULL: UvRect = UvRect {
+        x: 0.0,
+        y: 0.0,
+        width: 1.0,
+        height: 1.0,
+    };
+}
+
+/// A region of a texture in pixels, with the origin at the top left.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct PixelRect {
+    pub x: u32,
+    pub y: u32,
+    pub width: u32,
+    pub height: u32,
+}
+
+/// How a texture is cut into equally sized cells.
+#[derive(Clone, Copy, Debug, PartialEq, serde::Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct GridLayout {
+    pub cell_width: u32,
+    pub cell_height: u32,
+    /// Pixels around the outside of the grid.
+    #[serde(default)]
+    pub margin: u32,
+    /// Pixels between neighbouring cells.
+    #[serde(default)]
+    pub spacing: u32,
+}
+
+/**
+ * The frames a material's texture is divided into. Entities pick one by index, named frames can
+ * be looked up with `frame_index`. A plain texture is a sheet with one frame covering all of it.
+ */
+#[derive(Clone, Debug)]
+pub struct SpriteSheet {
+    texture_width: u32,
+    texture_height: u32,
+    region: UvRect,
+    frames: Vec<UvRect>,
+    names: HashMap<String, usize>,
+}
+
+impl SpriteSheet {
+    /// A sheet with no frames yet.
+    pub fn new(texture_width: u32, texture_height: u32) -> Self {
+        Self {
+            texture_width,
+            texture_height,
+            region: UvRect::FULL,
+            frames: Vec::new(),
+            names: HashMap::new(),
+        }
+    }
+
+    /// One frame covering the whole texture.
+    pub fn single(texture_width: u32, texture_height: u32) -> Self {
+        let mut sheet = Self::new(texture_width, texture_height);
+        sheet.frames.push(UvRect::FULL);
+        sheet
+    }
+
+    /// Cuts the texture into cells, numbered left to right then top to bottom.
+    pub fn grid(
+        texture_width: u32,
+        texture_height: u32,
+        layout: GridLayout,
+    ) -> anyhow::Result<Self> {
+        let mut sheet = Self::new(texture_width, texture_height);
+        sheet.add_grid(layout)?;
+        Ok(sheet)
+    }
+
+    /// Appends a frame for every cell of the layout, returning the index of the first.
+    pub fn add_grid(&mut self, layout: GridLayout) -> anyhow::Result<usize> {
+        if layout.cell_width == 0 || layout.cell_height == 0 {
+            anyhow::bail!("Grid cells must be at least one pixel across.");
+        }
+
+        let columns = cells_along(self.texture_width, layout.cell_width, layout);
+        let rows = cells_along(self.texture_height, layout.cell_height, layout);
+
+        if columns == 0 || rows == 0 {
+            anyhow::bail!(
+                "A {}x{} grid cell doesn't fit in a {}x{} texture.",
+                layout.cell_width,
+                layout.cell_height,
+                self.texture_width,
+                self.texture_height,
+            );
+        }
+
+        let first = self.frames.len();
+
+        // Only ever saturated when there's a single cell, so it's multiplied by 0.
+        let step_x = layout.cell_width.saturating_add(layout.spacing);
+        let step_y = layout.cell_height.saturating_add(layout.spacing);
+
+        for row in 0..rows {
+            for column in 0..columns {
+                self.add_frame(PixelRect {
+                    x: layout.margin + column * step_x,
+                    y: layout.margin + row * step_y,
+                    width: layout.cell_width,
+                    height: layout.cell_height,
+                })?;
+            }
+        }
+
+        Ok(first)
+    }
+
+    /// Appends a frame and returns its index.
+    pub fn add_frame(&mut self, rect: PixelRect) -> anyhow::Result<usize> {
+        // Rects come from descriptors, so the edges can be past what a u32 holds.
+        let right = rect.x.checked_add(rect.width);
+        let bottom = rect.y.checked_add(rect.height);
+
+        if right.is_none_or(|right| right > self.texture_width)
+            || bottom.is_none_or(|bottom| bottom > self.texture_height)
+        {
+            anyhow::bail!(
+                "Frame {rect:?} lies outside the {}x{} texture.",
+                self.texture_width,
+                self.texture_height,
+            );
+        }
+
+        let width = self.texture_width as f32;
+        let height = self.texture_height as f32;
+
+        self.frames.push(UvRect {
+            x: rect.x as f32 / width,
+            y: rect.y as f32 / height,
+            width: rect.width as f32 / width,
+            height: rect.height as f32 / height,
+        });
+
+        Ok(self.frames.len() - 1)
+    }
+
+    /// Appends a frame that can be looked up by name, replacing any earlier frame's claim to it.
+    pub fn add_named_frame(&mut self, name: &str, rect: PixelRect) -> anyhow::Result<usize> {
+        let index = self.add_frame(rect)?;
+        self.names.insert(name.to_owned(), index);
+        Ok(index)
+    }
+
+    /// Gives an existing frame a name.
+    pub fn name_frame(&mut self, name: &str, index: usize) -> anyhow::Result<()> {
+        if index >= self.frames.len() {
+            anyhow::bail!(
+                "Can't name frame {index} `{name}`, the sheet only has {} frames.",
+                self.frames.len()
+            );
+        }
+
+        self.names.insert(name.to_owned(), index);
+        Ok(())
+    }
+
+    pub fn frame_index(&self, name: &str) -> Option<usize> {
+        self.names.get(name).copied()
+    }
+
+    /// Where the texture lies on the GPU texture, set for textures packed into an atlas.
+    pub fn set_region(&mut self, region: UvRect) {
+        self.region = region;
+    }
+
+    /// The frame's coordinates on the GPU texture. Frames past the end show the whole texture.
+    pub fn frame(&self, index: usize) -> UvRect {
+        let frame = self.frames.get(index).copied().unwrap_or(UvRect::FULL);
+
+        UvRect {
+            x: self.region.x + frame.x * self.region.width,
+            y: self.region.y + frame.y * self.region.height,
+            width: frame.width * self.region.width,
+            height: frame.height * self.region.height,
+        }
+    }
+
+    pub fn len(&self) -> usize {
+        self.frames.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.frames.is_empty()
+    }
+}
+
+fn cells_along(texture_size: u32, cell_size: u32, layout: GridLayout) -> u32 {
+    // Widened so large margins and spacing from descriptors can't overflow.
+    let usable = u64::from(texture_size).saturating_sub(u64::from(layout.margin) * 2)
+        + u64::from(layout.spacing);
+
+    // At most the texture size, so it fits back in a u32.
+    (usable / (u64::from(cell_size) + u64::from(layout.spacing))) as u32
+}
@@ -1,20 +1,24 @@
+use std::rc::Rc;
+
 use anyhow::*;
 use image::GenericImageView;
 
-use super::Graphics;
+use super::{
+    sprite_sheet::{PixelRect, UvRect},
+    Graphics,
+};
 
-// TODO: This has become just a thin wrapper to wgpu::Texture, not much point in keeping this.
-pub struct Texture {
+/// A texture on the GPU and the bind group sprites sample it through. Atlas pages are shared by
+/// every texture packed into them, so materials on the same page draw without rebinding.
+pub struct TexturePage {
     pub texture: wgpu::Texture,
     pub view: wgpu::TextureView,
+    pub bind_group: wgpu::BindGroup,
+    pub width: u32,
+    pub height: u32,
 }
 
-impl Texture {
-    pub fn from_bytes(graphics: &Graphics, bytes: &[u8], label: &str) -> Result<Self> {
-        let img = image::load_from_memory(bytes)?;
-        Self::from_image(graphics, &img, label)
-    }
-
+impl TexturePage {
     pub fn from_image(graphics: &Graphics, img: &image::DynamicImage, label: &str) -> Result<Self> {
         let buffer = img.to_rgba8();
         let dimensions = img.dimensions();
@@ -26,7 +30,58 @@ impl Texture {
         };
 
         let (texture, view) = graphics.create_texture(label, size, buffer);
+        let bind_group = graphics.create_texture_bind_group(label, &view);
+
+        Ok(Self {
+            texture,
+            view,
+            bind_group,
+            width: dimensions.0,
+            height: dimensions.1,
+        })
+    }
+}
+
+/// An image a material can show, either a page of its own or a region of an atlas page.
+pub struct Texture {
+    pub page: Rc<TexturePage>,
+    pub width: u32,
+    pub height: u32,
+    /// Where the image lies on its page.
+    pub region: UvRect,
+}
+
+impl Texture {
+    pub fn from_bytes(graphics: &Graphics, bytes: &[u8], label: &str) -> Result<Self> {
+        let img = image::load_from_memory(bytes)?;
+        Self::from_image(graphics, &img, label)
+    }
+
+    pub fn from_image(graphics: &Graphics, img: &image::DynamicImage, label: &str) -> Result<Self> {
+        let page = TexturePage::from_image(graphics, img, label)?;
+
+        Ok(Self {
+            width: page.width,
+            height: page.height,
+            page: Rc::new(page),
+            region: UvRect::FULL,
+        })
+    }
+
+    /// A texture covering part of a shared page.
+    pub fn from_region(page: Rc<TexturePage>, rect: PixelRect) -> Self {
+        let region = UvRect {
+            x: rect.x as f32 / page.width as f32,
+            y: rect.y as f32 / page.height as f32,
+            width: rect.width as f32 / page.width as f32,
+            height: rect.height as f32 / page.height as f32,
+        };
 
-        Ok(Self { texture, view })
+        Self {
+            page,
+            width: rect.width,
+            height: rect.height,
+            region,
+        }
     }
 }
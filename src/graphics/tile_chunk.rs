@@ -0,0 +1,118 @@
+use std::collections::HashMap;
+
+use crate::tilemap::{ChunkBounds, Tilemap, CHUNK_SIZE};
+
+use super::{material::Material, sorting_layer::SortingLayer, vertex::SpriteInstance, Graphics};
+
+/// Identifies one chunk of one layer of one of the state's tilemaps.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub struct ChunkId {
+    pub tilemap: usize,
+    pub layer: usize,
+    pub chunk: usize,
+}
+
+/// A tilemap chunk's instances on the GPU, rebuilt only when its tiles change.
+pub struct TileChunk {
+    pub id: ChunkId,
+    pub buffer: wgpu::Buffer,
+    pub instances: u32,
+    pub material: usize,
+    pub sorting_layer: SortingLayer,
+    pub order_in_layer: i32,
+    pub render_layers: u32,
+    pub bounds: ChunkBounds,
+}
+
+/// Every tilemap chunk the renderer has built, looked up by id.
+#[derive(Default)]
+pub struct TileChunks {
+    chunks: Vec<TileChunk>,
+    lookup: HashMap<ChunkId, usize>,
+}
+
+impl TileChunks {
+    pub fn chunks(&self) -> &[TileChunk] {
+        &self.chunks
+    }
+
+    pub fn get(&self, index: usize) -> Option<&TileChunk> {
+        self.chunks.get(index)
+    }
+}
+
+impl Graphics {
+    /// Uploads the chunks of a tilemap whose tiles changed since it was last written.
+    pub fn write_tilemap(&mut self, id: usize, tilemap: &mut Tilemap, materials: &[Material]) {
+        let Some(material) = materials.get(tilemap.material) else {
+            return;
+        };
+
+        for layer_index in 0..tilemap.num_layers() {
+            let layer = tilemap.layer(layer_index).unwrap();
+            let (sorting_layer, order_in_layer) = (layer.sorting_layer, layer.order_in_layer);
+
+            for chunk in 0..tilemap.num_chunks() {
+                let chunk_id = ChunkId {
+                    tilemap: id,
+                    layer: layer_index,
+                    chunk,
+                };
+
+                let index = match self.tile_chunks.lookup.get(&chunk_id) {
+                    Some(&index) => index,
+                    None => {
+                        let buffer = self.device.create_buffer(&wgpu::BufferDescriptor {
+                            label: Some("Tile Chunk Instance Buffer"),
+                            size: (CHUNK_SIZE * CHUNK_SIZE * std::mem::size_of::<SpriteInstance>())
+                                as u64,
+                            usage: wgpu::BufferUsages::VERTEX | wgpu::BufferUsages::COPY_DST,
+                            mapped_at_creation: false,
+                        });
+
+                        self.tile_chunks.chunks.push(TileChunk {
+                            id: chunk_id,
+                            buffer,
+                            instances: 0,
+                            material: tilemap.material,
+                            sorting_layer,
+                            order_in_layer,
+                            render_layers: tilemap.render_layers,
+                            bounds: tilemap.chunk_bounds(chunk),
+                        });
+
+                        let index = self.tile_chunks.chunks.len() - 1;
+                        self.tile_chunks.lookup.insert(chunk_id, index);
+                        index
+                    }
+                };
+
+                let tile_chunk = &mut self.tile_chunks.chunks[index];
+
+                // Cheap to keep current, unlike the instances.
+                tile_chunk.material = tilemap.material;
+                tile_chunk.sorting_layer = sorting_layer;
+                tile_chunk.order_in_layer = order_in_layer;
+                tile_chunk.render_layers = tilemap.render_layers;
+                tile_chunk.bounds = tilemap.chunk_bounds(chunk);
+
+                if !tilemap.is_chunk_dirty(layer_index, chunk) {
+                    continue;
+                }
+
+                let instances = tilemap.chunk_instances(layer_index, chunk, &material.sprite_sheet);
+
+                if !instances.is_empty() {
+                    self.queue.write_buffer(
+                        &tile_chunk.buffer,
+                        0,
+                        bytemuck::cast_slice(&instances),
+                    );
+                }
+
+                tile_chunk.instances = instances.len() as u32;
+                tilemap.clear_dirty(layer_index, chunk);
+            }
+        }
+    }
+}
@@ -1,5 +1,8 @@
 use std::mem;
 
+use super::{sprite::SpriteAppearance, sprite_sheet::UvRect};
+
+/// A corner of the quad every sprite is drawn with.
 #[repr(C)]
 #[derive(Copy, Clone, Debug, bytemuck::Pod, bytemuck::Zeroable)]
 pub struct Vertex {
@@ -7,75 +10,147 @@ pub struct Vertex {
     pub tex_coords: [f32; 2],
 }
 
+impl Vertex {
+    pub fn desc<'a>() -> wgpu::VertexBufferLayout<'a> {
+        wgpu::VertexBufferLayout {
+            array_stride: mem::size_of::<Self>() as wgpu::BufferAddress,
+            step_mode: wgpu::VertexStepMode::Vertex,
+            attributes: &[
+                wgpu::VertexAttribute {
+                    offset: 0,
+                    shader_location: 0,
+                    format: wgpu::VertexFormat::Float32x3,
+                },
+                wgpu::VertexAttribute {
+                    offset: mem::size_of::<[f32; 3]>() as wgpu::BufferAddress,
+                    shader_location: 1,
+                    format: wgpu::VertexFormat::Float32x2,
+                },
+            ],
+        }
+    }
+}
+
+// Bits of `SpriteInstance::flags`, matching `sprite.wgsl`.
+pub const FLIP_X: u32 = 1;
+pub const FLIP_Y: u32 = 2;
+
+/// Everything that differs between one drawn sprite and the next.
 #[repr(C)]
 #[derive(Copy, Clone, Debug, bytemuck::Pod, bytemuck::Zeroable)]
-pub struct RenderVertex {
-    pub position: [f32; 3],
-    pub tex_coords: [f32; 2],
+pub struct SpriteInstance {
     pub transform: [[f32; 4]; 4],
+    /// The part of the texture page shown, as x, y, width and height.
+    pub uv_rect: [f32; 4],
+    /// Multiplied with the sampled color, opacity is already folded into alpha.
+    pub tint: [f32; 4],
+    /// Added over the tinted color, weighted by its own alpha.
+    pub flash: [f32; 4],
+    /// `FLIP_X` and `FLIP_Y`.
+    pub flags: u32,
 }
 
-impl RenderVertex {
-    // TODO: This makes no sense.
+impl SpriteInstance {
     pub fn new(
         position: cgmath::Vector2<f32>,
         rotation: cgmath::Quaternion<f32>,
-        verts: &[Vertex],
-    ) -> Vec<Self> {
-        verts
-            .iter()
-            .map(|v| {
-                let transform = (cgmath::Matrix4::from_translation(cgmath::Vector3 {
-                    x: position.x,
-                    y: position.y,
-                    z: 0.0,
-                }) * cgmath::Matrix4::from(rotation))
-                .into();
+        uv: UvRect,
+        appearance: &SpriteAppearance,
+    ) -> Self {
+        let transform = cgmath::Matrix4::from_translation(cgmath::Vector3 {
+            x: position.x,
+            y: position.y,
+            z: 0.0,
+        }) * cgmath::Matrix4::from(rotation);
+
+        let tint = appearance.tint;
+        let flash = appearance.flash;
+
+        let mut flags = 0;
+
+        if appearance.flip_x {
+            flags |= FLIP_X;
+        }
+
+        if appearance.flip_y {
+            flags |= FLIP_Y;
+        }
 
-                Self {
-                    position: v.position,
-                    tex_coords: v.tex_coords,
-                    transform,
-                }
-            })
-            .collect::<Vec<_>>()
+        Self {
+            transform: transform.into(),
+            uv_rect: [uv.x, uv.y, uv.width, uv.height],
+            tint: [
+                tint.r as f32,
+                tint.g as f32,
+                tint.b as f32,
+                tint.a as f32 * appearance.opacity,
+            ],
+            flash: [
+                flash.r as f32,
+                flash.g as f32,
+                flash.b as f32,
+                flash.a as f32,
+            ],
+            flags,
+        }
+    }
+
+    /// Scales the sprite's quad about its center, sprites are one unit across otherwise.
+    pub fn scaled(mut self, scale: f32) -> Self {
+        for axis in &mut self.transform[..2] {
+            for value in axis.iter_mut() {
+                *value *= scale;
+            }
+        }
+
+        self
     }
 
     pub fn desc<'a>() -> wgpu::VertexBufferLayout<'a> {
         wgpu::VertexBufferLayout {
-            array_stride: std::mem::size_of::<Self>() as wgpu::BufferAddress,
-            step_mode: wgpu::VertexStepMode::Vertex,
+            array_stride: mem::size_of::<Self>() as wgpu::BufferAddress,
+            step_mode: wgpu::VertexStepMode::Instance,
             attributes: &[
                 wgpu::VertexAttribute {
                     offset: 0,
-                    shader_location: 0,
-                    format: wgpu::VertexFormat::Float32x3,
-                },
-                wgpu::VertexAttribute {
-                    offset: std::mem::size_of::<[f32; 3]>() as wgpu::BufferAddress,
-                    shader_location: 1,
-                    format: wgpu::VertexFormat::Float32x2,
-                },
-                wgpu::VertexAttribute {
-                    offset: std::mem::size_of::<[f32; 5]>() as wgpu::BufferAddress,
                     shader_location: 2,
                     format: wgpu::VertexFormat::Float32x4,
                 },
                 wgpu::VertexAttribute {
-                    offset: mem::size_of::<[f32; 9]>() as wgpu::BufferAddress,
+                    offset: mem::size_of::<[f32; 4]>() as wgpu::BufferAddress,
                     shader_location: 3,
                     format: wgpu::VertexFormat::Float32x4,
                 },
                 wgpu::VertexAttribute {
-                    offset: mem::size_of::<[f32; 13]>() as wgpu::BufferAddress,
+                    offset: mem::size_of::<[f32; 8]>() as wgpu::BufferAddress,
                     shader_location: 4,
                     format: wgpu::VertexFormat::Float32x4,
                 },
                 wgpu::VertexAttribute {
-                    offset: mem::size_of::<[f32; 17]>() as wgpu::BufferAddress,
+                    offset: mem::size_of::<[f32; 12]>() as wgpu::BufferAddress,
                     shader_location: 5,
                     format: wgpu::VertexFormat::Float32x4,
                 },
+                wgpu::VertexAttribute {
+                    offset: mem::size_of::<[f32; 16]>() as wgpu::BufferAddress,
+                    shader_location: 6,
+                    format: wgpu::VertexFormat::Float32x4,
+                },
+                wgpu::VertexAttribute {
+                    offset: mem::size_of::<[f32; 20]>() as wgpu::BufferAddress,
+                    shader_location: 7,
+                    format: wgpu::VertexFormat::Float32x4,
+                },
+                wgpu::VertexAttribute {
+                    offset: mem::size_of::<[f32; 24]>() as wgpu::BufferAddress,
+                    shader_location: 8,
+                    format: wgpu::VertexFormat::Float32x4,
+                },
+                wgpu::VertexAttribute {
+                    offset: mem::size_of::<[f32; 28]>() as wgpu::BufferAddress,
+                    shader_location: 9,
+                    format: wgpu::VertexFormat::Uint32,
+                },
             ],
         }
     }
@@ -1,22 +1,55 @@
 use winit::event::*;
 
+#[derive(Clone, Debug, PartialEq)]
+pub enum TextEvent {
+    Insert(String),
+    Preedit(String, Option<(usize, usize)>),
+    Backspace,
+    Delete,
+    CursorLeft,
+    CursorRight,
+    Home,
+    End,
+    Submit,
+    Cancel,
+}
+
 pub struct ReadOnlyInput {
+    clicks: Vec<cgmath::Vector2<f32>>,
     is_down_pressed: bool,
     is_left_pressed: bool,
     is_right_pressed: bool,
     is_up_pressed: bool,
+    is_zoom_in_pressed: bool,
+    is_zoom_out_pressed: bool,
+    text_events: Vec<TextEvent>,
+}
+
+impl Default for ReadOnlyInput {
+    fn default() -> Self {
+        Self::new()
+    }
 }
 
 impl ReadOnlyInput {
     pub fn new() -> Self {
         Self {
+            clicks: Vec::new(),
             is_down_pressed: false,
             is_left_pressed: false,
             is_right_pressed: false,
             is_up_pressed: false,
+            is_zoom_in_pressed: false,
+            is_zoom_out_pressed: false,
+            text_events: Vec::new(),
         }
     }
 
+    /// Where the left mouse button was pressed since the last snapshot, in window pixels.
+    pub fn clicks(&self) -> &[cgmath::Vector2<f32>] {
+        &self.clicks
+    }
+
     pub fn is_down_pressed(&self) -> bool {
         self.is_down_pressed
     }
@@ -32,35 +65,124 @@ impl ReadOnlyInput {
     pub fn is_up_pressed(&self) -> bool {
         self.is_up_pressed
     }
+
+    pub fn is_zoom_in_pressed(&self) -> bool {
+        self.is_zoom_in_pressed
+    }
+
+    pub fn is_zoom_out_pressed(&self) -> bool {
+        self.is_zoom_out_pressed
+    }
+
+    pub fn text_events(&self) -> &[TextEvent] {
+        &self.text_events
+    }
 }
 
 pub struct Input {
+    clicks: Vec<cgmath::Vector2<f32>>,
+    clipboard: Option<arboard::Clipboard>,
+    cursor_position: cgmath::Vector2<f32>,
     is_down_pressed: bool,
     is_left_pressed: bool,
     is_right_pressed: bool,
     is_up_pressed: bool,
+    is_zoom_in_pressed: bool,
+    is_zoom_out_pressed: bool,
+    modifiers: ModifiersState,
+    text_events: Vec<TextEvent>,
+    text_mode: bool,
+}
+
+impl Default for Input {
+    fn default() -> Self {
+        Self::new()
+    }
 }
 
 impl Input {
     pub fn new() -> Self {
         Self {
+            clicks: Vec::new(),
+            // The clipboard is optional, headless sessions simply can't paste.
+            clipboard: arboard::Clipboard::new().ok(),
+            cursor_position: cgmath::Vector2::new(0.0, 0.0),
             is_down_pressed: false,
             is_left_pressed: false,
             is_right_pressed: false,
             is_up_pressed: false,
+            is_zoom_in_pressed: false,
+            is_zoom_out_pressed: false,
+            modifiers: ModifiersState::empty(),
+            text_events: Vec::new(),
+            text_mode: false,
         }
     }
 
-    pub fn to_read_only(&self) -> ReadOnlyInput {
+    /// Snapshots the current key state. Clicks and text events queued since the last snapshot are
+    /// moved into the returned value, so each one is seen by exactly one frame.
+    pub fn take_read_only(&mut self) -> ReadOnlyInput {
         ReadOnlyInput {
+            clicks: std::mem::take(&mut self.clicks),
             is_down_pressed: self.is_down_pressed,
             is_left_pressed: self.is_left_pressed,
             is_right_pressed: self.is_right_pressed,
             is_up_pressed: self.is_up_pressed,
+            is_zoom_in_pressed: self.is_zoom_in_pressed,
+            is_zoom_out_pressed: self.is_zoom_out_pressed,
+            text_events: std::mem::take(&mut self.text_events),
         }
     }
 
+    pub fn is_text_mode(&self) -> bool {
+        self.text_mode
+    }
+
+    /**
+     * While text mode is on, characters and editing keys are routed to the text event queue and
+     * movement keys are released so the player doesn't walk around while typing.
+     */
+    pub fn set_text_mode(&mut self, text_mode: bool) {
+        if text_mode == self.text_mode {
+            return;
+        }
+
+        self.text_mode = text_mode;
+        self.text_events.clear();
+
+        self.is_down_pressed = false;
+        self.is_left_pressed = false;
+        self.is_right_pressed = false;
+        self.is_up_pressed = false;
+        self.is_zoom_in_pressed = false;
+        self.is_zoom_out_pressed = false;
+    }
+
     pub fn handle_event(&mut self, event: &WindowEvent) -> bool {
+        match event {
+            WindowEvent::ModifiersChanged(modifiers) => {
+                self.modifiers = *modifiers;
+                return false;
+            }
+            WindowEvent::CursorMoved { position, .. } => {
+                self.cursor_position = cgmath::Vector2::new(position.x as f32, position.y as f32);
+                return true;
+            }
+            WindowEvent::MouseInput {
+                state: ElementState::Pressed,
+                button: MouseButton::Left,
+                ..
+            } => {
+                self.click(self.cursor_position);
+                return true;
+            }
+            _ => {}
+        }
+
+        if self.text_mode {
+            return self.handle_text_event(event);
+        }
+
         match event {
             WindowEvent::KeyboardInput {
                 input:
@@ -70,29 +192,102 @@ impl Input {
                         ..
                     },
                 ..
-            } => {
-                let is_pressed = *state == ElementState::Pressed;
+            } => self.set_key(*keycode, *state == ElementState::Pressed),
+            _ => false,
+        }
+    }
 
-                match keycode {
-                    VirtualKeyCode::W | VirtualKeyCode::Up => {
-                        self.is_up_pressed = is_pressed;
-                        true
-                    }
-                    VirtualKeyCode::A | VirtualKeyCode::Left => {
-                        self.is_left_pressed = is_pressed;
-                        true
-                    }
-                    VirtualKeyCode::S | VirtualKeyCode::Down => {
-                        self.is_down_pressed = is_pressed;
-                        true
-                    }
-                    VirtualKeyCode::D | VirtualKeyCode::Right => {
-                        self.is_right_pressed = is_pressed;
-                        true
-                    }
-                    _ => false,
+    /// Queues a left click at a point in window pixels directly, without a window event.
+    pub fn click(&mut self, position: cgmath::Vector2<f32>) {
+        self.clicks.push(position);
+    }
+
+    /// Applies a key press or release directly, without a window event. Returns whether the key is
+    /// bound to anything.
+    pub fn set_key(&mut self, keycode: VirtualKeyCode, is_pressed: bool) -> bool {
+        match keycode {
+            VirtualKeyCode::W | VirtualKeyCode::Up => {
+                self.is_up_pressed = is_pressed;
+                true
+            }
+            VirtualKeyCode::A | VirtualKeyCode::Left => {
+                self.is_left_pressed = is_pressed;
+                true
+            }
+            VirtualKeyCode::S | VirtualKeyCode::Down => {
+                self.is_down_pressed = is_pressed;
+                true
+            }
+            VirtualKeyCode::D | VirtualKeyCode::Right => {
+                self.is_right_pressed = is_pressed;
+                true
+            }
+            VirtualKeyCode::Equals | VirtualKeyCode::NumpadAdd => {
+                self.is_zoom_in_pressed = is_pressed;
+                true
+            }
+            VirtualKeyCode::Minus | VirtualKeyCode::NumpadSubtract => {
+                self.is_zoom_out_pressed = is_pressed;
+                true
+            }
+            _ => false,
+        }
+    }
+
+    fn handle_text_event(&mut self, event: &WindowEvent) -> bool {
+        match event {
+            WindowEvent::ReceivedCharacter(c) => {
+                // Control characters (backspace, return, ctrl+v, ...) arrive here too, but those are
+                // handled from their key events below.
+                if !c.is_control() && !self.modifiers.ctrl() && !self.modifiers.logo() {
+                    self.text_events.push(TextEvent::Insert(c.to_string()));
+                }
+
+                true
+            }
+            WindowEvent::Ime(ime) => {
+                match ime {
+                    Ime::Preedit(text, cursor) => self
+                        .text_events
+                        .push(TextEvent::Preedit(text.clone(), *cursor)),
+                    Ime::Commit(text) => self.text_events.push(TextEvent::Insert(text.clone())),
+                    Ime::Enabled | Ime::Disabled => {}
                 }
+
+                true
+            }
+            WindowEvent::KeyboardInput {
+                input:
+                    KeyboardInput {
+                        state: ElementState::Pressed,
+                        virtual_keycode: Some(keycode),
+                        ..
+                    },
+                ..
+            } => {
+                let text_event = match keycode {
+                    VirtualKeyCode::Back => TextEvent::Backspace,
+                    VirtualKeyCode::Delete => TextEvent::Delete,
+                    VirtualKeyCode::Left => TextEvent::CursorLeft,
+                    VirtualKeyCode::Right => TextEvent::CursorRight,
+                    VirtualKeyCode::Home => TextEvent::Home,
+                    VirtualKeyCode::End => TextEvent::End,
+                    VirtualKeyCode::Return | VirtualKeyCode::NumpadEnter => TextEvent::Submit,
+                    VirtualKeyCode::Escape => TextEvent::Cancel,
+                    VirtualKeyCode::V if self.modifiers.ctrl() || self.modifiers.logo() => {
+                        match self.clipboard.as_mut().and_then(|c| c.get_text().ok()) {
+                            Some(text) => TextEvent::Insert(text),
+                            None => return true,
+                        }
+                    }
+                    _ => return true,
+                };
+
+                self.text_events.push(text_event);
+                true
             }
+            // Key releases are swallowed too so nothing leaks through to the game while typing.
+            WindowEvent::KeyboardInput { .. } => true,
             _ => false,
         }
     }
@@ -1,14 +1,16 @@
-mod camera;
-mod components;
-mod config;
-mod entity;
-mod graphics;
-mod input;
-mod parsing;
-mod physics;
-mod resources;
-mod state;
-mod ui;
+pub mod animation;
+pub mod camera;
+pub mod components;
+pub mod config;
+pub mod entity;
+pub mod graphics;
+pub mod input;
+pub mod parsing;
+pub mod physics;
+pub mod resources;
+pub mod state;
+pub mod tilemap;
+pub mod ui;
 
 use cgmath::prelude::*;
 use dotenv::dotenv;
@@ -18,8 +20,9 @@ use winit::{
     window::WindowBuilder,
 };
 
+use camera::CameraFollow;
 use components::player_movement::PlayerMovement;
-use graphics::{material, sorting_layer};
+use graphics::{material, pixel_perfect::PixelPerfect};
 use input::Input;
 use physics::collider;
 use resources::Resource;
@@ -39,21 +42,29 @@ pub async fn run() {
         }
     };
 
-    let ball_texture = Resource::load_texture(&state.graphics, "ball.png")
-        .await
-        .unwrap();
+    if state.config().pixel_perfect() {
+        state.set_pixel_perfect(Some(PixelPerfect {
+            reference_width: 320,
+            reference_height: 180,
+            pixels_per_unit: 16.0,
+        }));
+    }
+
+    let graphics = state.graphics.as_ref().unwrap();
+
+    let ball_texture = Resource::load_texture(graphics, "ball.png").await.unwrap();
 
-    let ball_material =
-        material::Material::new(String::from("ball"), &state.graphics, ball_texture);
+    let ball_material = material::Material::new(String::from("ball"), ball_texture);
 
     let ball_mat_id = state.add_material(ball_material);
 
+    let foreground = state.sorting_layer("foreground").unwrap();
+
     let mut player = entity::Entity::create(
-        state.num_entities(),
         cgmath::Vector2::zero(),
         cgmath::Quaternion::zero(),
         ball_mat_id,
-        sorting_layer::SortingLayer::Foreground,
+        foreground,
         Some(collider::Collider {
             origin: cgmath::Vector2::zero(),
             width: 1.0,
@@ -62,26 +73,40 @@ pub async fn run() {
     );
 
     player.add_component(Box::new(PlayerMovement { walk_speed: 35.0 }));
-    state.add_entity(player);
+    let player_index = state.add_entity(player).unwrap();
 
-    state.add_entity(entity::Entity::create(
-        state.num_entities() + 1,
-        cgmath::Vector2 { x: 3.0, y: 1.0 },
-        cgmath::Quaternion::zero(),
-        ball_mat_id,
-        sorting_layer::SortingLayer::Foreground,
-        Some(collider::Collider {
-            origin: cgmath::Vector2::zero(),
-            width: 1.0,
-            height: 1.0,
-        }),
-    ));
+    state.cameras[0].follow(CameraFollow {
+        target: player_index,
+        damping: 6.0,
+        dead_zone: cgmath::Vector2::new(1.0, 0.75),
+    });
+
+    state
+        .add_entity(entity::Entity::create(
+            cgmath::Vector2 { x: 3.0, y: 1.0 },
+            cgmath::Quaternion::zero(),
+            ball_mat_id,
+            foreground,
+            Some(collider::Collider {
+                origin: cgmath::Vector2::zero(),
+                width: 1.0,
+                height: 1.0,
+            }),
+        ))
+        .unwrap();
 
     let mut input = Input::new();
 
     event_loop.run(move |event, _, control_flow| match event {
         Event::RedrawRequested(window_id) if window_id == window.id() => {
-            state.update(input.to_read_only());
+            state.update(input.take_read_only());
+
+            let text_mode = state.is_text_input_active();
+
+            if text_mode != input.is_text_mode() {
+                input.set_text_mode(text_mode);
+                window.set_ime_allowed(text_mode);
+            }
 
             match state.render() {
                 Ok(_) => {}
@@ -94,27 +119,36 @@ pub async fn run() {
         Event::WindowEvent {
             ref event,
             window_id,
-        } if window_id == window.id() => {
-            if !input.handle_event(event) {
-                match event {
-                    WindowEvent::CloseRequested
-                    | WindowEvent::KeyboardInput {
-                        input:
-                            KeyboardInput {
-                                state: ElementState::Pressed,
-                                virtual_keycode: Some(VirtualKeyCode::Escape),
-                                ..
-                            },
+        } if window_id == window.id() && !input.handle_event(event) => match event {
+            WindowEvent::CloseRequested
+            | WindowEvent::KeyboardInput {
+                input:
+                    KeyboardInput {
+                        state: ElementState::Pressed,
+                        virtual_keycode: Some(VirtualKeyCode::Escape),
+                        ..
+                    },
+                ..
+            } => *control_flow = ControlFlow::Exit,
+            WindowEvent::KeyboardInput {
+                input:
+                    KeyboardInput {
+                        state: ElementState::Pressed,
+                        virtual_keycode: Some(VirtualKeyCode::F12),
                         ..
-                    } => *control_flow = ControlFlow::Exit,
-                    WindowEvent::Resized(physical_size) => state.resize(*physical_size),
-                    WindowEvent::ScaleFactorChanged { new_inner_size, .. } => {
-                        state.resize(**new_inner_size)
-                    }
-                    _ => {}
+                    },
+                ..
+            } => {
+                if let Err(e) = state.save_screenshot() {
+                    eprintln!("{:?}", e);
                 }
             }
-        }
+            WindowEvent::Resized(physical_size) => state.resize(*physical_size),
+            WindowEvent::ScaleFactorChanged { new_inner_size, .. } => {
+                state.resize(**new_inner_size)
+            }
+            _ => {}
+        },
         _ => {}
     });
 }
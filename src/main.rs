@@ -1,5 +1,4 @@
 use fooheppy::run;
-use pollster;
 
 fn main() {
     pollster::block_on(run());
@@ -0,0 +1,166 @@
+use std::rc::Rc;
+
+use anyhow::Context;
+use serde::Deserialize;
+
+use crate::{
+    graphics::{
+        material::Material,
+        pipeline::BlendMode,
+        shader::{MaterialShader, ShaderParam, ShaderValue},
+        Graphics,
+    },
+    resources::Resource,
+};
+
+use super::sprite_sheet::LoadSpriteSheet;
+
+/**
+ * A material under `res/materials`, optionally drawn with a custom shader under `res/shaders`,
+ * for example:
+ *
+ * ```json
+ * {
+ *     "texture": "sheet.json",
+ *     "normal_map": "normals/sheet.png",
+ *     "blend": "additive",
+ *     "shader": "dissolve.wgsl",
+ *     "params": [
+ *         { "name": "amount", "type": "float", "value": 0.4 },
+ *         { "name": "edge_color", "type": "color", "value": [1.0, 0.5, 0.0, 1.0] },
+ *         { "name": "noise", "type": "texture", "value": "effects/noise.png" }
+ *     ]
+ * }
+ * ```
+ *
+ * The texture is a sprite sheet descriptor or an image under `res/textures`. The blend mode
+ * defaults to `alpha`, and parameters need a shader. Texture parameters are images under
+ * `res/textures` too, loaded on their own rather than from an atlas so shaders can sample the
+ * whole image. So is the normal map, which has to be the size of the texture, see `NormalMap`.
+ */
+#[derive(Deserialize)]
+#[serde(deny_unknown_fields)]
+struct MaterialDescriptor {
+    texture: String,
+    #[serde(default)]
+    normal_map: Option<String>,
+    #[serde(default)]
+    blend: BlendMode,
+    #[serde(default)]
+    shader: Option<String>,
+    #[serde(default)]
+    params: Vec<ParamDescriptor>,
+}
+
+#[derive(Deserialize)]
+#[serde(tag = "type", rename_all = "snake_case", deny_unknown_fields)]
+enum ParamDescriptor {
+    Float {
+        name: String,
+        value: f32,
+    },
+    Vector2 {
+        name: String,
+        value: [f32; 2],
+    },
+    Vector4 {
+        name: String,
+        value: [f32; 4],
+    },
+    /// RGBA in 0..1.
+    Color {
+        name: String,
+        value: [f64; 4],
+    },
+    Texture {
+        name: String,
+        value: String,
+    },
+}
+
+pub trait LoadMaterial {
+    fn load_material(graphics: &Graphics, file_name: &str) -> anyhow::Result<Material>;
+}
+
+impl LoadMaterial for Resource {
+    fn load_material(graphics: &Graphics, file_name: &str) -> anyhow::Result<Material> {
+        let path = Self::build_path(Some("materials"), file_name);
+
+        let source = std::fs::read_to_string(&path)
+            .with_context(|| format!("Couldn't read material {file_name}"))?;
+
+        let descriptor: MaterialDescriptor = serde_json::from_str(&source)
+            .with_context(|| format!("Invalid material {file_name}"))?;
+
+        let mut material = if descriptor.texture.ends_with(".json") {
+            Self::load_sprite_sheet(graphics, &descriptor.texture)?
+        } else {
+            let texture = Self::read_texture(graphics, &descriptor.texture)
+                .with_context(|| format!("Couldn't read texture {}", descriptor.texture))?;
+
+            Material::new(descriptor.texture.clone(), texture)
+        };
+
+        material.name = file_name.to_owned();
+        material.blend_mode = descriptor.blend;
+
+        if let Some(normal_map) = &descriptor.normal_map {
+            let texture = &material.diffuse_texture;
+
+            let normal_map = Resource::read_normal_map(graphics, normal_map, texture.region)
+                .with_context(|| format!("Couldn't read normal map {normal_map}"))?;
+
+            if (normal_map.width, normal_map.height) != (texture.width, texture.height) {
+                anyhow::bail!(
+                    "Invalid material {file_name}: the normal map is {}x{} but the texture is {}x{}.",
+                    normal_map.width,
+                    normal_map.height,
+                    texture.width,
+                    texture.height
+                );
+            }
+
+            material.normal_map = Some(normal_map);
+        }
+
+        let Some(shader) = descriptor.shader else {
+            if !descriptor.params.is_empty() {
+                anyhow::bail!("Invalid material {file_name}: parameters need a shader.");
+            }
+
+            return Ok(material);
+        };
+
+        let params = descriptor
+            .params
+            .into_iter()
+            .map(|param| create_param(graphics, param))
+            .collect::<anyhow::Result<_>>()
+            .with_context(|| format!("Invalid material {file_name}"))?;
+
+        let shader = MaterialShader::new(graphics, &shader, params)
+            .with_context(|| format!("Couldn't create material {file_name}"))?;
+
+        Ok(material.with_shader(shader))
+    }
+}
+
+fn create_param(graphics: &Graphics, param: ParamDescriptor) -> anyhow::Result<ShaderParam> {
+    let (name, value) = match param {
+        ParamDescriptor::Float { name, value } => (name, ShaderValue::Float(value)),
+        ParamDescriptor::Vector2 { name, value } => (name, ShaderValue::Vector2(value.into())),
+        ParamDescriptor::Vector4 { name, value } => (name, ShaderValue::Vector4(value.into())),
+        ParamDescriptor::Color {
+            name,
+            value: [r, g, b, a],
+        } => (name, ShaderValue::Color(wgpu::Color { r, g, b, a })),
+        ParamDescriptor::Texture { name, value } => {
+            let page = Resource::read_texture_page(graphics, &value)
+                .with_context(|| format!("Couldn't read texture {value} for parameter {name}"))?;
+
+            (name, ShaderValue::Texture(Rc::new(page)))
+        }
+    };
+
+    Ok(ShaderParam { name, value })
+}
@@ -1,2 +1,6 @@
+pub mod material;
 pub mod nml;
+pub mod sorting_layers;
+pub mod sprite_sheet;
 pub mod styles;
+pub mod tiled;
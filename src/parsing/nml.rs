@@ -2,7 +2,7 @@ use roxmltree::{Children, Document, Node, NodeType};
 
 use crate::{
     resources::Resource,
-    ui::{element::Element, element_kind::ElementKind},
+    ui::{element::Element, element_kind::ElementKind, text_field::TextField},
 };
 
 pub trait LoadNml {
@@ -63,10 +63,22 @@ fn create_element(node: Node, element_count: &mut usize) -> Result<Element, ()>
     let render_id = *element_count;
     *element_count += 1;
 
-    Ok(Element::new(
+    let mut element = Element::new(
         render_id,
-        None,
+        node.attribute("id").map(str::to_owned),
         node.tag_name().name().to_owned(),
         children,
-    ))
+    );
+
+    if element.tag_name == "input" {
+        let mut text_field = TextField::new();
+
+        if let Some(value) = node.attribute("value") {
+            text_field.set_value(value);
+        }
+
+        element.text_field = Some(text_field);
+    }
+
+    Ok(element)
 }
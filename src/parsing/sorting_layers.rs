@@ -0,0 +1,44 @@
+use anyhow::Context;
+use serde::Deserialize;
+
+use crate::{
+    graphics::sorting_layer::{SortingLayerSettings, SortingLayers},
+    resources::Resource,
+};
+
+/**
+ * A list of sorting layers under `res/data`, drawn first to last, for example:
+ *
+ * ```json
+ * {
+ *     "layers": [
+ *         { "name": "ground" },
+ *         { "name": "characters", "y_sort": true },
+ *         { "name": "effects" }
+ *     ]
+ * }
+ * ```
+ */
+#[derive(Deserialize)]
+#[serde(deny_unknown_fields)]
+struct SortingLayersDescriptor {
+    layers: Vec<SortingLayerSettings>,
+}
+
+pub trait LoadSortingLayers {
+    fn load_sorting_layers(file_name: &str) -> anyhow::Result<SortingLayers>;
+}
+
+impl LoadSortingLayers for Resource {
+    fn load_sorting_layers(file_name: &str) -> anyhow::Result<SortingLayers> {
+        let path = Self::build_path(Some("data"), file_name);
+
+        let source = std::fs::read_to_string(&path)
+            .with_context(|| format!("Couldn't read sorting layers {}", path.display()))?;
+
+        let descriptor: SortingLayersDescriptor = serde_json::from_str(&source)
+            .with_context(|| format!("Invalid sorting layers {}", path.display()))?;
+
+        SortingLayers::new(descriptor.layers)
+    }
+}
@@ -0,0 +1,118 @@
+use std::collections::HashMap;
+
+use anyhow::Context;
+use serde::Deserialize;
+
+use crate::{
+    graphics::{
+        material::Material,
+        sprite_sheet::{GridLayout, PixelRect, SpriteSheet},
+        Graphics,
+    },
+    resources::Resource,
+};
+
+/**
+ * A sprite sheet descriptor under `res/textures`, for example:
+ *
+ * ```json
+ * {
+ *     "texture": "characters.png",
+ *     "grid": { "cell_width": 16, "cell_height": 16, "margin": 0, "spacing": 0 },
+ *     "names": { "idle": 0, "jump": 5 },
+ *     "regions": [{ "name": "banner", "x": 0, "y": 32, "width": 48, "height": 16 }]
+ * }
+ * ```
+ *
+ * Grid cells come first, numbered left to right then top to bottom, followed by the regions in
+ * the order they're listed. Every part is optional except the texture.
+ */
+#[derive(Deserialize)]
+#[serde(deny_unknown_fields)]
+struct SpriteSheetDescriptor {
+    texture: String,
+    #[serde(default)]
+    grid: Option<GridLayout>,
+    #[serde(default)]
+    names: HashMap<String, usize>,
+    #[serde(default)]
+    regions: Vec<RegionDescriptor>,
+}
+
+#[derive(Deserialize)]
+#[serde(deny_unknown_fields)]
+struct RegionDescriptor {
+    name: String,
+    x: u32,
+    y: u32,
+    width: u32,
+    height: u32,
+}
+
+pub trait LoadSpriteSheet {
+    fn load_sprite_sheet(graphics: &Graphics, file_name: &str) -> anyhow::Result<Material>;
+}
+
+impl LoadSpriteSheet for Resource {
+    /// Loads a descriptor and the texture it names into a material whose frames it describes.
+    fn load_sprite_sheet(graphics: &Graphics, file_name: &str) -> anyhow::Result<Material> {
+        let path = Self::build_path(Some("textures"), file_name);
+
+        let source = std::fs::read_to_string(&path)
+            .with_context(|| format!("Couldn't read sprite sheet {file_name}"))?;
+
+        let descriptor: SpriteSheetDescriptor = serde_json::from_str(&source)
+            .with_context(|| format!("Invalid sprite sheet {file_name}"))?;
+
+        let texture = Self::read_texture(graphics, &descriptor.texture).with_context(|| {
+            format!(
+                "Couldn't read texture {} for sprite sheet {file_name}",
+                descriptor.texture
+            )
+        })?;
+
+        let mut sprite_sheet = create_sprite_sheet(&descriptor, texture.width, texture.height)
+            .with_context(|| format!("Invalid sprite sheet {file_name}"))?;
+        sprite_sheet.set_region(texture.region);
+
+        let mut material = Material::new(file_name.to_owned(), texture);
+        material.sprite_sheet = sprite_sheet;
+
+        Ok(material)
+    }
+}
+
+fn create_sprite_sheet(
+    descriptor: &SpriteSheetDescriptor,
+    texture_width: u32,
+    texture_height: u32,
+) -> anyhow::Result<SpriteSheet> {
+    let mut sheet = SpriteSheet::new(texture_width, texture_height);
+
+    if let Some(layout) = descriptor.grid {
+        sheet.add_grid(layout)?;
+    }
+
+    for (name, index) in &descriptor.names {
+        sheet.name_frame(name, *index)?;
+    }
+
+    for region in &descriptor.regions {
+        let rect = PixelRect {
+            x: region.x,
+            y: region.y,
+            width: region.width,
+            height: region.height,
+        };
+
+        sheet
+            .add_named_frame(&region.name, rect)
+            .with_context(|| format!("In region `{}`", region.name))?;
+    }
+
+    if sheet.is_empty() {
+        anyhow::bail!("Sprite sheets need a grid or at least one region.");
+    }
+
+    Ok(sheet)
+}
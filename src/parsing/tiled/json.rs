@@ -0,0 +1,344 @@
+use std::path::Path;
+
+use anyhow::Context;
+use serde::Deserialize;
+use serde_json::Value;
+
+use crate::animation::clip::AnimationFrame;
+
+use super::{
+    read_relative, texture_name, TiledLayer, TiledMap, TiledObject, TiledObjectLayer,
+    TiledProperties, TiledProperty, TiledShape, TiledTile, TiledTileLayer, TiledTileset,
+};
+
+// Only the parts of Tiled's format the engine uses, everything else is ignored.
+
+#[derive(Deserialize)]
+struct MapDescriptor {
+    width: usize,
+    height: usize,
+    tilewidth: u32,
+    tileheight: u32,
+    #[serde(default)]
+    infinite: bool,
+    #[serde(default)]
+    layers: Vec<LayerDescriptor>,
+    #[serde(default)]
+    tilesets: Vec<TilesetDescriptor>,
+    #[serde(default)]
+    properties: Vec<PropertyDescriptor>,
+}
+
+#[derive(Deserialize)]
+struct TilesetDescriptor {
+    #[serde(default)]
+    firstgid: u32,
+    /// External tilesets only have a source and their first id in the map.
+    #[serde(default)]
+    source: Option<String>,
+    #[serde(default)]
+    name: String,
+    #[serde(default)]
+    image: Option<String>,
+    #[serde(default)]
+    tilewidth: u32,
+    #[serde(default)]
+    tileheight: u32,
+    #[serde(default)]
+    margin: u32,
+    #[serde(default)]
+    spacing: u32,
+    #[serde(default)]
+    tilecount: u32,
+    #[serde(default)]
+    tiles: Vec<TileDescriptor>,
+}
+
+#[derive(Deserialize)]
+struct TileDescriptor {
+    id: u32,
+    #[serde(default)]
+    animation: Vec<FrameDescriptor>,
+    #[serde(default)]
+    properties: Vec<PropertyDescriptor>,
+}
+
+#[derive(Deserialize)]
+struct FrameDescriptor {
+    tileid: usize,
+    /// Milliseconds.
+    duration: u32,
+}
+
+#[derive(Deserialize)]
+struct LayerDescriptor {
+    #[serde(rename = "type")]
+    kind: String,
+    #[serde(default)]
+    name: String,
+    #[serde(default)]
+    data: Option<Value>,
+    #[serde(default)]
+    encoding: Option<String>,
+    #[serde(default)]
+    objects: Vec<ObjectDescriptor>,
+    #[serde(default)]
+    layers: Vec<LayerDescriptor>,
+    #[serde(default)]
+    properties: Vec<PropertyDescriptor>,
+}
+
+#[derive(Deserialize)]
+struct ObjectDescriptor {
+    id: u32,
+    #[serde(default)]
+    name: String,
+    #[serde(default, rename = "type")]
+    kind: String,
+    #[serde(default)]
+    class: String,
+    x: f32,
+    y: f32,
+    #[serde(default)]
+    width: f32,
+    #[serde(default)]
+    height: f32,
+    #[serde(default)]
+    gid: Option<u32>,
+    #[serde(default)]
+    point: bool,
+    #[serde(default)]
+    ellipse: bool,
+    #[serde(default)]
+    polygon: Option<Value>,
+    #[serde(default)]
+    polyline: Option<Value>,
+    #[serde(default)]
+    properties: Vec<PropertyDescriptor>,
+}
+
+#[derive(Deserialize)]
+struct PropertyDescriptor {
+    name: String,
+    #[serde(default = "default_property_type", rename = "type")]
+    kind: String,
+    value: Value,
+}
+
+fn default_property_type() -> String {
+    "string".to_owned()
+}
+
+pub(super) fn parse_map(source: &str, path: &Path) -> anyhow::Result<TiledMap> {
+    let descriptor: MapDescriptor = serde_json::from_str(source)?;
+
+    if descriptor.infinite {
+        anyhow::bail!("Infinite maps aren't supported.");
+    }
+
+    let mut tilesets = Vec::new();
+
+    for tileset in &descriptor.tilesets {
+        tilesets.push(create_tileset(tileset, path)?);
+    }
+
+    let mut layers = Vec::new();
+    create_layers(&descriptor.layers, &mut layers)?;
+
+    let mut map = TiledMap {
+        width: descriptor.width,
+        height: descriptor.height,
+        tile_width: descriptor.tilewidth,
+        tile_height: descriptor.tileheight,
+        tilesets,
+        layers,
+        properties: create_properties(&descriptor.properties)?,
+    };
+
+    map.sort_tilesets();
+    map.check_layer_sizes()?;
+
+    Ok(map)
+}
+
+fn create_tileset(descriptor: &TilesetDescriptor, path: &Path) -> anyhow::Result<TiledTileset> {
+    let Some(source) = &descriptor.source else {
+        return create_embedded_tileset(descriptor, descriptor.firstgid, path)
+            .with_context(|| format!("In tileset `{}`", descriptor.name));
+    };
+
+    let (tileset_path, tileset_source) = read_relative(path, source)?;
+
+    if source.ends_with(".tsx") {
+        super::tmx::parse_tileset(&tileset_source, &tileset_path, descriptor.firstgid)
+    } else {
+        parse_tileset(&tileset_source, &tileset_path, descriptor.firstgid)
+    }
+    .with_context(|| format!("Invalid tileset {}", tileset_path.display()))
+}
+
+/// Reads an external `.tsj` tileset.
+pub(super) fn parse_tileset(
+    source: &str,
+    path: &Path,
+    first_gid: u32,
+) -> anyhow::Result<TiledTileset> {
+    let descriptor: TilesetDescriptor = serde_json::from_str(source)?;
+    create_embedded_tileset(&descriptor, first_gid, path)
+}
+
+fn create_embedded_tileset(
+    descriptor: &TilesetDescriptor,
+    first_gid: u32,
+    path: &Path,
+) -> anyhow::Result<TiledTileset> {
+    let Some(image) = &descriptor.image else {
+        anyhow::bail!("Tilesets need a single image, image collections aren't supported.");
+    };
+
+    let mut tiles = std::collections::HashMap::new();
+
+    for tile in &descriptor.tiles {
+        let animation = tile
+            .animation
+            .iter()
+            .map(|frame| AnimationFrame {
+                frame: frame.tileid,
+                duration: frame.duration as f32 / 1000.0,
+            })
+            .collect();
+
+        let properties =
+            create_properties(&tile.properties).with_context(|| format!("In tile {}", tile.id))?;
+
+        tiles.insert(
+            tile.id,
+            TiledTile {
+                animation,
+                properties,
+            },
+        );
+    }
+
+    Ok(TiledTileset {
+        first_gid,
+        name: descriptor.name.clone(),
+        image: texture_name(path, image)?,
+        tile_width: descriptor.tilewidth,
+        tile_height: descriptor.tileheight,
+        margin: descriptor.margin,
+        spacing: descriptor.spacing,
+        tile_count: descriptor.tilecount,
+        tiles,
+    })
+}
+
+fn create_layers(
+    descriptors: &[LayerDescriptor],
+    layers: &mut Vec<TiledLayer>,
+) -> anyhow::Result<()> {
+    for descriptor in descriptors {
+        match descriptor.kind.as_str() {
+            "tilelayer" => layers.push(TiledLayer::Tiles(
+                create_tile_layer(descriptor)
+                    .with_context(|| format!("In tile layer `{}`", descriptor.name))?,
+            )),
+            "objectgroup" => layers.push(TiledLayer::Objects(
+                create_object_layer(descriptor)
+                    .with_context(|| format!("In object layer `{}`", descriptor.name))?,
+            )),
+            "group" => create_layers(&descriptor.layers, layers)?,
+            other => anyhow::bail!(
+                "Layer `{}` is a {other}, which isn't supported.",
+                descriptor.name
+            ),
+        }
+    }
+
+    Ok(())
+}
+
+fn create_tile_layer(descriptor: &LayerDescriptor) -> anyhow::Result<TiledTileLayer> {
+    if let Some(encoding) = &descriptor.encoding {
+        if encoding != "csv" {
+            anyhow::bail!("Tile data is {encoding} encoded, save the map with CSV tile layers.");
+        }
+    }
+
+    let tiles = match &descriptor.data {
+        Some(data) => {
+            serde_json::from_value(data.clone()).context("Tile data must be a list of tile ids")?
+        }
+        None => anyhow::bail!("Tile layers need data, chunked layers aren't supported."),
+    };
+
+    Ok(TiledTileLayer {
+        name: descriptor.name.clone(),
+        tiles,
+        properties: create_properties(&descriptor.properties)?,
+    })
+}
+
+fn create_object_layer(descriptor: &LayerDescriptor) -> anyhow::Result<TiledObjectLayer> {
+    let mut objects = Vec::new();
+
+    for object in &descriptor.objects {
+        let shape = if object.point {
+            TiledShape::Point
+        } else if object.ellipse {
+            TiledShape::Ellipse
+        } else if object.polygon.is_some() || object.polyline.is_some() {
+            TiledShape::Polygon
+        } else {
+            TiledShape::Rectangle
+        };
+
+        let class = if object.class.is_empty() {
+            &object.kind
+        } else {
+            &object.class
+        };
+
+        objects.push(TiledObject {
+            id: object.id,
+            name: object.name.clone(),
+            class: class.clone(),
+            x: object.x,
+            y: object.y,
+            width: object.width,
+            height: object.height,
+            shape,
+            gid: object.gid,
+            properties: create_properties(&object.properties)
+                .with_context(|| format!("In object {}", object.id))?,
+        });
+    }
+
+    Ok(TiledObjectLayer {
+        name: descriptor.name.clone(),
+        objects,
+        properties: create_properties(&descriptor.properties)?,
+    })
+}
+
+fn create_properties(descriptors: &[PropertyDescriptor]) -> anyhow::Result<TiledProperties> {
+    let mut properties = TiledProperties::default();
+
+    for descriptor in descriptors {
+        let property = match &descriptor.value {
+            Value::String(value) => TiledProperty::parse(&descriptor.kind, value),
+            Value::Bool(_) | Value::Number(_) => {
+                TiledProperty::parse(&descriptor.kind, &descriptor.value.to_string())
+            }
+            _ => Err(anyhow::anyhow!(
+                "Properties of type `{}` aren't supported.",
+                descriptor.kind
+            )),
+        }
+        .with_context(|| format!("In property `{}`", descriptor.name))?;
+
+        properties.insert(&descriptor.name, property);
+    }
+
+    Ok(properties)
+}
@@ -0,0 +1,340 @@
+mod json;
+mod spawn;
+mod tmx;
+
+use std::{
+    collections::HashMap,
+    path::{Component, Path, PathBuf},
+};
+
+use anyhow::Context;
+
+use crate::{animation::clip::AnimationFrame, resources::Resource};
+
+pub use spawn::{ComponentFactory, SpawnedMap, TiledComponents};
+
+const FLIPPED_HORIZONTALLY: u32 = 0x8000_0000;
+const FLIPPED_VERTICALLY: u32 = 0x4000_0000;
+const FLIPPED_DIAGONALLY: u32 = 0x2000_0000;
+const ROTATED_HEXAGONAL: u32 = 0x1000_0000;
+
+/**
+ * A map made in Tiled, loaded from `res/maps` by `load_tiled_map` and placed in a state with
+ * `spawn`. Both the JSON (`.tmj`, `.json`) and XML (`.tmx`) formats are read, with embedded or
+ * external tilesets. Tile layers must be stored as CSV or plain arrays, not base64.
+ */
+#[derive(Clone, Debug)]
+pub struct TiledMap {
+    /// In tiles.
+    pub width: usize,
+    pub height: usize,
+    /// Pixels per tile, one tile is one world unit.
+    pub tile_width: u32,
+    pub tile_height: u32,
+    pub tilesets: Vec<TiledTileset>,
+    /// Bottom to top, with group layers flattened.
+    pub layers: Vec<TiledLayer>,
+    pub properties: TiledProperties,
+}
+
+#[derive(Clone, Debug)]
+pub struct TiledTileset {
+    pub first_gid: u32,
+    pub name: String,
+    /// Path under `res/textures`.
+    pub image: String,
+    pub tile_width: u32,
+    pub tile_height: u32,
+    pub margin: u32,
+    pub spacing: u32,
+    pub tile_count: u32,
+    /// Tiles with animations or properties, by their id within the tileset.
+    pub tiles: HashMap<u32, TiledTile>,
+}
+
+#[derive(Clone, Debug, Default)]
+pub struct TiledTile {
+    /// Frames are ids within the same tileset.
+    pub animation: Vec<AnimationFrame>,
+    pub properties: TiledProperties,
+}
+
+#[derive(Clone, Debug)]
+pub enum TiledLayer {
+    Tiles(TiledTileLayer),
+    Objects(TiledObjectLayer),
+}
+
+#[derive(Clone, Debug)]
+pub struct TiledTileLayer {
+    pub name: String,
+    /// Global tile ids with their flip bits, row by row from the top. Zero is an empty cell.
+    pub tiles: Vec<u32>,
+    pub properties: TiledProperties,
+}
+
+#[derive(Clone, Debug)]
+pub struct TiledObjectLayer {
+    pub name: String,
+    pub objects: Vec<TiledObject>,
+    pub properties: TiledProperties,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum TiledShape {
+    Rectangle,
+    Ellipse,
+    Point,
+    /// Polygons and polylines, their points aren't kept.
+    Polygon,
+}
+
+/// An object as Tiled stores it, in pixels from the top left of the map.
+#[derive(Clone, Debug)]
+pub struct TiledObject {
+    pub id: u32,
+    pub name: String,
+    /// The object's class, or type in older versions of Tiled. Picks its component.
+    pub class: String,
+    pub x: f32,
+    pub y: f32,
+    pub width: f32,
+    pub height: f32,
+    pub shape: TiledShape,
+    /// Set for tile objects, with the same flip bits as tile layers.
+    pub gid: Option<u32>,
+    pub properties: TiledProperties,
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub enum TiledProperty {
+    Bool(bool),
+    Int(i64),
+    Float(f64),
+    /// Strings, colors and file paths.
+    String(String),
+}
+
+/// Custom properties set in Tiled.
+#[derive(Clone, Debug, Default)]
+pub struct TiledProperties(HashMap<String, TiledProperty>);
+
+impl TiledProperties {
+    pub fn get(&self, name: &str) -> Option<&TiledProperty> {
+        self.0.get(name)
+    }
+
+    pub fn get_bool(&self, name: &str) -> Option<bool> {
+        match self.get(name)? {
+            TiledProperty::Bool(value) => Some(*value),
+            _ => None,
+        }
+    }
+
+    pub fn get_int(&self, name: &str) -> Option<i64> {
+        match self.get(name)? {
+            TiledProperty::Int(value) => Some(*value),
+            _ => None,
+        }
+    }
+
+    /// Int properties read as floats too.
+    pub fn get_float(&self, name: &str) -> Option<f64> {
+        match self.get(name)? {
+            TiledProperty::Float(value) => Some(*value),
+            TiledProperty::Int(value) => Some(*value as f64),
+            _ => None,
+        }
+    }
+
+    pub fn get_str(&self, name: &str) -> Option<&str> {
+        match self.get(name)? {
+            TiledProperty::String(value) => Some(value),
+            _ => None,
+        }
+    }
+
+    pub fn insert(&mut self, name: &str, property: TiledProperty) {
+        self.0.insert(name.to_owned(), property);
+    }
+
+    pub fn len(&self) -> usize {
+        self.0.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.0.is_empty()
+    }
+}
+
+impl TiledProperty {
+    /// Reads a value written as text, with one of Tiled's property types.
+    fn parse(property_type: &str, value: &str) -> anyhow::Result<Self> {
+        Ok(match property_type {
+            "bool" => Self::Bool(
+                value
+                    .parse()
+                    .with_context(|| format!("`{value}` isn't a bool"))?,
+            ),
+            "int" | "object" => Self::Int(
+                value
+                    .parse()
+                    .with_context(|| format!("`{value}` isn't an int"))?,
+            ),
+            "float" => Self::Float(
+                value
+                    .parse()
+                    .with_context(|| format!("`{value}` isn't a float"))?,
+            ),
+            "string" | "color" | "file" => Self::String(value.to_owned()),
+            other => anyhow::bail!("Properties of type `{other}` aren't supported."),
+        })
+    }
+}
+
+/// A tile id with its flip bits split off.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct TiledGid {
+    pub gid: u32,
+    pub flip_x: bool,
+    pub flip_y: bool,
+}
+
+impl TiledGid {
+    /// `None` for empty cells. Rotated tiles are stored as diagonal flips, which can't be drawn.
+    pub fn decode(raw: u32) -> anyhow::Result<Option<Self>> {
+        if raw & (FLIPPED_DIAGONALLY | ROTATED_HEXAGONAL) != 0 {
+            anyhow::bail!(
+                "Tile {} is rotated, only horizontal and vertical flips are supported.",
+                raw & !(FLIPPED_HORIZONTALLY | FLIPPED_VERTICALLY | FLIPPED_DIAGONALLY)
+            );
+        }
+
+        let gid = raw & !(FLIPPED_HORIZONTALLY | FLIPPED_VERTICALLY);
+
+        if gid == 0 {
+            return Ok(None);
+        }
+
+        Ok(Some(Self {
+            gid,
+            flip_x: raw & FLIPPED_HORIZONTALLY != 0,
+            flip_y: raw & FLIPPED_VERTICALLY != 0,
+        }))
+    }
+}
+
+impl TiledMap {
+    /// Reads a JSON map. `file_name` is where it lives under `res/maps`, external tilesets and
+    /// images are found relative to it.
+    pub fn from_json(source: &str, file_name: &str) -> anyhow::Result<Self> {
+        json::parse_map(source, &Path::new("maps").join(file_name))
+            .with_context(|| format!("Invalid Tiled map {file_name}"))
+    }
+
+    /// Reads a TMX map, see `from_json`.
+    pub fn from_tmx(source: &str, file_name: &str) -> anyhow::Result<Self> {
+        tmx::parse_map(source, &Path::new("maps").join(file_name))
+            .with_context(|| format!("Invalid Tiled map {file_name}"))
+    }
+
+    /// The tileset a global tile id belongs to, and the tile's id within it.
+    pub fn tileset_for(&self, gid: u32) -> Option<(usize, u32)> {
+        let (index, tileset) = self
+            .tilesets
+            .iter()
+            .enumerate()
+            .filter(|(_, tileset)| tileset.first_gid <= gid)
+            .max_by_key(|(_, tileset)| tileset.first_gid)?;
+
+        let id = gid - tileset.first_gid;
+        (id < tileset.tile_count).then_some((index, id))
+    }
+
+    // Tiled lists embedded and external tilesets in any order.
+    fn sort_tilesets(&mut self) {
+        self.tilesets.sort_by_key(|tileset| tileset.first_gid);
+    }
+
+    fn check_layer_sizes(&self) -> anyhow::Result<()> {
+        for layer in &self.layers {
+            if let TiledLayer::Tiles(layer) = layer {
+                if layer.tiles.len() != self.width * self.height {
+                    anyhow::bail!(
+                        "Tile layer `{}` has {} tiles, the {}x{} map needs {}.",
+                        layer.name,
+                        layer.tiles.len(),
+                        self.width,
+                        self.height,
+                        self.width * self.height
+                    );
+                }
+            }
+        }
+
+        Ok(())
+    }
+}
+
+pub trait LoadTiledMap {
+    fn load_tiled_map(file_name: &str) -> anyhow::Result<TiledMap>;
+}
+
+impl LoadTiledMap for Resource {
+    /// Loads a map from `res/maps`, TMX if the file ends in `.tmx` and JSON otherwise.
+    fn load_tiled_map(file_name: &str) -> anyhow::Result<TiledMap> {
+        let path = Self::build_path(Some("maps"), file_name);
+
+        let source = std::fs::read_to_string(&path)
+            .with_context(|| format!("Couldn't read Tiled map {file_name}"))?;
+
+        if file_name.ends_with(".tmx") {
+            TiledMap::from_tmx(&source, file_name)
+        } else {
+            TiledMap::from_json(&source, file_name)
+        }
+    }
+}
+
+/// Reads a file Tiled refers to, given relative to the file referring to it.
+fn read_relative(from: &Path, relative: &str) -> anyhow::Result<(PathBuf, String)> {
+    let path = resolve(from, relative);
+    let full_path = Resource::build_path(None, &path.to_string_lossy());
+
+    let source = std::fs::read_to_string(full_path)
+        .with_context(|| format!("Couldn't read {}", path.display()))?;
+
+    Ok((path, source))
+}
+
+/// Tiled stores image paths relative to the file using them, textures are named from `res/textures`.
+fn texture_name(from: &Path, image: &str) -> anyhow::Result<String> {
+    let path = resolve(from, image);
+
+    let name = path.strip_prefix("textures").map_err(|_| {
+        anyhow::anyhow!(
+            "Tileset image {image} must be under res/textures, it's at res/{}",
+            path.display()
+        )
+    })?;
+
+    Ok(name.to_string_lossy().replace('\\', "/"))
+}
+
+// Joins a path onto the directory of `from`, both relative to `res`, folding away any `..`.
+fn resolve(from: &Path, relative: &str) -> PathBuf {
+    let joined = from.parent().unwrap_or(Path::new("")).join(relative);
+    let mut resolved = PathBuf::new();
+
+    for component in joined.components() {
+        match component {
+            Component::ParentDir => {
+                resolved.pop();
+            }
+            Component::Normal(part) => resolved.push(part),
+            _ => {}
+        }
+    }
+
+    resolved
+}
@@ -0,0 +1,379 @@
+use std::collections::HashMap;
+
+use anyhow::Context;
+use cgmath::Zero;
+
+use crate::{
+    components::Component,
+    entity::Entity,
+    graphics::{
+        material::Material,
+        sorting_layer::SortingLayer,
+        sprite_sheet::{GridLayout, SpriteSheet},
+    },
+    physics::{
+        collider::Collider,
+        collision_grid::{CollisionGrid, TileCollision},
+    },
+    resources::Resource,
+    state::State,
+    tilemap::{Tile, Tilemap},
+};
+
+use super::{
+    TiledGid, TiledLayer, TiledMap, TiledObject, TiledProperties, TiledShape, TiledTileLayer,
+    TiledTileset,
+};
+
+/// Builds the component for an object from its properties.
+pub type ComponentFactory = fn(&TiledObject) -> anyhow::Result<Box<dyn Component>>;
+
+/// Which component each object class gets when a map is spawned.
+#[derive(Default)]
+pub struct TiledComponents {
+    factories: HashMap<String, ComponentFactory>,
+}
+
+impl TiledComponents {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn register(&mut self, class: &str, factory: ComponentFactory) {
+        self.factories.insert(class.to_owned(), factory);
+    }
+
+    pub fn get(&self, class: &str) -> Option<ComponentFactory> {
+        self.factories.get(class).copied()
+    }
+}
+
+/// What `TiledMap::spawn` added to the state.
+#[derive(Debug, Default)]
+pub struct SpawnedMap {
+    pub materials: Vec<usize>,
+    pub tilemaps: Vec<usize>,
+    pub collision_grids: Vec<usize>,
+    pub entities: Vec<usize>,
+}
+
+impl TiledMap {
+    /**
+     * Adds the map to a state with its bottom left corner at `position`. Tile layers become
+     * layers of one tilemap per tileset. Objects become entities centered on their rectangle,
+     * tile objects show their tile and every other object is hidden. Rectangles and ellipses get
+     * a collider, and objects whose class is registered in `components` get that component.
+     * Tile layers whose `collision` property is set also add a collision grid, see
+     * `tile_collision`.
+     *
+     * Layers are drawn in the sorting layer named by their `sorting_layer` property. Tile layers
+     * default to the first sorting layer and object layers to the last. Object rotation isn't
+     * applied. Without a renderer no tileset is loaded and everything refers to material 0.
+     */
+    pub fn spawn(
+        &self,
+        state: &mut State,
+        position: cgmath::Vector2<f32>,
+        components: &TiledComponents,
+    ) -> anyhow::Result<SpawnedMap> {
+        let mut spawned = SpawnedMap::default();
+
+        let materials = self.load_materials(state)?;
+        spawned.materials = materials.iter().flatten().copied().collect();
+
+        let mut tilemaps: Vec<Option<Tilemap>> = self.tilesets.iter().map(|_| None).collect();
+
+        for (order, layer) in self.layers.iter().enumerate() {
+            match layer {
+                TiledLayer::Tiles(layer) => {
+                    let sorting_layer = self
+                        .sorting_layer(state, &layer.properties, true)
+                        .with_context(|| format!("In tile layer `{}`", layer.name))?;
+
+                    if layer.properties.get_bool("collision") == Some(true) {
+                        let grid = self
+                            .create_collision_grid(layer, position)
+                            .with_context(|| format!("In tile layer `{}`", layer.name))?;
+
+                        spawned.collision_grids.push(state.add_collision_grid(grid));
+                    }
+
+                    // The layer's index in each tileset's tilemap, added once it's used.
+                    let mut layer_indices: Vec<Option<usize>> =
+                        self.tilesets.iter().map(|_| None).collect();
+
+                    for (i, &raw) in layer.tiles.iter().enumerate() {
+                        let Some((tileset, tile)) = self
+                            .decode_tile(raw)
+                            .with_context(|| format!("In tile layer `{}`", layer.name))?
+                        else {
+                            continue;
+                        };
+
+                        let tilemap = tilemaps[tileset].get_or_insert_with(|| {
+                            self.create_tilemap(tileset, materials[tileset].unwrap_or(0), position)
+                        });
+
+                        let index = *layer_indices[tileset].get_or_insert_with(|| {
+                            let index = tilemap.add_layer(&layer.name, sorting_layer);
+                            tilemap.set_layer_sorting(index, sorting_layer, order as i32);
+                            index
+                        });
+
+                        // Tiled counts rows down from the top.
+                        let x = i % self.width;
+                        let y = self.height - 1 - i / self.width;
+
+                        tilemap.set_tile(index, x, y, Some(tile))?;
+                    }
+                }
+                TiledLayer::Objects(layer) => {
+                    let sorting_layer = self
+                        .sorting_layer(state, &layer.properties, false)
+                        .with_context(|| format!("In object layer `{}`", layer.name))?;
+
+                    for object in &layer.objects {
+                        let mut entity = self
+                            .create_entity(object, &materials, position, sorting_layer, components)
+                            .with_context(|| {
+                                format!("In object {} of layer `{}`", object.id, layer.name)
+                            })?;
+
+                        entity.set_order_in_layer(order as i32);
+                        spawned.entities.push(state.add_entity(entity)?);
+                    }
+                }
+            }
+        }
+
+        for tilemap in tilemaps.into_iter().flatten() {
+            spawned.tilemaps.push(state.add_tilemap(tilemap));
+        }
+
+        Ok(spawned)
+    }
+
+    // A material per tileset, cut into its tiles. `None` for all of them without a renderer.
+    fn load_materials(&self, state: &mut State) -> anyhow::Result<Vec<Option<usize>>> {
+        let mut materials = Vec::new();
+
+        for tileset in &self.tilesets {
+            let Some(graphics) = &state.graphics else {
+                materials.push(None);
+                continue;
+            };
+
+            let material = load_tileset(graphics, tileset)
+                .with_context(|| format!("Couldn't load tileset `{}`", tileset.name))?;
+
+            materials.push(Some(state.add_material(material)));
+        }
+
+        Ok(materials)
+    }
+
+    fn create_tilemap(
+        &self,
+        tileset: usize,
+        material: usize,
+        position: cgmath::Vector2<f32>,
+    ) -> Tilemap {
+        let mut tilemap = Tilemap::new(self.width, self.height, material, position);
+
+        for (id, tile) in &self.tilesets[tileset].tiles {
+            if !tile.animation.is_empty() {
+                tilemap.animate_tile(*id as usize, tile.animation.clone());
+            }
+        }
+
+        tilemap
+    }
+
+    // The tileset a cell's tile comes from, and the tile. `None` for empty cells.
+    fn decode_tile(&self, raw: u32) -> anyhow::Result<Option<(usize, Tile)>> {
+        let Some(decoded) = TiledGid::decode(raw)? else {
+            return Ok(None);
+        };
+
+        let (tileset, id) = self
+            .tileset_for(decoded.gid)
+            .ok_or_else(|| anyhow::anyhow!("Tile {} isn't in any tileset.", decoded.gid))?;
+
+        Ok(Some((
+            tileset,
+            Tile {
+                index: id as usize,
+                flip_x: decoded.flip_x,
+                flip_y: decoded.flip_y,
+            },
+        )))
+    }
+
+    // Cells take their collision from the `collision` property of the tile in them.
+    fn create_collision_grid(
+        &self,
+        layer: &TiledTileLayer,
+        position: cgmath::Vector2<f32>,
+    ) -> anyhow::Result<CollisionGrid> {
+        let mut cells = vec![TileCollision::Empty; self.width * self.height];
+
+        for (i, &raw) in layer.tiles.iter().enumerate() {
+            let Some((tileset, tile)) = self.decode_tile(raw)? else {
+                continue;
+            };
+
+            let Some(properties) = self.tilesets[tileset]
+                .tiles
+                .get(&(tile.index as u32))
+                .map(|tile| &tile.properties)
+            else {
+                continue;
+            };
+
+            let collision = tile_collision(properties, tile.flip_x).with_context(|| {
+                format!(
+                    "In tile {} of tileset `{}`",
+                    tile.index, self.tilesets[tileset].name
+                )
+            })?;
+
+            // Tiled counts rows down from the top.
+            cells[(self.height - 1 - i / self.width) * self.width + i % self.width] = collision;
+        }
+
+        CollisionGrid::from_cells(self.width, self.height, position, cells)
+    }
+
+    fn sorting_layer(
+        &self,
+        state: &State,
+        properties: &TiledProperties,
+        tiles: bool,
+    ) -> anyhow::Result<SortingLayer> {
+        match properties.get_str("sorting_layer") {
+            Some(name) => state.sorting_layer(name),
+            None if tiles => Ok(state.sorting_layers().first()),
+            None => Ok(state.sorting_layers().last()),
+        }
+    }
+
+    fn create_entity(
+        &self,
+        object: &TiledObject,
+        materials: &[Option<usize>],
+        position: cgmath::Vector2<f32>,
+        sorting_layer: SortingLayer,
+        components: &TiledComponents,
+    ) -> anyhow::Result<Entity> {
+        let tile = object
+            .gid
+            .map(|gid| self.decode_tile(gid))
+            .transpose()?
+            .flatten();
+
+        let width = object.width / self.tile_width as f32;
+        let height = object.height / self.tile_height as f32;
+        let left = object.x / self.tile_width as f32;
+
+        // Rectangles hang down from their position, tile objects sit on top of it.
+        let top = if tile.is_some() {
+            object.y / self.tile_height as f32 - height
+        } else {
+            object.y / self.tile_height as f32
+        };
+
+        let center = position
+            + cgmath::Vector2::new(left + width / 2.0, self.height as f32 - top - height / 2.0);
+
+        let collider = matches!(object.shape, TiledShape::Rectangle | TiledShape::Ellipse)
+            .then_some(Collider {
+                origin: cgmath::Vector2::zero(),
+                width,
+                height,
+            })
+            .filter(|_| width > 0.0 && height > 0.0);
+
+        let material = tile
+            .and_then(|(tileset, _)| materials[tileset])
+            .unwrap_or(0);
+
+        let mut entity = Entity::create(
+            center,
+            cgmath::Quaternion::zero(),
+            material,
+            sorting_layer,
+            collider,
+        );
+
+        match tile {
+            Some((_, tile)) => {
+                entity.set_frame(tile.index);
+                entity.appearance.flip_x = tile.flip_x;
+                entity.appearance.flip_y = tile.flip_y;
+            }
+            // No camera draws an entity in no render layers.
+            None => entity.set_render_layers(0),
+        }
+
+        if let Some(factory) = components.get(&object.class) {
+            let component = factory(object)
+                .with_context(|| format!("Couldn't build a `{}` component", object.class))?;
+
+            entity.add_component(component);
+        }
+
+        Ok(entity)
+    }
+}
+
+/**
+ * Reads a tile's `collision` property: `solid`, `one_way` or `slope`. Slopes give the heights of
+ * their left and right edges in `slope_left` and `slope_right`, as fractions of the tile, and
+ * swap them when the tile is flipped horizontally.
+ */
+fn tile_collision(properties: &TiledProperties, flip_x: bool) -> anyhow::Result<TileCollision> {
+    Ok(match properties.get_str("collision") {
+        None => TileCollision::Empty,
+        Some("solid") => TileCollision::Solid,
+        Some("one_way") => TileCollision::OneWay,
+        Some("slope") => {
+            let left = properties.get_float("slope_left").unwrap_or(0.0) as f32;
+            let right = properties.get_float("slope_right").unwrap_or(0.0) as f32;
+
+            if flip_x {
+                TileCollision::Slope {
+                    left: right,
+                    right: left,
+                }
+            } else {
+                TileCollision::Slope { left, right }
+            }
+        }
+        Some(other) => {
+            anyhow::bail!("Unknown collision `{other}`, expected `solid`, `one_way` or `slope`.")
+        }
+    })
+}
+
+fn load_tileset(
+    graphics: &crate::graphics::Graphics,
+    tileset: &TiledTileset,
+) -> anyhow::Result<Material> {
+    let texture = Resource::read_texture(graphics, &tileset.image)
+        .with_context(|| format!("Couldn't read texture {}", tileset.image))?;
+
+    let layout = GridLayout {
+        cell_width: tileset.tile_width,
+        cell_height: tileset.tile_height,
+        margin: tileset.margin,
+        spacing: tileset.spacing,
+    };
+
+    let mut sprite_sheet = SpriteSheet::grid(texture.width, texture.height, layout)?;
+    sprite_sheet.set_region(texture.region);
+
+    let mut material = Material::new(tileset.name.clone(), texture);
+    material.sprite_sheet = sprite_sheet;
+
+    Ok(material)
+}
@@ -0,0 +1,326 @@
+use std::{collections::HashMap, fmt::Display, path::Path, str::FromStr};
+
+use anyhow::Context;
+use roxmltree::{Document, Node};
+
+use crate::animation::clip::AnimationFrame;
+
+use super::{
+    read_relative, texture_name, TiledLayer, TiledMap, TiledObject, TiledObjectLayer,
+    TiledProperties, TiledProperty, TiledShape, TiledTile, TiledTileLayer, TiledTileset,
+};
+
+pub(super) fn parse_map(source: &str, path: &Path) -> anyhow::Result<TiledMap> {
+    let doc = Document::parse(source)?;
+    let root = doc.root_element();
+
+    if !root.has_tag_name("map") {
+        return Err(error_at(root, "Expected a <map> element."));
+    }
+
+    if parse_or(root, "infinite", 0u32)? != 0 {
+        return Err(error_at(root, "Infinite maps aren't supported."));
+    }
+
+    let mut tilesets = Vec::new();
+    let mut properties = TiledProperties::default();
+
+    for child in root.children().filter(Node::is_element) {
+        match child.tag_name().name() {
+            "tileset" => tilesets.push(create_map_tileset(child, path)?),
+            "properties" => properties = create_properties(child)?,
+            _ => {}
+        }
+    }
+
+    let mut layers = Vec::new();
+    create_layers(root, &mut layers)?;
+
+    let mut map = TiledMap {
+        width: parse(root, "width")?,
+        height: parse(root, "height")?,
+        tile_width: parse(root, "tilewidth")?,
+        tile_height: parse(root, "tileheight")?,
+        tilesets,
+        layers,
+        properties,
+    };
+
+    map.sort_tilesets();
+    map.check_layer_sizes()?;
+
+    Ok(map)
+}
+
+/// Reads an external `.tsx` tileset.
+pub(super) fn parse_tileset(
+    source: &str,
+    path: &Path,
+    first_gid: u32,
+) -> anyhow::Result<TiledTileset> {
+    let doc = Document::parse(source)?;
+    let root = doc.root_element();
+
+    if !root.has_tag_name("tileset") {
+        return Err(error_at(root, "Expected a <tileset> element."));
+    }
+
+    create_tileset(root, first_gid, path)
+}
+
+fn create_map_tileset(node: Node, path: &Path) -> anyhow::Result<TiledTileset> {
+    let first_gid = parse(node, "firstgid")?;
+
+    let Some(source) = node.attribute("source") else {
+        return create_tileset(node, first_gid, path);
+    };
+
+    let (tileset_path, tileset_source) = read_relative(path, source).with_context(|| at(node))?;
+
+    if source.ends_with(".tsx") {
+        parse_tileset(&tileset_source, &tileset_path, first_gid)
+    } else {
+        super::json::parse_tileset(&tileset_source, &tileset_path, first_gid)
+    }
+    .with_context(|| format!("Invalid tileset {}", tileset_path.display()))
+}
+
+fn create_tileset(node: Node, first_gid: u32, path: &Path) -> anyhow::Result<TiledTileset> {
+    let mut image = None;
+    let mut tiles = HashMap::new();
+
+    for child in node.children().filter(Node::is_element) {
+        match child.tag_name().name() {
+            "image" => {
+                let source = attribute(child, "source")?;
+                image = Some(texture_name(path, source).with_context(|| at(child))?);
+            }
+            "tile" => {
+                tiles.insert(parse(child, "id")?, create_tile(child)?);
+            }
+            _ => {}
+        }
+    }
+
+    let Some(image) = image else {
+        return Err(error_at(
+            node,
+            "Tilesets need a single image, image collections aren't supported.",
+        ));
+    };
+
+    Ok(TiledTileset {
+        first_gid,
+        name: node.attribute("name").unwrap_or_default().to_owned(),
+        image,
+        tile_width: parse(node, "tilewidth")?,
+        tile_height: parse(node, "tileheight")?,
+        margin: parse_or(node, "margin", 0)?,
+        spacing: parse_or(node, "spacing", 0)?,
+        tile_count: parse(node, "tilecount")?,
+        tiles,
+    })
+}
+
+fn create_tile(node: Node) -> anyhow::Result<TiledTile> {
+    let mut tile = TiledTile::default();
+
+    for child in node.children().filter(Node::is_element) {
+        match child.tag_name().name() {
+            "properties" => tile.properties = create_properties(child)?,
+            "animation" => {
+                for frame in child.children().filter(|n| n.has_tag_name("frame")) {
+                    let duration: u32 = parse(frame, "duration")?;
+
+                    tile.animation.push(AnimationFrame {
+                        frame: parse(frame, "tileid")?,
+                        duration: duration as f32 / 1000.0,
+                    });
+                }
+            }
+            _ => {}
+        }
+    }
+
+    Ok(tile)
+}
+
+fn create_layers(node: Node, layers: &mut Vec<TiledLayer>) -> anyhow::Result<()> {
+    for child in node.children().filter(Node::is_element) {
+        match child.tag_name().name() {
+            "layer" => layers.push(TiledLayer::Tiles(create_tile_layer(child)?)),
+            "objectgroup" => layers.push(TiledLayer::Objects(create_object_layer(child)?)),
+            "group" => create_layers(child, layers)?,
+            "imagelayer" => return Err(error_at(child, "Image layers aren't supported.")),
+            _ => {}
+        }
+    }
+
+    Ok(())
+}
+
+fn create_tile_layer(node: Node) -> anyhow::Result<TiledTileLayer> {
+    let mut tiles = Vec::new();
+    let mut properties = TiledProperties::default();
+
+    for child in node.children().filter(Node::is_element) {
+        match child.tag_name().name() {
+            "properties" => properties = create_properties(child)?,
+            "data" => tiles = create_tile_data(child)?,
+            _ => {}
+        }
+    }
+
+    Ok(TiledTileLayer {
+        name: node.attribute("name").unwrap_or_default().to_owned(),
+        tiles,
+        properties,
+    })
+}
+
+fn create_tile_data(node: Node) -> anyhow::Result<Vec<u32>> {
+    if node.children().any(|child| child.has_tag_name("chunk")) {
+        return Err(error_at(node, "Chunked tile layers aren't supported."));
+    }
+
+    match node.attribute("encoding") {
+        Some("csv") => node
+            .text()
+            .unwrap_or_default()
+            .split(',')
+            .map(|id| {
+                let id = id.trim();
+                id.parse()
+                    .map_err(|_| error_at(node, format!("`{id}` isn't a tile id.")))
+            })
+            .collect(),
+        Some(encoding) => Err(error_at(
+            node,
+            format!("Tile data is {encoding} encoded, save the map with CSV tile layers."),
+        )),
+        None => node
+            .children()
+            .filter(|child| child.has_tag_name("tile"))
+            .map(|tile| parse_or(tile, "gid", 0))
+            .collect(),
+    }
+}
+
+fn create_object_layer(node: Node) -> anyhow::Result<TiledObjectLayer> {
+    let mut objects = Vec::new();
+    let mut properties = TiledProperties::default();
+
+    for child in node.children().filter(Node::is_element) {
+        match child.tag_name().name() {
+            "properties" => properties = create_properties(child)?,
+            "object" => objects.push(create_object(child)?),
+            _ => {}
+        }
+    }
+
+    Ok(TiledObjectLayer {
+        name: node.attribute("name").unwrap_or_default().to_owned(),
+        objects,
+        properties,
+    })
+}
+
+fn create_object(node: Node) -> anyhow::Result<TiledObject> {
+    let mut shape = TiledShape::Rectangle;
+    let mut properties = TiledProperties::default();
+
+    for child in node.children().filter(Node::is_element) {
+        match child.tag_name().name() {
+            "properties" => properties = create_properties(child)?,
+            "point" => shape = TiledShape::Point,
+            "ellipse" => shape = TiledShape::Ellipse,
+            "polygon" | "polyline" => shape = TiledShape::Polygon,
+            _ => {}
+        }
+    }
+
+    let class = node
+        .attribute("class")
+        .or_else(|| node.attribute("type"))
+        .unwrap_or_default();
+
+    Ok(TiledObject {
+        id: parse(node, "id")?,
+        name: node.attribute("name").unwrap_or_default().to_owned(),
+        class: class.to_owned(),
+        x: parse(node, "x")?,
+        y: parse(node, "y")?,
+        width: parse_or(node, "width", 0.0)?,
+        height: parse_or(node, "height", 0.0)?,
+        shape,
+        gid: node
+            .attribute("gid")
+            .map(|_| parse(node, "gid"))
+            .transpose()?,
+        properties,
+    })
+}
+
+fn create_properties(node: Node) -> anyhow::Result<TiledProperties> {
+    let mut properties = TiledProperties::default();
+
+    for property in node.children().filter(|n| n.has_tag_name("property")) {
+        let name = attribute(property, "name")?;
+        let kind = property.attribute("type").unwrap_or("string");
+
+        // Multi-line strings are stored as the element's text.
+        let value = property
+            .attribute("value")
+            .or_else(|| property.text())
+            .unwrap_or_default();
+
+        let value = TiledProperty::parse(kind, value)
+            .with_context(|| format!("In property `{name}`"))
+            .with_context(|| at(property))?;
+
+        properties.insert(name, value);
+    }
+
+    Ok(properties)
+}
+
+// Where a node starts in the source, for error messages.
+fn at(node: Node) -> String {
+    let position = node.document().text_pos_at(node.position());
+    format!("At line {}, column {}", position.row, position.col)
+}
+
+fn error_at(node: Node, message: impl Display) -> anyhow::Error {
+    anyhow::anyhow!("{message}").context(at(node))
+}
+
+fn attribute<'a>(node: Node<'a, '_>, name: &str) -> anyhow::Result<&'a str> {
+    node.attribute(name).ok_or_else(|| {
+        error_at(
+            node,
+            format!("<{}> is missing `{name}`.", node.tag_name().name()),
+        )
+    })
+}
+
+fn parse<T: FromStr>(node: Node, name: &str) -> anyhow::Result<T> {
+    let value = attribute(node, name)?;
+
+    value.parse().map_err(|_| {
+        error_at(
+            node,
+            format!(
+                "`{value}` isn't a valid `{name}` for <{}>.",
+                node.tag_name().name()
+            ),
+        )
+    })
+}
+
+fn parse_or<T: FromStr>(node: Node, name: &str, default: T) -> anyhow::Result<T> {
+    if node.has_attribute(name) {
+        parse(node, name)
+    } else {
+        Ok(default)
+    }
+}
@@ -0,0 +1,319 @@
+use crate::tilemap::{Tile, Tilemap};
+
+use super::collider::Collider;
+
+// Colliders resting exactly against a surface count as touching it.
+const SKIN: f32 = 0.001;
+
+/// How one cell of a `CollisionGrid` blocks movement.
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub enum TileCollision {
+    #[default]
+    Empty,
+    Solid,
+    /// Only blocks movement down onto its top.
+    OneWay,
+    /// Solid below a line between the heights of its left and right edges, as fractions of a
+    /// cell. Colliders are lifted onto the line but can always move sideways into the cell.
+    Slope {
+        left: f32,
+        right: f32,
+    },
+}
+
+/// Where `CollisionGrid::move_collider` left a collider and what stopped it.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct CollisionMove {
+    pub position: cgmath::Vector2<f32>,
+    pub hit_x: bool,
+    pub hit_y: bool,
+    /// Standing on a solid or one-way cell or a slope.
+    pub grounded: bool,
+}
+
+/**
+ * A static collision layer laid over a grid of one unit cells, with cell (0, 0) at the bottom
+ * left like a `Tilemap`. Neighbouring solid cells are merged into as few rectangles as possible,
+ * and one-way cells into rows, so queries don't look at every cell.
+ */
+pub struct CollisionGrid {
+    position: cgmath::Vector2<f32>,
+    width: usize,
+    height: usize,
+    cells: Vec<TileCollision>,
+    solid: Vec<Collider>,
+    one_way: Vec<Collider>,
+}
+
+impl CollisionGrid {
+    /// `position` is the world position of the bottom left corner of the grid.
+    pub fn new(width: usize, height: usize, position: cgmath::Vector2<f32>) -> Self {
+        Self {
+            position,
+            width,
+            height,
+            cells: vec![TileCollision::Empty; width * height],
+            solid: Vec::new(),
+            one_way: Vec::new(),
+        }
+    }
+
+    /// A grid built from its cells, row by row from the bottom.
+    pub fn from_cells(
+        width: usize,
+        height: usize,
+        position: cgmath::Vector2<f32>,
+        cells: Vec<TileCollision>,
+    ) -> anyhow::Result<Self> {
+        if cells.len() != width * height {
+            anyhow::bail!(
+                "A {width}x{height} collision grid needs {} cells, not {}.",
+                width * height,
+                cells.len()
+            );
+        }
+
+        let mut grid = Self::new(width, height, position);
+        grid.cells = cells;
+        grid.merge();
+
+        Ok(grid)
+    }
+
+    /// A grid covering a tilemap, with each cell of one of its layers flagged by `collision`.
+    pub fn from_tilemap(
+        tilemap: &Tilemap,
+        layer: usize,
+        collision: impl Fn(Tile) -> TileCollision,
+    ) -> Self {
+        let mut grid = Self::new(tilemap.width(), tilemap.height(), tilemap.position());
+
+        for y in 0..grid.height {
+            for x in 0..grid.width {
+                if let Some(tile) = tilemap.get_tile(layer, x, y) {
+                    grid.cells[y * grid.width + x] = collision(tile);
+                }
+            }
+        }
+
+        grid.merge();
+        grid
+    }
+
+    pub fn width(&self) -> usize {
+        self.width
+    }
+
+    pub fn height(&self) -> usize {
+        self.height
+    }
+
+    pub fn position(&self) -> cgmath::Vector2<f32> {
+        self.position
+    }
+
+    pub fn get(&self, x: usize, y: usize) -> TileCollision {
+        if x >= self.width || y >= self.height {
+            return TileCollision::Empty;
+        }
+
+        self.cells[y * self.width + x]
+    }
+
+    /// Changes one cell and re-merges the grid.
+    pub fn set(&mut self, x: usize, y: usize, collision: TileCollision) -> anyhow::Result<()> {
+        if x >= self.width || y >= self.height {
+            anyhow::bail!(
+                "Cell ({x}, {y}) is outside the {}x{} collision grid.",
+                self.width,
+                self.height
+            );
+        }
+
+        if self.cells[y * self.width + x] != collision {
+            self.cells[y * self.width + x] = collision;
+            self.merge();
+        }
+
+        Ok(())
+    }
+
+    /// The merged solid cells, as colliders centered on their world position.
+    pub fn solid_rects(&self) -> &[Collider] {
+        &self.solid
+    }
+
+    /// Runs of one-way cells along each row, see `solid_rects`.
+    pub fn one_way_rects(&self) -> &[Collider] {
+        &self.one_way
+    }
+
+    /// The cell a world position falls in.
+    pub fn cell_at(&self, point: cgmath::Vector2<f32>) -> Option<(usize, usize)> {
+        let local = point - self.position;
+
+        if local.x < 0.0 || local.y < 0.0 {
+            return None;
+        }
+
+        let (x, y) = (local.x as usize, local.y as usize);
+        (x < self.width && y < self.height).then_some((x, y))
+    }
+
+    /**
+     * Moves a collider attached at `position` by `movement`, stopping it against solid cells and
+     * the tops of one-way cells. Movement is resolved along x and then y, so colliders slide
+     * along walls. Afterwards a collider whose bottom center is inside a slope cell is lifted
+     * onto the slope, unless it's moving up.
+     */
+    pub fn move_collider(
+        &self,
+        collider: &Collider,
+        position: cgmath::Vector2<f32>,
+        movement: cgmath::Vector2<f32>,
+    ) -> CollisionMove {
+        let mut result = CollisionMove {
+            position,
+            hit_x: false,
+            hit_y: false,
+            grounded: false,
+        };
+
+        if movement.x != 0.0 {
+            let (allowed, hit) = self.sweep_x(collider, result.position, movement.x);
+            result.position.x += allowed;
+            result.hit_x = hit;
+        }
+
+        if movement.y != 0.0 {
+            let (allowed, hit) = self.sweep_y(collider, result.position, movement.y);
+            result.position.y += allowed;
+            result.hit_y = hit;
+            result.grounded = hit && movement.y < 0.0;
+        }
+
+        if movement.y <= 0.0 {
+            if let Some(floor) = self.slope_floor(collider, result.position) {
+                let bottom = result.position.y + collider.down();
+
+                if bottom < floor + SKIN {
+                    result.position.y += floor - bottom;
+                    result.grounded = true;
+                }
+            }
+        }
+
+        result
+    }
+
+    // How far a collider can move along x before a solid rect stops it.
+    fn sweep_x(&self, collider: &Collider, position: cgmath::Vector2<f32>, dx: f32) -> (f32, bool) {
+        let (left, right) = (position.x + collider.left(), position.x + collider.right());
+        let (bottom, top) = (position.y + collider.down(), position.y + collider.up());
+
+        let mut allowed = dx;
+
+        for rect in &self.solid {
+            if bottom >= rect.up() - SKIN || top <= rect.down() + SKIN {
+                continue;
+            }
+
+            if dx > 0.0 && rect.left() >= right - SKIN {
+                allowed = allowed.min((rect.left() - right).max(0.0));
+            } else if dx < 0.0 && rect.right() <= left + SKIN {
+                allowed = allowed.max((rect.right() - left).min(0.0));
+            }
+        }
+
+        (allowed, allowed != dx)
+    }
+
+    // How far a collider can move along y before a solid rect, or a one-way rect below it,
+    // stops it.
+    fn sweep_y(&self, collider: &Collider, position: cgmath::Vector2<f32>, dy: f32) -> (f32, bool) {
+        let (left, right) = (position.x + collider.left(), position.x + collider.right());
+        let (bottom, top) = (position.y + collider.down(), position.y + collider.up());
+
+        let mut allowed = dy;
+
+        // One-way cells can be passed through going up.
+        let one_way: &[Collider] = if dy < 0.0 { &self.one_way } else { &[] };
+
+        for rect in self.solid.iter().chain(one_way) {
+            if left >= rect.right() - SKIN || right <= rect.left() + SKIN {
+                continue;
+            }
+
+            if dy > 0.0 && rect.down() >= top - SKIN {
+                allowed = allowed.min((rect.down() - top).max(0.0));
+            } else if dy < 0.0 && rect.up() <= bottom + SKIN {
+                allowed = allowed.max((rect.up() - bottom).min(0.0));
+            }
+        }
+
+        (allowed, allowed != dy)
+    }
+
+    // The height of the slope under a collider's bottom center, if there is one.
+    fn slope_floor(&self, collider: &Collider, position: cgmath::Vector2<f32>) -> Option<f32> {
+        let bottom_center =
+            cgmath::Vector2::new(position.x + collider.origin.x, position.y + collider.down());
+
+        let (x, y) = self.cell_at(bottom_center)?;
+
+        let TileCollision::Slope { left, right } = self.get(x, y) else {
+            return None;
+        };
+
+        let along = bottom_center.x - self.position.x - x as f32;
+        Some(self.position.y + y as f32 + left + (right - left) * along)
+    }
+
+    // Greedily grows rectangles of solid cells right and then up, and one-way cells right only.
+    fn merge(&mut self) {
+        self.solid = self.merge_cells(TileCollision::Solid, true);
+        self.one_way = self.merge_cells(TileCollision::OneWay, false);
+    }
+
+    fn merge_cells(&self, kind: TileCollision, grow_up: bool) -> Vec<Collider> {
+        let mut taken = vec![false; self.cells.len()];
+        let mut rects = Vec::new();
+
+        let free = |taken: &[bool], x: usize, y: usize| {
+            self.cells[y * self.width + x] == kind && !taken[y * self.width + x]
+        };
+
+        for y in 0..self.height {
+            for x in 0..self.width {
+                if !free(&taken, x, y) {
+                    continue;
+                }
+
+                let mut x1 = x + 1;
+                while x1 < self.width && free(&taken, x1, y) {
+                    x1 += 1;
+                }
+
+                let mut y1 = y + 1;
+                while grow_up && y1 < self.height && (x..x1).all(|i| free(&taken, i, y1)) {
+                    y1 += 1;
+                }
+
+                for row in y..y1 {
+                    taken[row * self.width + x..row * self.width + x1].fill(true);
+                }
+
+                let (width, height) = ((x1 - x) as f32, (y1 - y) as f32);
+
+                rects.push(Collider {
+                    origin: self.position
+                        + cgmath::Vector2::new(x as f32 + width / 2.0, y as f32 + height / 2.0),
+                    width,
+                    height,
+                });
+            }
+        }
+
+        rects
+    }
+}
@@ -1 +1,2 @@
 pub mod collider;
+pub mod collision_grid;
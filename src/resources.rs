@@ -2,7 +2,13 @@ use std::path::{self, PathBuf};
 
 use wgpu_glyph::ab_glyph;
 
-use crate::graphics::{texture, Graphics};
+use crate::graphics::{
+    atlas::AtlasManifest,
+    normal_map::NormalMap,
+    sprite_sheet::UvRect,
+    texture::{self, TexturePage},
+    Graphics,
+};
 
 pub struct Resource {}
 
@@ -24,7 +30,7 @@ impl Resource {
         Ok(std::fs::read(path)?)
     }
 
-    /**
+    /*
      * TODO: This is being used for shaders (and maybe other future data types) and so is kept top-
      * level. There shouldn't be top-level data, shaders and other data types should live in their
      * own sub paths.
@@ -39,10 +45,44 @@ impl Resource {
         graphics: &Graphics,
         file_name: &str,
     ) -> anyhow::Result<texture::Texture> {
-        let data = Self::load_bytes(Some("textures"), file_name).await?;
+        Self::read_texture(graphics, file_name)
+    }
+
+    /// Textures packed by the build come from their atlas page, anything else loads on its own.
+    pub fn read_texture(graphics: &Graphics, file_name: &str) -> anyhow::Result<texture::Texture> {
+        if let Some(manifest) = AtlasManifest::get()? {
+            if let Some(entry) = manifest.textures.get(file_name) {
+                let page = graphics.atlas_page(manifest, entry.page)?;
+                return Ok(texture::Texture::from_region(page, entry.rect()));
+            }
+        }
+
+        let path = Self::build_path(Some("textures"), file_name);
+        let data = std::fs::read(path)?;
         texture::Texture::from_bytes(graphics, &data, file_name)
     }
 
+    /// Loads a texture onto a page of its own even if the build packed it, so it can be sampled
+    /// whole.
+    pub fn read_texture_page(graphics: &Graphics, file_name: &str) -> anyhow::Result<TexturePage> {
+        let path = Self::build_path(Some("textures"), file_name);
+        let data = std::fs::read(path)?;
+        let image = image::load_from_memory(&data)?;
+        TexturePage::from_image(graphics, &image, file_name)
+    }
+
+    /// Loads a normal map under `res/textures` for a texture at `region` of its page.
+    pub fn read_normal_map(
+        graphics: &Graphics,
+        file_name: &str,
+        region: UvRect,
+    ) -> anyhow::Result<NormalMap> {
+        let path = Self::build_path(Some("textures"), file_name);
+        let data = std::fs::read(path)?;
+        let image = image::load_from_memory(&data)?;
+        NormalMap::from_image(graphics, &image, file_name, region)
+    }
+
     pub async fn load_font(file_name: &str) -> anyhow::Result<ab_glyph::FontArc> {
         let data = Self::load_bytes(Some("fonts"), file_name).await?;
         Ok(ab_glyph::FontArc::try_from_vec(data)?)
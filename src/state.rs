@@ -1,37 +1,84 @@
 use std::{
     collections::{HashMap, LinkedList},
-    time::Instant,
+    path::PathBuf,
+    time::{Instant, SystemTime, UNIX_EPOCH},
 };
 
-use cgmath::{prelude::*, Quaternion};
+use anyhow::Context;
+use image::RgbaImage;
 use winit::window::Window;
 
 use crate::{
-    camera::Camera,
+    camera::{Camera, CameraBounds},
     config::Config,
     entity,
     graphics::{
-        material, sorting_layer,
-        sprite::{self},
-        vertex, Graphics,
+        lighting::Lighting,
+        material,
+        particles::Particles,
+        pixel_perfect::PixelPerfect,
+        shader::ShaderValue,
+        sorting_layer::{SortingLayer, SortingLayers},
+        sprite_sheet::UvRect,
+        vertex::SpriteInstance,
+        Frame, Graphics,
     },
     input,
-    parsing::{nml::LoadNml, styles::LoadStyles},
+    parsing::{nml::LoadNml, sorting_layers::LoadSortingLayers, styles::LoadStyles},
+    physics::{
+        collider::Collider,
+        collision_grid::{CollisionGrid, CollisionMove, TileCollision},
+    },
     resources::Resource,
+    tilemap::{Tile, Tilemap},
     ui::{canvas, style::Style},
 };
 
+// Factor the visible area changes by per second while a zoom key is held.
+const ZOOM_SPEED: f32 = 2.0;
+
+// Developer mode gizmos.
+const COLLIDER_COLOR: wgpu::Color = wgpu::Color::GREEN;
+const ORIGIN_COLOR: wgpu::Color = wgpu::Color {
+    r: 1.0,
+    g: 1.0,
+    b: 0.0,
+    a: 1.0,
+};
+const ORIGIN_SIZE: f32 = 0.15;
+const CAMERA_BOUNDS_COLOR: wgpu::Color = wgpu::Color {
+    r: 1.0,
+    g: 0.0,
+    b: 1.0,
+    a: 1.0,
+};
+
 pub struct State {
-    pub camera: Camera,
+    /// Rendered in order, later cameras draw over earlier ones. The first is the main camera that
+    /// zoom input applies to.
+    pub cameras: Vec<Camera>,
     config: Config,
     delta_time: f64,
     pub input: input::ReadOnlyInput,
     pub size: winit::dpi::PhysicalSize<u32>,
-    pub graphics: Graphics,
+    pub graphics: Option<Graphics>,
     materials: Vec<material::Material>,
     entities: Vec<Option<entity::Entity>>,
+    tilemaps: Vec<Tilemap>,
+    collision_grids: Vec<CollisionGrid>,
+    /// Despawned entity slots, reused by `add_entity` before the list grows.
+    free_slots: Vec<usize>,
+    /// Entities that despawned themselves during their own update, removed once every entity has
+    /// updated.
+    despawn_queue: Vec<usize>,
+    updating: Option<usize>,
     instant: Instant,
+    lighting: Lighting,
+    /// Submitted by particle emitters during each step, drawn at the next render.
+    particles: Particles,
+    pixel_perfect: Option<PixelPerfect>,
     last_n_ticks: LinkedList<f64>,
+    sorting_layers: SortingLayers,
     tick_queue_len: usize,
     ui_canvas: canvas::Canvas,
     styles: HashMap<String, Style>,
@@ -40,98 +87,165 @@ pub struct State {
 impl State {
     pub async fn new(window: &Window) -> Result<Self, anyhow::Error> {
         let size = window.inner_size();
+        let mut camera = Self::create_camera(size);
 
-        let camera = Camera {
-            aspect: 1.8,
-            position: (0.0, 0.0, 5.0).into(),
-            scale: 6.0,
-            znear: 0.1,
-            zfar: 100.0,
-        };
-
-        let mut graphics = Graphics::new(&window, &camera).await;
+        let graphics = Graphics::new(window).await;
 
         let grass_texture = Resource::load_texture(&graphics, "grass.png")
             .await
             .unwrap();
 
-        let grass_material =
-            material::Material::new(String::from("grass"), &graphics, grass_texture);
+        let grass_material = material::Material::new(String::from("grass"), grass_texture);
 
-        let materials = vec![grass_material];
+        const GROUND_SIZE: usize = 100;
 
-        let mut entities = Vec::new();
+        // The ground is offset by half a tile so tile centers land on whole numbers.
+        camera.bounds = Some(CameraBounds {
+            min: cgmath::Vector2::new(-0.5, -0.5),
+            max: cgmath::Vector2::new(GROUND_SIZE as f32 - 0.5, GROUND_SIZE as f32 - 0.5),
+        });
 
-        const NUM_INSTANCES_PER_ROW: u32 = 100;
+        let mut state = Self::create(camera, size, Some(graphics), vec![grass_material])?;
 
-        for y in 0..NUM_INSTANCES_PER_ROW {
-            for x in 0..NUM_INSTANCES_PER_ROW {
-                let entity = entity::Entity::create(
-                    entities.len(),
-                    cgmath::Vector2 {
-                        x: x as f32,
-                        y: y as f32,
-                    },
-                    Quaternion::zero(),
-                    0,
-                    sorting_layer::SortingLayer::Background,
-                    None,
-                );
+        let mut ground = Tilemap::new(
+            GROUND_SIZE,
+            GROUND_SIZE,
+            0,
+            cgmath::Vector2::new(-0.5, -0.5),
+        );
 
-                let verts = vertex::RenderVertex::new(
-                    entity.get_position(),
-                    entity.get_rotation(),
-                    &sprite::Sprite::get_vertices(),
-                );
+        let layer = ground.add_layer("ground", state.sorting_layer("background")?);
+        ground.fill(layer, Some(Tile::new(0)));
+        state.add_tilemap(ground);
 
-                graphics.write_entity(entity.get_id(), verts);
-                entities.push(Some(entity));
-            }
-        }
+        // A wall of solid cells just outside the ground keeps colliders on it.
+        const WALLS_SIZE: usize = GROUND_SIZE + 2;
 
-        let mut ui_root = Resource::load_nml("debug.nml")?;
-        let styles = Resource::load_styles("debug.json")?;
+        let walls = (0..WALLS_SIZE * WALLS_SIZE)
+            .map(|i| {
+                let (x, y) = (i % WALLS_SIZE, i / WALLS_SIZE);
 
-        ui_root.update(
-            &mut graphics,
-            cgmath::Vector2 { x: -1.0, y: 1.0 },
-            1.0,
-            &styles,
-        );
+                if x == 0 || y == 0 || x == WALLS_SIZE - 1 || y == WALLS_SIZE - 1 {
+                    TileCollision::Solid
+                } else {
+                    TileCollision::Empty
+                }
+            })
+            .collect();
+
+        let walls = CollisionGrid::from_cells(
+            WALLS_SIZE,
+            WALLS_SIZE,
+            cgmath::Vector2::new(-1.5, -1.5),
+            walls,
+        )?;
+
+        state.add_collision_grid(walls);
+
+        Ok(state)
+    }
+
+    /**
+     * Builds a state with no window, surface or renderer. Nothing is drawn and no GPU is touched,
+     * so materials can't be loaded, but entities, components, input and the UI layout all work.
+     * Drive it with `step` instead of `update`.
+     */
+    pub fn new_headless(size: winit::dpi::PhysicalSize<u32>) -> Result<Self, anyhow::Error> {
+        let camera = Self::create_camera(size);
+        Self::create(camera, size, None, Vec::new())
+    }
+
+    /// Builds a state that renders into offscreen textures only, see `capture_frame`.
+    pub async fn new_offscreen(
+        size: winit::dpi::PhysicalSize<u32>,
+        force_fallback_adapter: bool,
+    ) -> Result<Self, anyhow::Error> {
+        let camera = Self::create_camera(size);
+        let graphics =
+            Graphics::new_offscreen(size.width, size.height, force_fallback_adapter).await?;
+
+        Self::create(camera, size, Some(graphics), Vec::new())
+    }
+
+    fn create_camera(size: winit::dpi::PhysicalSize<u32>) -> Camera {
+        let mut camera = Camera::new(1.8, (0.0, 0.0, 5.0).into(), 6.0, 0.1, 100.0);
 
-        let ui_canvas = canvas::Canvas::new(ui_root);
+        if size.width > 0 && size.height > 0 {
+            camera.resize(size.width, size.height);
+        }
+
+        camera
+    }
+
+    fn create(
+        camera: Camera,
+        size: winit::dpi::PhysicalSize<u32>,
+        mut graphics: Option<Graphics>,
+        materials: Vec<material::Material>,
+    ) -> Result<Self, anyhow::Error> {
+        let (ui_canvas, styles) = load_ui(graphics.as_mut(), "debug.nml", "debug.json")?;
+        let sorting_layers = Resource::load_sorting_layers("sorting_layers.json")?;
 
         Ok(Self {
-            camera,
+            cameras: vec![camera],
             config: Config::new(),
             delta_time: 0.0,
             graphics,
-            entities,
+            entities: Vec::new(),
+            tilemaps: Vec::new(),
+            collision_grids: Vec::new(),
+            free_slots: Vec::new(),
+            despawn_queue: Vec::new(),
+            updating: None,
             input: input::ReadOnlyInput::new(),
             size,
             materials,
             instant: Instant::now(),
+            lighting: Lighting::default(),
+            particles: Particles::default(),
+            pixel_perfect: None,
             last_n_ticks: LinkedList::new(),
             tick_queue_len: 15,
+            sorting_layers,
             ui_canvas,
             styles,
         })
     }
 
+    pub fn is_headless(&self) -> bool {
+        self.graphics.is_none()
+    }
+
     pub fn resize(&mut self, new_size: winit::dpi::PhysicalSize<u32>) {
         if new_size.width > 0 && new_size.height > 0 {
-            self.camera.resize(new_size.width, new_size.height);
             self.size = new_size;
-            self.graphics.resize(new_size);
+
+            let world_size = self.world_size();
+
+            for camera in &mut self.cameras {
+                camera.resize(world_size.width, world_size.height);
+            }
+
+            if let Some(graphics) = &mut self.graphics {
+                graphics.resize(new_size);
+            }
         }
     }
 
+    /// Advances the simulation by the real time elapsed since the previous update.
     pub fn update(&mut self, input: input::ReadOnlyInput) {
+        let delta_time = self.instant.elapsed().as_micros() as f64 / 1_000_000.00;
+        self.instant = Instant::now();
+        self.step(input, delta_time);
+    }
+
+    /// Advances the simulation by exactly `delta_time` seconds, for tests and headless runs.
+    pub fn step(&mut self, input: input::ReadOnlyInput, delta_time: f64) {
         self.input = input;
-        self.delta_time = self.instant.elapsed().as_micros() as f64 / 1_000_000.00;
+        self.delta_time = delta_time;
 
         if self.delta_time > 0.0 {
-            self.last_n_ticks.push_front(1.0 / self.delta_time as f64);
+            self.last_n_ticks.push_front(1.0 / self.delta_time);
         }
 
         if self.last_n_ticks.len() > self.tick_queue_len {
@@ -143,38 +257,295 @@ impl State {
 
         // self.ui_canvas.root().set_body(&format!("FPS: {fps}"));
 
-        self.instant = Instant::now();
+        for click in self.input.clicks() {
+            let point = cgmath::Vector2::new(
+                click.x / self.size.width as f32 * 2.0 - 1.0,
+                1.0 - click.y / self.size.height as f32 * 2.0,
+            );
+
+            self.ui_canvas.click(point);
+        }
+
+        self.ui_canvas.handle_text_events(self.input.text_events());
+
+        self.particles.clear();
+        self.lighting.clear();
+
+        if let Some(graphics) = &mut self.graphics {
+            graphics.gizmos_mut().clear();
+        }
 
         for i in 0..self.entities.len() {
             if let Some(mut entity) = self.entities[i].take() {
+                self.updating = Some(i);
                 entity.update(self, self.delta_time);
+                self.updating = None;
                 self.entities[i] = Some(entity);
             }
         }
 
-        self.graphics.write_camera(&self.camera);
+        for index in std::mem::take(&mut self.despawn_queue) {
+            self.despawn_entity(index);
+        }
+
+        self.update_cameras();
+
+        if self.config.developer_mode() {
+            self.draw_developer_gizmos();
+        }
+
+        for tilemap in &mut self.tilemaps {
+            tilemap.update(self.delta_time as f32);
+        }
+
+        if let Some(graphics) = &mut self.graphics {
+            for (id, tilemap) in self.tilemaps.iter_mut().enumerate() {
+                graphics.write_tilemap(id, tilemap, &self.materials);
+            }
+        }
+    }
+
+    fn update_cameras(&mut self) {
+        let zoom_direction = match (
+            self.input.is_zoom_in_pressed(),
+            self.input.is_zoom_out_pressed(),
+        ) {
+            (true, false) => -1.0,
+            (false, true) => 1.0,
+            _ => 0.0,
+        };
+
+        if let Some(camera) = self.cameras.first_mut() {
+            if zoom_direction != 0.0 {
+                camera.zoom_by(ZOOM_SPEED.powf(zoom_direction * self.delta_time as f32));
+            }
+        }
+
+        for i in 0..self.cameras.len() {
+            let target_position = self.cameras[i]
+                .following()
+                .and_then(|follow| self.entity(follow.target))
+                .map(|entity| entity.get_position());
+
+            self.cameras[i].update(target_position, self.delta_time);
+        }
+    }
+
+    /// Outlines colliders and camera bounds and marks entity origins.
+    fn draw_developer_gizmos(&mut self) {
+        let Some(graphics) = &mut self.graphics else {
+            return;
+        };
+
+        let gizmos = graphics.gizmos_mut();
+
+        for entity in self.entities.iter().flatten() {
+            let position = entity.get_position();
+
+            if let Some(collider) = &entity.collider {
+                gizmos.rect(
+                    position + cgmath::Vector2::new(collider.left(), collider.down()),
+                    position + cgmath::Vector2::new(collider.right(), collider.up()),
+                    COLLIDER_COLOR,
+                );
+            }
+
+            gizmos.cross(position, ORIGIN_SIZE, ORIGIN_COLOR);
+        }
+
+        for bounds in self.cameras.iter().filter_map(|camera| camera.bounds) {
+            gizmos.rect(bounds.min, bounds.max, CAMERA_BOUNDS_COLOR);
+        }
+    }
+
+    /// Adds a camera drawn after the existing ones and returns its index.
+    pub fn add_camera(&mut self, mut camera: Camera) -> usize {
+        let world_size = self.world_size();
+
+        camera.set_pixels_per_unit(self.pixel_perfect.map(|settings| settings.pixels_per_unit));
+        camera.resize(world_size.width, world_size.height);
+        self.cameras.push(camera);
+        self.cameras.len() - 1
+    }
+
+    pub fn pixel_perfect(&self) -> Option<PixelPerfect> {
+        self.pixel_perfect
+    }
+
+    /**
+     * Draws the world at a fixed reference resolution, upscaled by whole pixels and letterboxed to
+     * fit the window, with every camera locked to the texel grid. Pass `None` to go back to
+     * drawing straight into the window.
+     */
+    pub fn set_pixel_perfect(&mut self, settings: Option<PixelPerfect>) {
+        self.pixel_perfect = settings;
+
+        if let Some(graphics) = &mut self.graphics {
+            graphics.set_pixel_perfect(settings);
+        }
+
+        let world_size = self.world_size();
+
+        for camera in &mut self.cameras {
+            camera.set_pixels_per_unit(settings.map(|settings| settings.pixels_per_unit));
+            camera.resize(world_size.width, world_size.height);
+        }
+    }
+
+    // The size of the target the world is drawn into, which is the window's unless pixel perfect.
+    fn world_size(&self) -> winit::dpi::PhysicalSize<u32> {
+        match self.pixel_perfect {
+            Some(settings) => {
+                winit::dpi::PhysicalSize::new(settings.reference_width, settings.reference_height)
+            }
+            None => self.size,
+        }
     }
 
     pub fn render(&mut self) -> Result<(), wgpu::SurfaceError> {
-        self.graphics.render(
-            &self.entities,
-            &self.materials,
-            &mut self.ui_canvas,
-            &self.config,
-            &self.styles,
-        )
-    }
-
-    pub fn add_entity(&mut self, entity: entity::Entity) -> usize {
-        let verts = vertex::RenderVertex::new(
-            entity.get_position(),
-            entity.get_rotation(),
-            &sprite::Sprite::get_vertices(),
-        );
+        match &mut self.graphics {
+            Some(graphics) => graphics.render(Frame {
+                cameras: &self.cameras,
+                entities: &self.entities,
+                materials: &self.materials,
+                sorting_layers: &self.sorting_layers,
+                particles: &self.particles,
+                lighting: &self.lighting,
+                ui_canvas: &mut self.ui_canvas,
+                config: &self.config,
+                styles: &self.styles,
+            }),
+            None => Ok(()),
+        }
+    }
+
+    /// Renders the current frame into an offscreen texture and reads it back.
+    pub fn capture_frame(&mut self) -> Result<RgbaImage, anyhow::Error> {
+        let graphics = self
+            .graphics
+            .as_mut()
+            .ok_or_else(|| anyhow::anyhow!("Can't capture a frame without a renderer."))?;
+
+        graphics.render_to_image(Frame {
+            cameras: &self.cameras,
+            entities: &self.entities,
+            materials: &self.materials,
+            sorting_layers: &self.sorting_layers,
+            particles: &self.particles,
+            lighting: &self.lighting,
+            ui_canvas: &mut self.ui_canvas,
+            config: &self.config,
+            styles: &self.styles,
+        })
+    }
+
+    /// Captures the current frame and saves it as a PNG under `screenshots/`.
+    pub fn save_screenshot(&mut self) -> Result<PathBuf, anyhow::Error> {
+        let image = self.capture_frame()?;
+
+        let timestamp = SystemTime::now().duration_since(UNIX_EPOCH)?.as_millis();
+        let dir = PathBuf::from("screenshots");
+        std::fs::create_dir_all(&dir)?;
+
+        let path = dir.join(format!("screenshot-{timestamp}.png"));
+        image.save(&path)?;
+
+        Ok(path)
+    }
+
+    /**
+     * Spawns an entity into the first free slot and returns its index, which also becomes the
+     * entity's id. Fails if the renderer has no room left for another sprite, in which case the
+     * entity isn't added.
+     */
+    pub fn add_entity(&mut self, mut entity: entity::Entity) -> anyhow::Result<usize> {
+        let index = self
+            .free_slots
+            .last()
+            .copied()
+            .unwrap_or(self.entities.len());
+
+        entity.set_id(index);
+        self.write_entity(&entity)?;
+
+        if index == self.entities.len() {
+            self.entities.push(Some(entity));
+        } else {
+            self.free_slots.pop();
+            self.entities[index] = Some(entity);
+        }
+
+        Ok(index)
+    }
+
+    /**
+     * Removes an entity and frees its slot for the next `add_entity`. Returns `None` if the slot is
+     * already empty. An entity despawning itself from inside its own update also gets `None`, and
+     * is removed once every entity has updated. Cameras following the entity stop following it.
+     */
+    pub fn despawn_entity(&mut self, index: usize) -> Option<entity::Entity> {
+        if self.updating == Some(index) {
+            if !self.despawn_queue.contains(&index) {
+                self.despawn_queue.push(index);
+            }
+            return None;
+        }
+
+        let entity = self.entities.get_mut(index)?.take()?;
+        self.free_slots.push(index);
+
+        // The slot is reused, so cameras would follow whatever's spawned into it next.
+        for camera in &mut self.cameras {
+            if camera
+                .following()
+                .is_some_and(|follow| follow.target == index)
+            {
+                camera.stop_following();
+            }
+        }
+
+        Some(entity)
+    }
+
+    /// Uploads the entity's current transform to the renderer, if there is one.
+    pub fn write_entity(&mut self, entity: &entity::Entity) -> anyhow::Result<()> {
+        if let Some(graphics) = &mut self.graphics {
+            let uv = self
+                .materials
+                .get(entity.sprite_mat)
+                .map_or(UvRect::FULL, |material| {
+                    material.sprite_sheet.frame(entity.get_frame())
+                });
+
+            let instance = SpriteInstance::new(
+                entity.get_position(),
+                entity.get_rotation(),
+                uv,
+                &entity.appearance,
+            );
+
+            graphics
+                .write_entity(entity.get_id(), instance)
+                .with_context(|| format!("Couldn't write entity {}", entity.get_id()))?;
+        }
+
+        Ok(())
+    }
+
+    /// Looks up one of the layers declared in `res/data/sorting_layers.json`.
+    pub fn sorting_layer(&self, name: &str) -> anyhow::Result<SortingLayer> {
+        self.sorting_layers
+            .get(name)
+            .ok_or_else(|| anyhow::anyhow!("No sorting layer named `{name}`."))
+    }
+
+    pub fn sorting_layers(&self) -> &SortingLayers {
+        &self.sorting_layers
+    }
 
-        self.graphics.write_entity(entity.get_id(), verts);
-        self.entities.push(Some(entity));
-        self.entities.len() - 1
+    /// Layers can't be added or reordered once entities refer to them, but Y-sorting can change.
+    pub fn sorting_layers_mut(&mut self) -> &mut SortingLayers {
+        &mut self.sorting_layers
     }
 
     pub fn add_material(&mut self, material: material::Material) -> usize {
@@ -182,7 +553,167 @@ impl State {
         self.materials.len() - 1
     }
 
+    pub fn material(&self, index: usize) -> Option<&material::Material> {
+        self.materials.get(index)
+    }
+
+    pub fn material_mut(&mut self, index: usize) -> Option<&mut material::Material> {
+        self.materials.get_mut(index)
+    }
+
+    /// Changes a parameter of a material drawn with a custom shader, see `MaterialShader`.
+    pub fn set_material_param(
+        &mut self,
+        index: usize,
+        name: &str,
+        value: ShaderValue,
+    ) -> anyhow::Result<()> {
+        let Some(material) = self.materials.get_mut(index) else {
+            anyhow::bail!("There's no material {index}.");
+        };
+
+        let Some(shader) = &mut material.shader else {
+            anyhow::bail!("Material {} has no custom shader.", material.name);
+        };
+
+        let Some(graphics) = &self.graphics else {
+            anyhow::bail!("Shader parameters can't be set without graphics.");
+        };
+
+        shader.set_param(graphics, name, value)
+    }
+
+    pub fn particles(&self) -> &Particles {
+        &self.particles
+    }
+
+    /// Emitters submit through this, and the particle budget is set here.
+    pub fn particles_mut(&mut self) -> &mut Particles {
+        &mut self.particles
+    }
+
+    pub fn lighting(&self) -> &Lighting {
+        &self.lighting
+    }
+
+    /// Lights and occluders are submitted through this, and the ambient level is set here.
+    pub fn lighting_mut(&mut self) -> &mut Lighting {
+        &mut self.lighting
+    }
+
+    /// Adds a tilemap drawn from the next update on and returns its index.
+    pub fn add_tilemap(&mut self, tilemap: Tilemap) -> usize {
+        self.tilemaps.push(tilemap);
+        self.tilemaps.len() - 1
+    }
+
+    pub fn tilemaps(&self) -> &[Tilemap] {
+        &self.tilemaps
+    }
+
+    /// Edits through this are picked up by the renderer on the next update.
+    pub fn tilemap_mut(&mut self, index: usize) -> Option<&mut Tilemap> {
+        self.tilemaps.get_mut(index)
+    }
+
+    /// Adds a static collision layer that `move_collider` stops colliders against.
+    pub fn add_collision_grid(&mut self, grid: CollisionGrid) -> usize {
+        self.collision_grids.push(grid);
+        self.collision_grids.len() - 1
+    }
+
+    pub fn collision_grids(&self) -> &[CollisionGrid] {
+        &self.collision_grids
+    }
+
+    pub fn collision_grid_mut(&mut self, index: usize) -> Option<&mut CollisionGrid> {
+        self.collision_grids.get_mut(index)
+    }
+
+    /// Moves a collider attached at `position` through every collision grid in turn.
+    pub fn move_collider(
+        &self,
+        collider: &Collider,
+        position: cgmath::Vector2<f32>,
+        movement: cgmath::Vector2<f32>,
+    ) -> CollisionMove {
+        let mut result = CollisionMove {
+            position: position + movement,
+            hit_x: false,
+            hit_y: false,
+            grounded: false,
+        };
+
+        // Each grid can only shorten the movement the previous ones allowed.
+        for grid in &self.collision_grids {
+            let moved = grid.move_collider(collider, position, result.position - position);
+
+            result = CollisionMove {
+                position: moved.position,
+                hit_x: result.hit_x || moved.hit_x,
+                hit_y: result.hit_y || moved.hit_y,
+                grounded: result.grounded || moved.grounded,
+            };
+        }
+
+        result
+    }
+
     pub fn num_entities(&self) -> usize {
         self.entities.len()
     }
+
+    /// Entities currently being updated are taken out of this list, so their slot reads as `None`
+    /// from inside a component.
+    pub fn entities(&self) -> &[Option<entity::Entity>] {
+        &self.entities
+    }
+
+    pub fn entity(&self, index: usize) -> Option<&entity::Entity> {
+        self.entities.get(index)?.as_ref()
+    }
+
+    pub fn delta_time(&self) -> f64 {
+        self.delta_time
+    }
+
+    pub fn config(&self) -> &Config {
+        &self.config
+    }
+
+    pub fn config_mut(&mut self) -> &mut Config {
+        &mut self.config
+    }
+
+    pub fn focus_text_field(&mut self, script_id: &str) -> bool {
+        self.ui_canvas.focus(script_id)
+    }
+
+    pub fn is_text_input_active(&self) -> bool {
+        self.ui_canvas.focused().is_some()
+    }
+
+    pub fn ui_canvas(&mut self) -> &mut canvas::Canvas {
+        &mut self.ui_canvas
+    }
+
+    /// Replaces the UI with an NML document and its styles, both under `res/ui`.
+    pub fn load_ui(&mut self, nml_file: &str, styles_file: &str) -> anyhow::Result<()> {
+        (self.ui_canvas, self.styles) = load_ui(self.graphics.as_mut(), nml_file, styles_file)?;
+        Ok(())
+    }
+}
+
+// Loads a UI and lays it out over the whole target.
+fn load_ui(
+    graphics: Option<&mut Graphics>,
+    nml_file: &str,
+    styles_file: &str,
+) -> anyhow::Result<(canvas::Canvas, HashMap<String, Style>)> {
+    let mut root = Resource::load_nml(nml_file)?;
+    let styles = Resource::load_styles(styles_file)?;
+
+    root.update(graphics, cgmath::Vector2 { x: -1.0, y: 1.0 }, 1.0, &styles)?;
+
+    Ok((canvas::Canvas::new(root), styles))
 }
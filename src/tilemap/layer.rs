@@ -0,0 +1,35 @@
+use crate::graphics::sorting_layer::SortingLayer;
+
+use super::Tile;
+
+/// One grid of tiles in a tilemap, drawn in its own sorting layer.
+pub struct TilemapLayer {
+    pub name: String,
+    pub sorting_layer: SortingLayer,
+    pub order_in_layer: i32,
+    /// Row by row from the bottom, `None` where there's no tile.
+    pub(super) tiles: Vec<Option<Tile>>,
+    /// Chunks whose tiles changed since the renderer last saw them.
+    pub(super) dirty: Vec<bool>,
+}
+
+impl TilemapLayer {
+    pub(super) fn new(
+        name: &str,
+        sorting_layer: SortingLayer,
+        num_tiles: usize,
+        num_chunks: usize,
+    ) -> Self {
+        Self {
+            name: name.to_owned(),
+            sorting_layer,
+            order_in_layer: 0,
+            tiles: vec![None; num_tiles],
+            dirty: vec![true; num_chunks],
+        }
+    }
+
+    pub fn tiles(&self) -> &[Option<Tile>] {
+        &self.tiles
+    }
+}
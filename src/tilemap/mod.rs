@@ -0,0 +1,365 @@
+pub mod layer;
+
+use std::collections::HashMap;
+
+use cgmath::Zero;
+
+use crate::{
+    animation::clip::AnimationFrame,
+    entity::DEFAULT_RENDER_LAYERS,
+    graphics::{
+        sorting_layer::SortingLayer, sprite::SpriteAppearance, sprite_sheet::SpriteSheet,
+        vertex::SpriteInstance,
+    },
+};
+
+use layer::TilemapLayer;
+
+/// Tiles along each side of a chunk. Every chunk of every layer is drawn with one call.
+pub const CHUNK_SIZE: usize = 16;
+
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct Tile {
+    /// Frame of the tileset's sprite sheet.
+    pub index: usize,
+    pub flip_x: bool,
+    pub flip_y: bool,
+}
+
+impl Tile {
+    pub fn new(index: usize) -> Self {
+        Self {
+            index,
+            ..Default::default()
+        }
+    }
+}
+
+/// Where one chunk of a tilemap lies, see `Tilemap::chunk_bounds`.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct ChunkBounds {
+    pub min: cgmath::Vector2<f32>,
+    pub max: cgmath::Vector2<f32>,
+}
+
+/**
+ * A grid of tiles drawn from one tileset material, in any number of layers. Tiles are one world
+ * unit across like sprites, with tile (0, 0) at the bottom left. The grid is split into chunks
+ * that are only rebuilt when a tile in them changes.
+ */
+pub struct Tilemap {
+    position: cgmath::Vector2<f32>,
+    width: usize,
+    height: usize,
+    /// A material with a sprite sheet, tile indices are its frames.
+    pub material: usize,
+    /// Bitmask of render layers, cameras only draw tilemaps matching their `layer_mask`.
+    pub render_layers: u32,
+    layers: Vec<TilemapLayer>,
+    /// Looping animations, keyed by the tile index they replace.
+    animations: HashMap<usize, Vec<AnimationFrame>>,
+    time: f32,
+}
+
+impl Tilemap {
+    /// `position` is the world position of the bottom left corner of the map.
+    pub fn new(
+        width: usize,
+        height: usize,
+        material: usize,
+        position: cgmath::Vector2<f32>,
+    ) -> Self {
+        Self {
+            position,
+            width,
+            height,
+            material,
+            render_layers: DEFAULT_RENDER_LAYERS,
+            layers: Vec::new(),
+            animations: HashMap::new(),
+            time: 0.0,
+        }
+    }
+
+    pub fn width(&self) -> usize {
+        self.width
+    }
+
+    pub fn height(&self) -> usize {
+        self.height
+    }
+
+    pub fn position(&self) -> cgmath::Vector2<f32> {
+        self.position
+    }
+
+    pub fn set_position(&mut self, position: cgmath::Vector2<f32>) {
+        self.position = position;
+        self.mark_all_dirty();
+    }
+
+    /// Adds an empty layer drawn over the existing ones in the same sorting layer.
+    pub fn add_layer(&mut self, name: &str, sorting_layer: SortingLayer) -> usize {
+        let mut layer = TilemapLayer::new(
+            name,
+            sorting_layer,
+            self.width * self.height,
+            self.num_chunks(),
+        );
+
+        layer.order_in_layer = self
+            .layers
+            .iter()
+            .filter(|other| other.sorting_layer == sorting_layer)
+            .map(|other| other.order_in_layer + 1)
+            .max()
+            .unwrap_or(0);
+
+        self.layers.push(layer);
+        self.layers.len() - 1
+    }
+
+    pub fn layer(&self, layer: usize) -> Option<&TilemapLayer> {
+        self.layers.get(layer)
+    }
+
+    pub fn layer_index(&self, name: &str) -> Option<usize> {
+        self.layers.iter().position(|layer| layer.name == name)
+    }
+
+    pub fn num_layers(&self) -> usize {
+        self.layers.len()
+    }
+
+    /// Moves a layer to another sorting layer or order, its chunks are redrawn to match.
+    pub fn set_layer_sorting(&mut self, layer: usize, sorting_layer: SortingLayer, order: i32) {
+        if let Some(layer) = self.layers.get_mut(layer) {
+            layer.sorting_layer = sorting_layer;
+            layer.order_in_layer = order;
+            layer.dirty.fill(true);
+        }
+    }
+
+    pub fn get_tile(&self, layer: usize, x: usize, y: usize) -> Option<Tile> {
+        if x >= self.width || y >= self.height {
+            return None;
+        }
+
+        *self.layers.get(layer)?.tiles.get(y * self.width + x)?
+    }
+
+    /// Replaces or clears a tile, only the chunk it's in is rebuilt.
+    pub fn set_tile(
+        &mut self,
+        layer: usize,
+        x: usize,
+        y: usize,
+        tile: Option<Tile>,
+    ) -> anyhow::Result<()> {
+        if x >= self.width || y >= self.height {
+            anyhow::bail!(
+                "Tile ({x}, {y}) is outside the {}x{} tilemap.",
+                self.width,
+                self.height
+            );
+        }
+
+        let chunk = self.chunk_at(x, y);
+        let width = self.width;
+
+        let layer = self
+            .layers
+            .get_mut(layer)
+            .ok_or_else(|| anyhow::anyhow!("Tilemap has no layer {layer}."))?;
+
+        let slot = &mut layer.tiles[y * width + x];
+
+        if *slot != tile {
+            *slot = tile;
+            layer.dirty[chunk] = true;
+        }
+
+        Ok(())
+    }
+
+    /// Fills a whole layer with one tile, or clears it.
+    pub fn fill(&mut self, layer: usize, tile: Option<Tile>) {
+        if let Some(layer) = self.layers.get_mut(layer) {
+            layer.tiles.fill(tile);
+            layer.dirty.fill(true);
+        }
+    }
+
+    /// Shows `frames` in a loop wherever a tile with this index is placed.
+    pub fn animate_tile(&mut self, index: usize, frames: Vec<AnimationFrame>) {
+        self.animations.insert(index, frames);
+        self.mark_dirty_where(|tile| tile == index);
+    }
+
+    /// Advances animated tiles, marking the chunks showing them for rebuilding when they change.
+    pub fn update(&mut self, delta_time: f32) {
+        let before = self.time;
+        self.time += delta_time;
+
+        let changed: Vec<usize> = self
+            .animations
+            .iter()
+            .filter(|(_, frames)| {
+                animation_position(frames, before) != animation_position(frames, self.time)
+            })
+            .map(|(index, _)| *index)
+            .collect();
+
+        if !changed.is_empty() {
+            self.mark_dirty_where(|tile| changed.contains(&tile));
+        }
+    }
+
+    /// The sprite sheet frame a tile index shows right now, after animation.
+    pub fn displayed_frame(&self, index: usize) -> usize {
+        match self.animations.get(&index) {
+            Some(frames) => animation_position(frames, self.time)
+                .map_or(index, |position| frames[position].frame),
+            None => index,
+        }
+    }
+
+    pub fn num_chunks_x(&self) -> usize {
+        self.width.div_ceil(CHUNK_SIZE)
+    }
+
+    pub fn num_chunks_y(&self) -> usize {
+        self.height.div_ceil(CHUNK_SIZE)
+    }
+
+    pub fn num_chunks(&self) -> usize {
+        self.num_chunks_x() * self.num_chunks_y()
+    }
+
+    pub fn chunk_at(&self, x: usize, y: usize) -> usize {
+        (y / CHUNK_SIZE) * self.num_chunks_x() + x / CHUNK_SIZE
+    }
+
+    /// The world space rectangle a chunk covers.
+    pub fn chunk_bounds(&self, chunk: usize) -> ChunkBounds {
+        let (x0, y0, x1, y1) = self.chunk_tiles(chunk);
+
+        ChunkBounds {
+            min: self.position + cgmath::Vector2::new(x0 as f32, y0 as f32),
+            max: self.position + cgmath::Vector2::new(x1 as f32, y1 as f32),
+        }
+    }
+
+    pub fn is_chunk_dirty(&self, layer: usize, chunk: usize) -> bool {
+        self.layers
+            .get(layer)
+            .and_then(|layer| layer.dirty.get(chunk).copied())
+            .unwrap_or(false)
+    }
+
+    /// Called by the renderer once it has rebuilt a chunk.
+    pub fn clear_dirty(&mut self, layer: usize, chunk: usize) {
+        if let Some(dirty) = self
+            .layers
+            .get_mut(layer)
+            .and_then(|layer| layer.dirty.get_mut(chunk))
+        {
+            *dirty = false;
+        }
+    }
+
+    /// An instance for every tile in a chunk, framed by the tileset's sprite sheet.
+    pub fn chunk_instances(
+        &self,
+        layer: usize,
+        chunk: usize,
+        sheet: &SpriteSheet,
+    ) -> Vec<SpriteInstance> {
+        let Some(tilemap_layer) = self.layers.get(layer) else {
+            return Vec::new();
+        };
+
+        let (x0, y0, x1, y1) = self.chunk_tiles(chunk);
+        let mut instances = Vec::new();
+
+        for y in y0..y1 {
+            for x in x0..x1 {
+                let Some(tile) = tilemap_layer.tiles[y * self.width + x] else {
+                    continue;
+                };
+
+                let appearance = SpriteAppearance {
+                    flip_x: tile.flip_x,
+                    flip_y: tile.flip_y,
+                    ..Default::default()
+                };
+
+                // Sprites are centered on their position.
+                let center = self.position + cgmath::Vector2::new(x as f32 + 0.5, y as f32 + 0.5);
+
+                instances.push(SpriteInstance::new(
+                    center,
+                    cgmath::Quaternion::zero(),
+                    sheet.frame(self.displayed_frame(tile.index)),
+                    &appearance,
+                ));
+            }
+        }
+
+        instances
+    }
+
+    // The tile columns and rows a chunk covers, ends exclusive.
+    fn chunk_tiles(&self, chunk: usize) -> (usize, usize, usize, usize) {
+        let x0 = (chunk % self.num_chunks_x()) * CHUNK_SIZE;
+        let y0 = (chunk / self.num_chunks_x()) * CHUNK_SIZE;
+
+        (
+            x0,
+            y0,
+            (x0 + CHUNK_SIZE).min(self.width),
+            (y0 + CHUNK_SIZE).min(self.height),
+        )
+    }
+
+    fn mark_all_dirty(&mut self) {
+        for layer in &mut self.layers {
+            layer.dirty.fill(true);
+        }
+    }
+
+    fn mark_dirty_where(&mut self, matches: impl Fn(usize) -> bool) {
+        let width = self.width;
+        let num_chunks_x = self.num_chunks_x();
+
+        for layer in &mut self.layers {
+            for (i, tile) in layer.tiles.iter().enumerate() {
+                if tile.is_some_and(|tile| matches(tile.index)) {
+                    let (x, y) = (i % width, i / width);
+                    layer.dirty[(y / CHUNK_SIZE) * num_chunks_x + x / CHUNK_SIZE] = true;
+                }
+            }
+        }
+    }
+}
+
+// Which frame of a looping animation shows at a time, `None` if it has no length.
+fn animation_position(frames: &[AnimationFrame], time: f32) -> Option<usize> {
+    let total: f32 = frames.iter().map(|frame| frame.duration).sum();
+
+    if total <= 0.0 {
+        return None;
+    }
+
+    let mut remaining = time.rem_euclid(total);
+
+    for (position, frame) in frames.iter().enumerate() {
+        if remaining < frame.duration {
+            return Some(position);
+        }
+
+        remaining -= frame.duration;
+    }
+
+    Some(frames.len() - 1)
+}
@@ -1,15 +1,74 @@
+use crate::input::TextEvent;
+
 use super::element::Element;
 
 pub struct Canvas {
     root: Element,
+    focused: Option<usize>,
 }
 
 impl Canvas {
     pub fn new(root: Element) -> Self {
-        Self { root }
+        Self {
+            root,
+            focused: None,
+        }
     }
 
     pub fn root(&mut self) -> &mut Element {
         &mut self.root
     }
+
+    pub fn focused(&self) -> Option<usize> {
+        self.focused
+    }
+
+    /// Focuses the text field with the given NML `id`. Returns false if there is no such field.
+    pub fn focus(&mut self, script_id: &str) -> bool {
+        match self.root.find_by_script_id(script_id) {
+            Some(element) if element.text_field.is_some() => {
+                self.focused = Some(element.render_id);
+                true
+            }
+            _ => false,
+        }
+    }
+
+    /**
+     * Focuses the text field under a point in UI space, from -1 to 1 with y up, or blurs the
+     * focused one if there's none. Returns whether a field is focused.
+     */
+    pub fn click(&mut self, point: cgmath::Vector2<f32>) -> bool {
+        self.focused = self
+            .root
+            .find_text_field_at(point)
+            .map(|element| element.render_id);
+
+        self.focused.is_some()
+    }
+
+    pub fn blur(&mut self) {
+        self.focused = None;
+    }
+
+    pub fn focused_element(&mut self) -> Option<&mut Element> {
+        let render_id = self.focused?;
+        self.root.find_by_render_id_mut(render_id)
+    }
+
+    pub fn handle_text_events(&mut self, events: &[TextEvent]) {
+        for event in events {
+            if let Some(text_field) = self
+                .focused_element()
+                .and_then(|element| element.text_field.as_mut())
+            {
+                text_field.handle_event(event);
+            }
+
+            if let TextEvent::Cancel = event {
+                self.blur();
+                return;
+            }
+        }
+    }
 }
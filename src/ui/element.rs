@@ -6,10 +6,12 @@ use wgpu_glyph::{GlyphBrush, Section, Text};
 use crate::graphics::Graphics;
 
 use super::{
-    element_kind::ElementKind, style::Style, style_rule::StyleRule, ui_vertex::UiRenderVertex,
+    element_kind::ElementKind, style::Style, style_rule::StyleRule, text_field::TextField,
+    ui_vertex::UiRenderVertex,
 };
 
 const DEFAULT_PADDING: cgmath::Vector2<f32> = cgmath::Vector2 { x: 0.1, y: -0.1 };
+pub const LINE_HEIGHT: f32 = 0.1;
 
 #[derive(Debug)]
 pub struct Element {
@@ -17,7 +19,11 @@ pub struct Element {
     pub script_id: Option<String>,
     pub tag_name: String,
     pub body: Vec<ElementKind>,
+    pub text_field: Option<TextField>,
     height: f32,
+    // Top left corner and right edge from the last layout.
+    position: cgmath::Vector2<f32>,
+    right_bound: f32,
 }
 
 impl Element {
@@ -32,46 +38,107 @@ impl Element {
             script_id,
             tag_name,
             body,
+            text_field: None,
             height: 0.0,
+            position: cgmath::Vector2::new(0.0, 0.0),
+            right_bound: 0.0,
         }
     }
     pub fn body(&self) -> &Vec<ElementKind> {
         &self.body
     }
 
+    pub fn find_by_script_id(&self, script_id: &str) -> Option<&Element> {
+        if self.script_id.as_deref() == Some(script_id) {
+            return Some(self);
+        }
+
+        self.body.iter().find_map(|child| match child {
+            ElementKind::Element(element) => element.find_by_script_id(script_id),
+            ElementKind::Content(_) => None,
+        })
+    }
+
+    pub fn find_by_render_id_mut(&mut self, render_id: usize) -> Option<&mut Element> {
+        if self.render_id == render_id {
+            return Some(self);
+        }
+
+        self.body.iter_mut().find_map(|child| match child {
+            ElementKind::Element(element) => element.find_by_render_id_mut(render_id),
+            ElementKind::Content(_) => None,
+        })
+    }
+
+    /// Whether a point in UI space, from -1 to 1 with y up, is inside the element's last layout.
+    pub fn contains(&self, point: cgmath::Vector2<f32>) -> bool {
+        (self.position.x..=self.right_bound).contains(&point.x)
+            && (self.position.y - self.height..=self.position.y).contains(&point.y)
+    }
+
+    /// The innermost element with a text field under a point in UI space.
+    pub fn find_text_field_at(&self, point: cgmath::Vector2<f32>) -> Option<&Element> {
+        if !self.contains(point) {
+            return None;
+        }
+
+        self.body
+            .iter()
+            .find_map(|child| match child {
+                ElementKind::Element(element) => element.find_text_field_at(point),
+                ElementKind::Content(_) => None,
+            })
+            .or_else(|| self.text_field.is_some().then_some(self))
+    }
+
+    /// Lays out this element and its children. Vertices are only written when there is a renderer,
+    /// headless states still get heights computed.
     pub fn update(
         &mut self,
-        graphics: &mut Graphics,
+        mut graphics: Option<&mut Graphics>,
         starting_position: cgmath::Vector2<f32>,
         right_bound: f32,
         styles: &HashMap<String, Style>,
-    ) -> () {
+    ) -> anyhow::Result<()> {
         let mut child_position = starting_position;
+        let mut body_height = 0.0;
 
-        let body_height = self.body.iter_mut().fold(0.0, |acc, child| {
+        for child in self.body.iter_mut() {
             child.update(
-                graphics,
+                graphics.as_deref_mut(),
                 child_position + DEFAULT_PADDING,
                 right_bound - DEFAULT_PADDING.x,
                 styles,
-            );
+            )?;
 
             let child_height = child.get_height();
             child_position.y -= child_height;
-            acc + child_height
-        });
+            body_height += child_height;
+        }
 
-        let height = body_height + (DEFAULT_PADDING.y.abs() * 2.0);
+        let field_height = if self.text_field.is_some() {
+            LINE_HEIGHT
+        } else {
+            0.0
+        };
 
-        self.write_verts(
-            graphics,
-            starting_position,
-            height,
-            right_bound,
-            styles.get(&self.tag_name),
-        );
+        let height = body_height + field_height + (DEFAULT_PADDING.y.abs() * 2.0);
+
+        if let Some(graphics) = graphics {
+            self.write_verts(
+                graphics,
+                starting_position,
+                height,
+                right_bound,
+                styles.get(&self.tag_name),
+            )?;
+        }
 
         self.height = height;
+        self.position = starting_position;
+        self.right_bound = right_bound;
+
+        Ok(())
     }
 
     pub fn write_verts(
@@ -81,7 +148,7 @@ impl Element {
         height: f32,
         right_bound: f32,
         style: Option<&Style>,
-    ) {
+    ) -> anyhow::Result<()> {
         let verts = [
             starting_position,
             cgmath::Vector2 {
@@ -102,15 +169,14 @@ impl Element {
 
         if let Some(style) = style {
             for rule in style.get_rules() {
-                match rule {
-                    StyleRule::BackgroundColor(color) => background_color = *color,
-                    _ => (),
+                if let StyleRule::BackgroundColor(color) = rule {
+                    background_color = *color
                 }
             }
         }
 
         let render_verts = UiRenderVertex::new(&verts, background_color);
-        graphics.write_ui_element(self.render_id, render_verts);
+        graphics.write_ui_element(self.render_id, render_verts)
     }
 
     pub fn get_height(&self) -> &f32 {
@@ -126,6 +192,18 @@ pub trait DrawElement<'a> {
         bounds: cgmath::Vector2<f32>,
         start_position: cgmath::Vector2<f32>,
         style: &HashMap<String, Style>,
+        focused: Option<usize>,
+    );
+
+    /// Draws only the text fields under an element, each as `draw_element` would.
+    fn draw_text_fields(
+        &mut self,
+        element: &'a Element,
+        text_brush: &mut GlyphBrush<()>,
+        bounds: cgmath::Vector2<f32>,
+        start_position: cgmath::Vector2<f32>,
+        style: &HashMap<String, Style>,
+        focused: Option<usize>,
     );
 }
 
@@ -140,6 +218,7 @@ where
         bounds: cgmath::Vector2<f32>,
         start_position: cgmath::Vector2<f32>,
         styles: &HashMap<String, Style>,
+        focused: Option<usize>,
     ) {
         let index_start = element.render_id as u32 * 6;
         let index_end = index_start + 6;
@@ -160,16 +239,61 @@ where
                     style,
                 ),
                 ElementKind::Element(element) => self.draw_element(
-                    &element,
+                    element,
                     text_brush,
                     bounds,
                     draw_position + DEFAULT_PADDING,
                     styles,
+                    focused,
                 ),
             }
 
             draw_position.y -= child.get_height();
         }
+
+        if let Some(text_field) = &element.text_field {
+            let text = text_field.display_text(focused == Some(element.render_id));
+
+            draw_content(
+                &text,
+                text_brush,
+                bounds,
+                draw_position + DEFAULT_PADDING,
+                style,
+            );
+        }
+    }
+
+    fn draw_text_fields(
+        &mut self,
+        element: &'b Element,
+        text_brush: &mut GlyphBrush<()>,
+        bounds: cgmath::Vector2<f32>,
+        start_position: cgmath::Vector2<f32>,
+        styles: &HashMap<String, Style>,
+        focused: Option<usize>,
+    ) {
+        if element.text_field.is_some() {
+            self.draw_element(element, text_brush, bounds, start_position, styles, focused);
+            return;
+        }
+
+        let mut draw_position = start_position;
+
+        for child in element.body() {
+            if let ElementKind::Element(element) = child {
+                self.draw_text_fields(
+                    element,
+                    text_brush,
+                    bounds,
+                    draw_position + DEFAULT_PADDING,
+                    styles,
+                    focused,
+                );
+            }
+
+            draw_position.y -= child.get_height();
+        }
     }
 }
 
@@ -189,11 +313,8 @@ fn draw_content(
 
     if let Some(style) = style {
         for rule in style.get_rules() {
-            match rule {
-                StyleRule::TextColor(c) => {
-                    text_color = [c.r as f32, c.g as f32, c.b as f32, c.a as f32]
-                }
-                _ => (),
+            if let StyleRule::TextColor(c) = rule {
+                text_color = [c.r as f32, c.g as f32, c.b as f32, c.a as f32]
             }
         }
     }
@@ -201,7 +322,7 @@ fn draw_content(
     text_brush.queue(Section {
         screen_position: (draw_position.x, draw_position.y),
         bounds: (bounds.x, bounds.y),
-        text: vec![Text::new(&content).with_color(text_color).with_scale(20.0)],
+        text: vec![Text::new(content).with_color(text_color).with_scale(20.0)],
         ..Section::default()
     })
 }
@@ -4,7 +4,10 @@ use cgmath::Vector2;
 
 use crate::graphics::Graphics;
 
-use super::{element::Element, style::Style};
+use super::{
+    element::{Element, LINE_HEIGHT},
+    style::Style,
+};
 
 #[derive(Debug)]
 pub enum ElementKind {
@@ -15,20 +18,23 @@ pub enum ElementKind {
 impl ElementKind {
     pub fn update(
         &mut self,
-        graphics: &mut Graphics,
+        graphics: Option<&mut Graphics>,
         starting_position: Vector2<f32>,
         right_bound: f32,
         styles: &HashMap<String, Style>,
-    ) -> () {
-        if let ElementKind::Element(element) = self {
-            element.update(graphics, starting_position, right_bound, styles);
+    ) -> anyhow::Result<()> {
+        match self {
+            ElementKind::Element(element) => {
+                element.update(graphics, starting_position, right_bound, styles)
+            }
+            ElementKind::Content(_) => Ok(()),
         }
     }
 
     pub fn get_height(&self) -> f32 {
         match self {
             ElementKind::Element(element) => *element.get_height(),
-            ElementKind::Content(_text) => 0.1,
+            ElementKind::Content(_text) => LINE_HEIGHT,
         }
     }
 }
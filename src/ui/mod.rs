@@ -3,4 +3,5 @@ pub mod element;
 pub mod element_kind;
 pub mod style;
 pub mod style_rule;
+pub mod text_field;
 pub mod ui_vertex;
@@ -0,0 +1,99 @@
+use crate::input::TextEvent;
+
+#[derive(Debug, Default)]
+pub struct TextField {
+    value: String,
+    // Byte offset into `value`, always on a char boundary.
+    cursor: usize,
+    preedit: Option<String>,
+    submitted: bool,
+}
+
+impl TextField {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn value(&self) -> &str {
+        &self.value
+    }
+
+    pub fn set_value(&mut self, value: &str) {
+        self.value = value.to_owned();
+        self.cursor = self.value.len();
+        self.preedit = None;
+    }
+
+    /// Returns true once after the user pressed return in this field.
+    pub fn take_submitted(&mut self) -> bool {
+        std::mem::take(&mut self.submitted)
+    }
+
+    pub fn handle_event(&mut self, event: &TextEvent) {
+        match event {
+            TextEvent::Insert(text) => {
+                // Pasted text can contain newlines and tabs, fields are single line.
+                let text = text.chars().filter(|c| !c.is_control()).collect::<String>();
+                self.value.insert_str(self.cursor, &text);
+                self.cursor += text.len();
+                self.preedit = None;
+            }
+            TextEvent::Preedit(text, _) => {
+                self.preedit = if text.is_empty() {
+                    None
+                } else {
+                    Some(text.clone())
+                };
+            }
+            TextEvent::Backspace => {
+                if let Some(prev) = self.prev_boundary() {
+                    self.value.replace_range(prev..self.cursor, "");
+                    self.cursor = prev;
+                }
+            }
+            TextEvent::Delete => {
+                if let Some(next) = self.next_boundary() {
+                    self.value.replace_range(self.cursor..next, "");
+                }
+            }
+            TextEvent::CursorLeft => self.cursor = self.prev_boundary().unwrap_or(self.cursor),
+            TextEvent::CursorRight => self.cursor = self.next_boundary().unwrap_or(self.cursor),
+            TextEvent::Home => self.cursor = 0,
+            TextEvent::End => self.cursor = self.value.len(),
+            TextEvent::Submit => self.submitted = true,
+            TextEvent::Cancel => self.preedit = None,
+        }
+    }
+
+    /// The text to draw: the value with any in-progress IME composition and, if focused, a cursor
+    /// spliced in.
+    pub fn display_text(&self, focused: bool) -> String {
+        let mut text = self.value.clone();
+        let mut cursor = self.cursor;
+
+        if let Some(preedit) = &self.preedit {
+            text.insert_str(cursor, preedit);
+            cursor += preedit.len();
+        }
+
+        if focused {
+            text.insert(cursor, '|');
+        }
+
+        text
+    }
+
+    fn prev_boundary(&self) -> Option<usize> {
+        self.value[..self.cursor]
+            .char_indices()
+            .next_back()
+            .map(|(i, _)| i)
+    }
+
+    fn next_boundary(&self) -> Option<usize> {
+        self.value[self.cursor..]
+            .chars()
+            .next()
+            .map(|c| self.cursor + c.len_utf8())
+    }
+}
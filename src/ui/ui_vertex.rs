@@ -2,12 +2,17 @@
 #[derive(Copy, Clone, Debug, bytemuck::Pod, bytemuck::Zeroable)]
 pub struct UiRenderVertex {
     pub position: [f32; 3],
-    pub color: [f32; 3], // TODO: Support alpha channel and maybe 64-bit components?
+    pub color: [f32; 4], // TODO: Support 64-bit components?
 }
 
 impl UiRenderVertex {
     pub fn new(verts: &[cgmath::Vector2<f32>], color: wgpu::Color) -> Vec<Self> {
-        let color = [color.r as f32, color.g as f32, color.b as f32];
+        let color = [
+            color.r as f32,
+            color.g as f32,
+            color.b as f32,
+            color.a as f32,
+        ];
 
         verts
             .iter()
@@ -31,7 +36,7 @@ impl UiRenderVertex {
                 wgpu::VertexAttribute {
                     offset: std::mem::size_of::<[f32; 3]>() as wgpu::BufferAddress,
                     shader_location: 1,
-                    format: wgpu::VertexFormat::Float32x3,
+                    format: wgpu::VertexFormat::Float32x4,
                 },
             ],
         }
@@ -0,0 +1,107 @@
+use std::{cell::Cell, rc::Rc};
+
+use cgmath::prelude::*;
+use winit::{dpi::PhysicalSize, event::VirtualKeyCode};
+
+use fooheppy::{
+    animation::{
+        clip::{AnimationClip, ClipPlayer, PlaybackMode},
+        state_machine::{AnimationStateMachine, Condition},
+    },
+    components::{animator::Animator, player_movement::PlayerMovement},
+    entity::Entity,
+    input::Input,
+    state::State,
+};
+
+fn positions(clip: &AnimationClip, steps: usize) -> Vec<usize> {
+    let mut player = ClipPlayer::default();
+    (0..steps)
+        .map(|_| {
+            player.advance(clip, 0.1);
+            player.position()
+        })
+        .collect()
+}
+
+#[test]
+fn clips_follow_their_playback_mode() {
+    let frames = [10, 11, 12];
+
+    let looping = AnimationClip::uniform("loop", &frames, 0.1, PlaybackMode::Loop);
+    assert_eq!(positions(&looping, 5), [1, 2, 0, 1, 2]);
+
+    let ping_pong = AnimationClip::uniform("ping_pong", &frames, 0.1, PlaybackMode::PingPong);
+    assert_eq!(positions(&ping_pong, 6), [1, 2, 1, 0, 1, 2]);
+
+    let once = AnimationClip::uniform("once", &frames, 0.1, PlaybackMode::Once);
+    let mut player = ClipPlayer::default();
+    assert_eq!(player.advance(&once, 0.25), [1, 2]);
+    assert!(!player.is_finished());
+    assert!(player.advance(&once, 1.0).is_empty());
+    assert!(player.is_finished());
+    assert_eq!(player.position(), 2);
+}
+
+#[test]
+fn animator_switches_states_from_movement_and_fires_events() {
+    let mut state = State::new_headless(PhysicalSize::new(800, 600)).unwrap();
+
+    let mut machine = AnimationStateMachine::new();
+    machine.add_state(AnimationClip::uniform(
+        "idle",
+        &[0],
+        0.5,
+        PlaybackMode::Loop,
+    ));
+
+    let mut walk = AnimationClip::uniform("walk", &[1, 2, 3], 0.1, PlaybackMode::Loop);
+    walk.add_event(1, "step");
+    machine.add_state(walk);
+
+    machine
+        .add_transition("idle", "walk", vec![Condition::Bool("moving".into(), true)])
+        .unwrap();
+    machine
+        .add_transition("walk", "idle", vec![Condition::Less("speed".into(), 0.1)])
+        .unwrap();
+    assert!(machine.add_transition("idle", "run", Vec::new()).is_err());
+
+    let steps = Rc::new(Cell::new(0));
+    let mut animator = Animator::new(machine).unwrap();
+    let counter = steps.clone();
+    animator.on_event("step", move |_, _| counter.set(counter.get() + 1));
+
+    let mut player = Entity::create(
+        cgmath::Vector2::zero(),
+        cgmath::Quaternion::zero(),
+        0,
+        state.sorting_layer("foreground").unwrap(),
+        None,
+    );
+    player.add_component(Box::new(PlayerMovement { walk_speed: 35.0 }));
+    player.add_component(Box::new(animator));
+    let id = state.add_entity(player).unwrap();
+
+    let mut input = Input::new();
+
+    state.step(input.take_read_only(), 0.05);
+    assert_eq!(state.entity(id).unwrap().get_frame(), 0);
+
+    input.set_key(VirtualKeyCode::D, true);
+    state.step(input.take_read_only(), 0.05);
+    assert_eq!(state.entity(id).unwrap().get_frame(), 1);
+
+    state.step(input.take_read_only(), 0.1);
+    assert_eq!(state.entity(id).unwrap().get_frame(), 2);
+    assert_eq!(steps.get(), 1);
+
+    // A long frame passes the event again on the way round.
+    state.step(input.take_read_only(), 0.3);
+    assert_eq!(state.entity(id).unwrap().get_frame(), 2);
+    assert_eq!(steps.get(), 2);
+
+    input.set_key(VirtualKeyCode::D, false);
+    state.step(input.take_read_only(), 0.05);
+    assert_eq!(state.entity(id).unwrap().get_frame(), 0);
+}
@@ -0,0 +1,162 @@
+use cgmath::prelude::*;
+use winit::{dpi::PhysicalSize, event::VirtualKeyCode};
+
+use fooheppy::{
+    components::player_movement::PlayerMovement,
+    entity::Entity,
+    input::Input,
+    physics::{
+        collider::Collider,
+        collision_grid::{CollisionGrid, TileCollision},
+    },
+    state::State,
+};
+
+fn unit_collider() -> Collider {
+    Collider {
+        origin: cgmath::Vector2::zero(),
+        width: 1.0,
+        height: 1.0,
+    }
+}
+
+// Rows are listed top first, `#` solid, `-` one-way, `/` a slope rising to the right.
+fn grid(layout: &str) -> CollisionGrid {
+    let rows: Vec<_> = layout
+        .lines()
+        .map(str::trim)
+        .filter(|row| !row.is_empty())
+        .collect();
+
+    let width = rows[0].len();
+    let height = rows.len();
+
+    let cells = rows
+        .iter()
+        .rev()
+        .flat_map(|row| row.chars())
+        .map(|cell| match cell {
+            '#' => TileCollision::Solid,
+            '-' => TileCollision::OneWay,
+            '/' => TileCollision::Slope {
+                left: 0.0,
+                right: 1.0,
+            },
+            _ => TileCollision::Empty,
+        })
+        .collect();
+
+    CollisionGrid::from_cells(width, height, cgmath::Vector2::zero(), cells).unwrap()
+}
+
+#[test]
+fn solid_cells_merge_into_rectangles() {
+    let grid = grid(
+        "
+        ##....
+        ##....
+        ######
+        ",
+    );
+
+    // The bottom row, then the 2x2 block above its left end.
+    let rects: Vec<_> = grid
+        .solid_rects()
+        .iter()
+        .map(|rect| (rect.origin, rect.width, rect.height))
+        .collect();
+
+    assert_eq!(
+        rects,
+        [((3.0, 0.5).into(), 6.0, 1.0), ((1.0, 2.0).into(), 2.0, 2.0)]
+    );
+}
+
+#[test]
+fn colliders_stop_at_walls_and_slide_along_them() {
+    let grid = grid(
+        "
+        #.....
+        #.....
+        ######
+        ",
+    );
+
+    // Resting on the floor and moving down and left into the corner.
+    let moved = grid.move_collider(&unit_collider(), (2.5, 1.5).into(), (-2.0, -0.5).into());
+
+    assert_eq!(moved.position, (1.5, 1.5).into());
+    assert!(moved.hit_x && moved.hit_y && moved.grounded);
+
+    // Moving along the floor isn't blocked by it.
+    let moved = grid.move_collider(&unit_collider(), (2.5, 1.5).into(), (1.0, 0.0).into());
+
+    assert_eq!(moved.position, (3.5, 1.5).into());
+    assert!(!moved.hit_x);
+}
+
+#[test]
+fn one_way_cells_only_block_from_above() {
+    let grid = grid(
+        "
+        ......
+        ..--..
+        ......
+        ",
+    );
+
+    let up = grid.move_collider(&unit_collider(), (2.5, 0.5).into(), (0.0, 2.0).into());
+    assert_eq!(up.position, (2.5, 2.5).into());
+    assert!(!up.hit_y);
+
+    let down = grid.move_collider(&unit_collider(), (2.5, 2.75).into(), (0.0, -1.0).into());
+    assert_eq!(down.position, (2.5, 2.5).into());
+    assert!(down.grounded);
+}
+
+#[test]
+fn slopes_lift_colliders_onto_them() {
+    let grid = grid(
+        "
+        ......
+        ../...
+        ######
+        ",
+    );
+
+    // Walking right from the floor into the slope, its bottom center lands a quarter of the way up.
+    let moved = grid.move_collider(&unit_collider(), (1.75, 1.5).into(), (0.5, 0.0).into());
+
+    assert_eq!(moved.position, (2.25, 1.75).into());
+    assert!(moved.grounded);
+}
+
+#[test]
+fn player_movement_is_blocked_by_collision_grids() {
+    let mut state = State::new_headless(PhysicalSize::new(800, 600)).unwrap();
+
+    state.add_collision_grid(grid("..#"));
+
+    let mut player = Entity::create(
+        (0.5, 0.5).into(),
+        cgmath::Quaternion::zero(),
+        0,
+        state.sorting_layer("foreground").unwrap(),
+        Some(unit_collider()),
+    );
+
+    player.add_component(Box::new(PlayerMovement { walk_speed: 35.0 }));
+    let index = state.add_entity(player).unwrap();
+
+    let mut input = Input::new();
+    input.set_key(VirtualKeyCode::D, true);
+
+    for _ in 0..60 {
+        state.step(input.take_read_only(), 1.0 / 60.0);
+    }
+
+    assert_eq!(
+        state.entity(index).unwrap().get_position(),
+        (1.5, 0.5).into()
+    );
+}
@@ -0,0 +1,367 @@
+//! Golden-image harness: loads a scene fixture into an offscreen state on a software adapter,
+//! steps it a fixed number of frames and compares the result with a reference PNG under
+//! `tests/golden`. Run with `UPDATE_GOLDEN=1` to (re)write the references, or with
+//! `SKIP_GOLDEN=1` to pass them on machines without any adapter.
+
+use std::path::{Path, PathBuf};
+
+use cgmath::prelude::*;
+use image::{Rgba, RgbaImage};
+use serde::Deserialize;
+use winit::{dpi::PhysicalSize, event::VirtualKeyCode};
+
+use fooheppy::{
+    camera::{Camera, CameraClear, CameraFollow, Viewport},
+    components::player_movement::PlayerMovement,
+    entity::Entity,
+    graphics::{material::Material, pixel_perfect::PixelPerfect},
+    input::Input,
+    parsing::{material::LoadMaterial, sprite_sheet::LoadSpriteSheet},
+    resources::Resource,
+    state::State,
+};
+
+pub const FIXED_DELTA_TIME: f64 = 1.0 / 60.0;
+
+#[derive(Deserialize)]
+pub struct Scene {
+    pub width: u32,
+    pub height: u32,
+    #[serde(default)]
+    pub frames: usize,
+    #[serde(default)]
+    pub developer_mode: bool,
+    #[serde(default)]
+    pub pixel_perfect: Option<ScenePixelPerfect>,
+    /// The first entry configures the main camera, any others are added after it.
+    #[serde(default)]
+    pub cameras: Vec<SceneCamera>,
+    #[serde(default)]
+    pub held_keys: Vec<String>,
+    pub materials: Vec<String>,
+    pub entities: Vec<SceneEntity>,
+    /// Largest difference allowed in any one channel of any one pixel.
+    #[serde(default)]
+    pub tolerance: u8,
+    /// Pixels allowed over the tolerance. Glyph rasterization on the software adapter isn't quite
+    /// stable between runs, so scenes with text need a few.
+    #[serde(default)]
+    pub max_mismatched_pixels: usize,
+}
+
+#[derive(Deserialize)]
+pub struct SceneCamera {
+    pub position: [f32; 2],
+    pub scale: f32,
+    /// Index of an entity the camera snaps to every frame.
+    #[serde(default)]
+    pub follow: Option<usize>,
+    /// `[x, y, width, height]` as fractions of the target.
+    #[serde(default)]
+    pub viewport: Option<[f32; 4]>,
+    /// RGBA in 0..1.
+    #[serde(default)]
+    pub clear: Option<[f64; 4]>,
+    #[serde(default)]
+    pub layer_mask: Option<u32>,
+}
+
+#[derive(Deserialize)]
+pub struct ScenePixelPerfect {
+    /// `[width, height]` of the low resolution target.
+    pub reference: [u32; 2],
+    pub pixels_per_unit: f32,
+}
+
+#[derive(Deserialize)]
+pub struct SceneEntity {
+    pub position: [f32; 2],
+    pub material: usize,
+    pub layer: String,
+    #[serde(default)]
+    pub walk_speed: Option<f32>,
+    #[serde(default)]
+    pub render_layers: Option<u32>,
+    #[serde(default)]
+    pub frame: usize,
+    /// RGBA in 0..1.
+    #[serde(default)]
+    pub tint: Option<[f64; 4]>,
+    #[serde(default)]
+    pub opacity: Option<f32>,
+    /// RGBA in 0..1.
+    #[serde(default)]
+    pub flash: Option<[f64; 4]>,
+    #[serde(default)]
+    pub flip_x: bool,
+    #[serde(default)]
+    pub flip_y: bool,
+}
+
+fn fixtures_dir() -> PathBuf {
+    Path::new(env!("CARGO_MANIFEST_DIR")).join("tests")
+}
+
+pub fn load_scene(name: &str) -> Scene {
+    let path = fixtures_dir()
+        .join("fixtures")
+        .join("scenes")
+        .join(format!("{name}.json"));
+
+    let source = std::fs::read_to_string(&path)
+        .unwrap_or_else(|e| panic!("Couldn't read scene fixture {}: {e}", path.display()));
+
+    serde_json::from_str(&source)
+        .unwrap_or_else(|e| panic!("Couldn't parse scene fixture {}: {e}", path.display()))
+}
+
+/**
+ * Builds the scene on a software adapter. Without one the test fails, unless `SKIP_GOLDEN` is set,
+ * in which case this returns `None` and the test is skipped.
+ */
+pub fn build_state(scene: &Scene) -> Option<State> {
+    let size = PhysicalSize::new(scene.width, scene.height);
+
+    let mut state = match pollster::block_on(State::new_offscreen(size, true)) {
+        Ok(state) => state,
+        Err(e) if std::env::var("SKIP_GOLDEN").is_ok() => {
+            eprintln!("Skipping golden test, no software adapter: {e:?}");
+            return None;
+        }
+        Err(e) => {
+            panic!("No software adapter for golden tests, set SKIP_GOLDEN=1 to skip them: {e:?}")
+        }
+    };
+
+    state.config_mut().set_developer_mode(scene.developer_mode);
+
+    if let Some(fixture) = &scene.pixel_perfect {
+        state.set_pixel_perfect(Some(PixelPerfect {
+            reference_width: fixture.reference[0],
+            reference_height: fixture.reference[1],
+            pixels_per_unit: fixture.pixels_per_unit,
+        }));
+    }
+
+    for (i, fixture) in scene.cameras.iter().enumerate() {
+        let index = if i == 0 {
+            0
+        } else {
+            state.add_camera(Camera::new(1.0, (0.0, 0.0, 5.0).into(), 1.0, 0.1, 100.0))
+        };
+
+        let camera = &mut state.cameras[index];
+
+        if let Some([x, y, width, height]) = fixture.viewport {
+            camera
+                .set_viewport(Viewport {
+                    x,
+                    y,
+                    width,
+                    height,
+                })
+                .unwrap();
+        }
+
+        if let Some([r, g, b, a]) = fixture.clear {
+            camera.clear = CameraClear::Color(wgpu::Color { r, g, b, a });
+        }
+
+        if let Some(layer_mask) = fixture.layer_mask {
+            camera.layer_mask = layer_mask;
+        }
+
+        camera.set_position(fixture.position.into());
+        camera.set_scale(fixture.scale);
+
+        if let Some(target) = fixture.follow {
+            camera.follow(CameraFollow {
+                target,
+                damping: 0.0,
+                dead_zone: cgmath::Vector2::zero(),
+            });
+        }
+    }
+
+    // Descriptors under `materials/` load as custom shader materials, other descriptors as sprite
+    // sheets and anything else as a plain texture.
+    for file_name in &scene.materials {
+        let graphics = state.graphics.as_ref().unwrap();
+
+        let material = if let Some(name) = file_name.strip_prefix("materials/") {
+            Resource::load_material(graphics, name).unwrap()
+        } else if file_name.ends_with(".json") {
+            Resource::load_sprite_sheet(graphics, file_name).unwrap()
+        } else {
+            let texture = pollster::block_on(Resource::load_texture(graphics, file_name)).unwrap();
+            Material::new(file_name.clone(), texture)
+        };
+
+        state.add_material(material);
+    }
+
+    for fixture in &scene.entities {
+        let layer = state.sorting_layer(&fixture.layer).unwrap();
+
+        let mut entity = Entity::create(
+            fixture.position.into(),
+            cgmath::Quaternion::zero(),
+            fixture.material,
+            layer,
+            None,
+        );
+
+        entity.set_frame(fixture.frame);
+
+        if let Some([r, g, b, a]) = fixture.tint {
+            entity.appearance.tint = wgpu::Color { r, g, b, a };
+        }
+
+        if let Some(opacity) = fixture.opacity {
+            entity.appearance.opacity = opacity;
+        }
+
+        if let Some([r, g, b, a]) = fixture.flash {
+            entity.appearance.flash = wgpu::Color { r, g, b, a };
+        }
+
+        entity.appearance.flip_x = fixture.flip_x;
+        entity.appearance.flip_y = fixture.flip_y;
+
+        if let Some(render_layers) = fixture.render_layers {
+            entity.set_render_layers(render_layers);
+        }
+
+        if let Some(walk_speed) = fixture.walk_speed {
+            entity.add_component(Box::new(PlayerMovement { walk_speed }));
+        }
+
+        state.add_entity(entity).unwrap();
+    }
+
+    Some(state)
+}
+
+pub fn render_scene(name: &str) -> Option<(Scene, RgbaImage)> {
+    let scene = load_scene(name);
+    let mut state = build_state(&scene)?;
+
+    let mut input = Input::new();
+
+    for key in &scene.held_keys {
+        let keycode = match key.as_str() {
+            "W" => VirtualKeyCode::W,
+            "A" => VirtualKeyCode::A,
+            "S" => VirtualKeyCode::S,
+            "D" => VirtualKeyCode::D,
+            other => panic!("Unknown key `{other}` in scene fixture."),
+        };
+
+        input.set_key(keycode, true);
+    }
+
+    for _ in 0..scene.frames {
+        state.step(input.take_read_only(), FIXED_DELTA_TIME);
+    }
+
+    let image = state.capture_frame().unwrap();
+    Some((scene, image))
+}
+
+/// Renders the named scene and compares it with `tests/golden/<name>.png`.
+pub fn assert_golden(name: &str) {
+    let Some((scene, actual)) = render_scene(name) else {
+        return;
+    };
+
+    assert_matches_golden(name, &scene, &actual);
+}
+
+/// Compares a render with `tests/golden/<name>.png`, within the scene's tolerances.
+pub fn assert_matches_golden(name: &str, scene: &Scene, actual: &RgbaImage) {
+    let reference_path = fixtures_dir().join("golden").join(format!("{name}.png"));
+
+    if std::env::var("UPDATE_GOLDEN").is_ok() {
+        actual.save(&reference_path).unwrap();
+        eprintln!("Wrote golden image {}", reference_path.display());
+        return;
+    }
+
+    if !reference_path.exists() {
+        let (actual_path, _) = write_failure(name, actual, None);
+        panic!(
+            "Golden image {} is missing, rerun with UPDATE_GOLDEN=1 to accept {}",
+            reference_path.display(),
+            actual_path.display(),
+        );
+    }
+
+    let expected = image::open(&reference_path).unwrap().to_rgba8();
+
+    if expected.dimensions() != actual.dimensions() {
+        let (actual_path, _) = write_failure(name, actual, None);
+        panic!(
+            "Golden image {name} is {:?} but the render is {:?}, see {}",
+            expected.dimensions(),
+            actual.dimensions(),
+            actual_path.display(),
+        );
+    }
+
+    let (diff, mismatched) = diff_images(&expected, actual, scene.tolerance);
+
+    if mismatched > scene.max_mismatched_pixels {
+        let (actual_path, diff_path) = write_failure(name, actual, Some(&diff));
+        panic!(
+            "Golden image {name} differs in {mismatched} pixels (tolerance {}, {} allowed), see {} and {}",
+            scene.tolerance,
+            scene.max_mismatched_pixels,
+            actual_path.display(),
+            diff_path.unwrap().display(),
+        );
+    }
+}
+
+/// Mismatched pixels are drawn red over a faded copy of the reference.
+fn diff_images(expected: &RgbaImage, actual: &RgbaImage, tolerance: u8) -> (RgbaImage, usize) {
+    let mut mismatched = 0;
+
+    let diff = RgbaImage::from_fn(expected.width(), expected.height(), |x, y| {
+        let e = expected.get_pixel(x, y);
+        let a = actual.get_pixel(x, y);
+
+        let over =
+            e.0.iter()
+                .zip(a.0.iter())
+                .any(|(e, a)| e.abs_diff(*a) > tolerance);
+
+        if over {
+            mismatched += 1;
+            Rgba([255, 0, 0, 255])
+        } else {
+            Rgba([e[0] / 4, e[1] / 4, e[2] / 4, 255])
+        }
+    });
+
+    (diff, mismatched)
+}
+
+fn write_failure(
+    name: &str,
+    actual: &RgbaImage,
+    diff: Option<&RgbaImage>,
+) -> (PathBuf, Option<PathBuf>) {
+    let dir = Path::new(env!("CARGO_TARGET_TMPDIR")).join("golden");
+    std::fs::create_dir_all(&dir).unwrap();
+
+    let actual_path = dir.join(format!("{name}-actual.png"));
+    actual.save(&actual_path).unwrap();
+
+    let diff_path = diff.map(|diff| {
+        let path = dir.join(format!("{name}-diff.png"));
+        diff.save(&path).unwrap();
+        path
+    });
+
+    (actual_path, diff_path)
+}
@@ -0,0 +1,274 @@
+use cgmath::prelude::*;
+use winit::{dpi::PhysicalSize, event::VirtualKeyCode};
+
+use fooheppy::{
+    camera::{
+        effects::{CameraEffects, EffectTransform},
+        Camera, CameraBounds, CameraFollow, Viewport,
+    },
+    components::{player_movement::PlayerMovement, Component},
+    entity::Entity,
+    graphics::pixel_perfect::{Letterbox, PixelPerfect},
+    input::{Input, ReadOnlyInput},
+    state::State,
+};
+
+fn create_player(state: &State) -> Entity {
+    let mut player = Entity::create(
+        cgmath::Vector2::zero(),
+        cgmath::Quaternion::zero(),
+        0,
+        state.sorting_layer("foreground").unwrap(),
+        None,
+    );
+
+    player.add_component(Box::new(PlayerMovement { walk_speed: 35.0 }));
+    player
+}
+
+#[test]
+fn headless_state_has_no_renderer() {
+    let mut state = State::new_headless(PhysicalSize::new(800, 600)).unwrap();
+
+    assert!(state.is_headless());
+    assert_eq!(state.num_entities(), 0);
+    assert!(state.render().is_ok());
+}
+
+#[test]
+fn step_uses_supplied_delta_time() {
+    let mut state = State::new_headless(PhysicalSize::new(800, 600)).unwrap();
+    let player = create_player(&state);
+    let id = state.add_entity(player).unwrap();
+
+    state.cameras[0].follow(CameraFollow {
+        target: id,
+        damping: 0.0,
+        dead_zone: cgmath::Vector2::zero(),
+    });
+
+    let mut input = Input::new();
+    input.set_key(VirtualKeyCode::D, true);
+
+    state.step(input.take_read_only(), 0.5);
+
+    assert_eq!(state.delta_time(), 0.5);
+
+    let position = state.entity(id).unwrap().get_position();
+    assert!((position.x - 0.07 * 35.0 * 0.5).abs() < f32::EPSILON);
+    assert_eq!(position.y, 0.0);
+    assert_eq!(state.cameras[0].position.x, position.x);
+
+    state.step(ReadOnlyInput::new(), 0.5);
+
+    assert_eq!(state.entity(id).unwrap().get_position(), position);
+}
+
+#[test]
+fn camera_follow_stays_in_dead_zone_and_bounds() {
+    let mut state = State::new_headless(PhysicalSize::new(800, 400)).unwrap();
+    let player = create_player(&state);
+    let id = state.add_entity(player).unwrap();
+
+    state.cameras[0].set_scale(2.0);
+    state.cameras[0].bounds = Some(CameraBounds {
+        min: cgmath::Vector2::new(-10.0, -10.0),
+        max: cgmath::Vector2::new(6.0, 10.0),
+    });
+    state.cameras[0].follow(CameraFollow {
+        target: id,
+        damping: 0.0,
+        dead_zone: cgmath::Vector2::new(1.0, 1.0),
+    });
+
+    let mut input = Input::new();
+    input.set_key(VirtualKeyCode::D, true);
+
+    // Inside the dead zone the camera doesn't move.
+    state.step(input.take_read_only(), 0.2);
+    assert_eq!(state.cameras[0].position.x, 0.0);
+
+    // Past it the camera trails the player by the dead zone...
+    state.step(input.take_read_only(), 0.4);
+    let player_x = state.entity(id).unwrap().get_position().x;
+    assert!((state.cameras[0].position.x - (player_x - 1.0)).abs() < 1e-5);
+
+    // ...until the right edge of the view reaches the bounds.
+    for _ in 0..10 {
+        state.step(input.take_read_only(), 0.5);
+    }
+    assert_eq!(
+        state.cameras[0].position.x,
+        6.0 - state.cameras[0].half_extents().x
+    );
+}
+
+#[test]
+fn camera_effects_wear_off_without_moving_the_camera() {
+    let mut state = State::new_headless(PhysicalSize::new(800, 600)).unwrap();
+    let base = cgmath::Vector2::new(state.cameras[0].position.x, state.cameras[0].position.y);
+    let scale = state.cameras[0].scale;
+
+    let effects = &mut state.cameras[0].effects;
+    effects.add_trauma(0.8);
+    effects.punch(cgmath::Vector2::new(4.0, 0.0));
+    effects.zoom_pulse(0.25, 0.5);
+    effects.pan_to(base + cgmath::Vector2::new(10.0, 0.0), 0.5, 0.25);
+
+    state.step(ReadOnlyInput::new(), 0.25);
+
+    let sample = state.cameras[0].effects.sample(base);
+    assert!(sample.offset.x > 0.0);
+    assert!(sample.zoom < 1.0);
+    assert!(state.cameras[0].effects.is_panning());
+
+    for _ in 0..20 {
+        state.step(ReadOnlyInput::new(), 0.25);
+    }
+
+    let camera = &state.cameras[0];
+    assert_eq!(camera.effects.sample(base), EffectTransform::IDENTITY);
+    assert_eq!(camera.effects.trauma(), 0.0);
+    assert!(!camera.effects.is_panning());
+    assert_eq!(camera.position.x, base.x);
+    assert_eq!(camera.scale, scale);
+}
+
+#[test]
+fn pixel_perfect_locks_scale_to_reference_resolution() {
+    let mut state = State::new_headless(PhysicalSize::new(800, 600)).unwrap();
+
+    let settings = PixelPerfect {
+        reference_width: 320,
+        reference_height: 180,
+        pixels_per_unit: 16.0,
+    };
+
+    state.set_pixel_perfect(Some(settings));
+    assert_eq!(state.cameras[0].scale, 180.0 / 32.0);
+
+    // Neither zooming nor resizing the window changes how many texels are visible.
+    state.cameras[0].zoom_by(2.0);
+    state.resize(PhysicalSize::new(1000, 700));
+    assert_eq!(state.cameras[0].scale, 180.0 / 32.0);
+    assert_eq!(state.cameras[0].half_extents().x, 320.0 / 32.0);
+
+    assert_eq!(
+        settings.letterbox(1000, 700),
+        Letterbox {
+            x: 20.0,
+            y: 80.0,
+            width: 960.0,
+            height: 540.0,
+        }
+    );
+    assert_eq!(settings.upscale(160, 90), 0.5);
+}
+
+#[test]
+fn despawned_slots_are_reused() {
+    let mut state = State::new_headless(PhysicalSize::new(800, 600)).unwrap();
+
+    let first = state.add_entity(create_player(&state)).unwrap();
+    let second = state.add_entity(create_player(&state)).unwrap();
+
+    assert!(state.despawn_entity(first).is_some());
+    assert!(state.despawn_entity(first).is_none());
+    assert!(state.entity(first).is_none());
+
+    let reused = state.add_entity(create_player(&state)).unwrap();
+
+    assert_eq!(reused, first);
+    assert_eq!(state.entity(reused).unwrap().get_id(), first);
+    assert_eq!(state.entity(second).unwrap().get_id(), second);
+    assert_eq!(state.num_entities(), 2);
+}
+
+struct DespawnSelf;
+
+impl Component for DespawnSelf {
+    fn update(&mut self, entity: &mut Entity, state: &mut State, _delta_time: f64) {
+        assert!(state.despawn_entity(entity.get_id()).is_none());
+    }
+}
+
+#[test]
+fn entities_can_despawn_themselves() {
+    let mut state = State::new_headless(PhysicalSize::new(800, 600)).unwrap();
+
+    let mut doomed = create_player(&state);
+    doomed.add_component(Box::new(DespawnSelf));
+    let id = state.add_entity(doomed).unwrap();
+    let survivor = state.add_entity(create_player(&state)).unwrap();
+
+    state.step(ReadOnlyInput::new(), 0.1);
+
+    assert!(state.entity(id).is_none());
+    assert!(state.entity(survivor).is_some());
+    assert_eq!(state.add_entity(create_player(&state)).unwrap(), id);
+}
+
+#[test]
+fn despawning_the_followed_entity_stops_the_camera_following() {
+    let mut state = State::new_headless(PhysicalSize::new(800, 600)).unwrap();
+    let id = state.add_entity(create_player(&state)).unwrap();
+
+    state.cameras[0].follow(CameraFollow {
+        target: id,
+        damping: 0.0,
+        dead_zone: cgmath::Vector2::zero(),
+    });
+
+    state.despawn_entity(id).unwrap();
+    assert!(state.cameras[0].following().is_none());
+
+    // Whatever reuses the slot isn't followed.
+    let mut other = create_player(&state);
+    other.move_by(cgmath::Vector2::new(5.0, 0.0));
+    assert_eq!(state.add_entity(other).unwrap(), id);
+
+    state.step(ReadOnlyInput::new(), 0.5);
+    assert_eq!(state.cameras[0].position.x, 0.0);
+}
+
+#[test]
+fn degenerate_viewports_are_rejected() {
+    let mut camera = Camera::new(2.0, (0.0, 0.0, 5.0).into(), 1.0, 0.1, 100.0);
+
+    let half = Viewport {
+        width: 0.5,
+        ..Viewport::FULL
+    };
+
+    for width in [0.0, -1.0, f32::NAN, f32::INFINITY] {
+        assert!(camera.set_viewport(Viewport { width, ..half }).is_err());
+        assert_eq!(camera.aspect, 2.0);
+    }
+
+    camera.set_viewport(half).unwrap();
+    assert_eq!(camera.aspect, 1.0);
+
+    // Aspect comes from the target, not from scaling the previous one.
+    camera.resize(800, 400);
+    assert_eq!(camera.aspect, 1.0);
+    camera.set_viewport(Viewport::FULL).unwrap();
+    assert_eq!(camera.aspect, 2.0);
+}
+
+#[test]
+fn camera_shake_stays_smooth_after_hours_of_play() {
+    let mut effects = CameraEffects::default();
+    effects.shake.decay = 0.0;
+    effects.update(5.0 * 60.0 * 60.0);
+    effects.add_trauma(1.0);
+
+    let base = cgmath::Vector2::zero();
+    let before = effects.sample(base).offset;
+
+    // Far less than an f32 can resolve five hours in.
+    effects.update(1e-4);
+    let after = effects.sample(base).offset;
+
+    assert_ne!(before, after);
+    assert!((after - before).magnitude() < 0.01);
+}
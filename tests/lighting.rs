@@ -0,0 +1,145 @@
+use cgmath::prelude::*;
+use winit::dpi::PhysicalSize;
+
+use fooheppy::{
+    components::{
+        light::{PointLight, SpotLight},
+        shadow_caster::{Occluder, ShadowCaster},
+        Component,
+    },
+    entity::Entity,
+    graphics::lighting::{Light, Lighting, MAX_LIGHTS},
+    input::Input,
+    physics::collider::Collider,
+    state::State,
+};
+
+fn spawn(state: &mut State, collider: Option<Collider>, component: Box<dyn Component>) {
+    let mut entity = Entity::create(
+        (1.0, 2.0).into(),
+        cgmath::Quaternion::zero(),
+        0,
+        state.sorting_layer("foreground").unwrap(),
+        collider,
+    );
+
+    entity.add_component(component);
+    state.add_entity(entity).unwrap();
+}
+
+fn light_at(position: cgmath::Vector2<f32>) -> Light {
+    Light {
+        position,
+        color: wgpu::Color::WHITE,
+        intensity: 1.0,
+        radius: 5.0,
+        falloff: 1.0,
+        height: 1.0,
+        cone: None,
+        cast_shadows: true,
+    }
+}
+
+#[test]
+fn components_submit_lights_and_occluders_each_step() {
+    let mut state = State::new_headless(PhysicalSize::new(800, 600)).unwrap();
+    let mut input = Input::new();
+
+    let mut torch = PointLight::new(wgpu::Color::WHITE, 3.0);
+    torch.offset = (0.0, 0.5).into();
+
+    let collider = Collider {
+        origin: (0.5, 0.0).into(),
+        width: 1.0,
+        height: 2.0,
+    };
+
+    spawn(&mut state, None, Box::new(torch));
+    spawn(
+        &mut state,
+        None,
+        Box::new(SpotLight::new(wgpu::Color::WHITE, 3.0, 0.0, 0.5)),
+    );
+    spawn(
+        &mut state,
+        Some(collider),
+        Box::new(ShadowCaster {
+            occluder: Occluder::Collider,
+        }),
+    );
+    // Casters without colliders have nothing to block light with.
+    spawn(
+        &mut state,
+        None,
+        Box::new(ShadowCaster {
+            occluder: Occluder::Collider,
+        }),
+    );
+
+    state.step(input.take_read_only(), 0.0);
+    state.step(input.take_read_only(), 0.0);
+
+    let lighting = state.lighting();
+    let lights = lighting.lights();
+
+    assert_eq!(lights.len(), 2);
+    assert_eq!(lights[0].position, (1.0, 2.5).into());
+    assert_eq!(lights[0].cone, None);
+    assert_eq!(lights[1].cone.unwrap().angle, 0.5);
+
+    let occluders: Vec<_> = lighting.occluders().collect();
+
+    assert_eq!(
+        occluders,
+        [[
+            (1.0, 1.0).into(),
+            (2.0, 1.0).into(),
+            (2.0, 3.0).into(),
+            (1.0, 3.0).into()
+        ]]
+    );
+
+    // Nothing was lit, lighting waits for an ambient level.
+    assert!(!lighting.is_enabled());
+}
+
+#[test]
+fn shadows_stretch_from_edges_facing_away_from_the_light() {
+    let square = [(0.0, 0.0), (1.0, 0.0), (1.0, 1.0), (0.0, 1.0)].map(cgmath::Vector2::from);
+
+    for winding in [square.to_vec(), square.iter().rev().copied().collect()] {
+        let mut lighting = Lighting::default();
+        lighting.add_occluder(winding);
+
+        let mut vertices = Vec::new();
+        lighting.shadow_triangles((-1.0, 0.5).into(), &mut vertices);
+
+        // The right, top and bottom edges, two triangles each, with the near corners staying put.
+        assert_eq!(vertices.len(), 18);
+
+        let near: Vec<_> = vertices.iter().filter(|vertex| vertex[2] == 1.0).collect();
+        assert!(near.iter().all(|vertex| vertex[0] >= 0.0));
+
+        // Far ends point away from the light.
+        let far = vertices.iter().filter(|vertex| vertex[2] == 0.0);
+        assert!(far.into_iter().all(|vertex| vertex[0] > 0.0));
+    }
+}
+
+#[test]
+fn lights_past_the_limit_are_dropped() {
+    let mut lighting = Lighting::default();
+
+    for _ in 0..MAX_LIGHTS {
+        assert!(lighting.add_light(light_at(cgmath::Vector2::zero())));
+    }
+
+    assert!(!lighting.add_light(light_at(cgmath::Vector2::zero())));
+
+    // Too few points for a polygon.
+    lighting.add_occluder([(0.0, 0.0).into(), (1.0, 0.0).into()]);
+    assert_eq!(lighting.occluders().count(), 0);
+
+    lighting.clear();
+    assert!(lighting.lights().is_empty());
+}
@@ -0,0 +1,160 @@
+use cgmath::prelude::*;
+use winit::dpi::PhysicalSize;
+
+use fooheppy::{
+    animation::curve::Curve,
+    components::{
+        particle_emitter::{Burst, ParticleEmitter, SimulationSpace},
+        Component,
+    },
+    entity::Entity,
+    input::Input,
+    state::State,
+};
+
+// Moves its entity one unit right every update.
+struct Mover;
+
+impl Component for Mover {
+    fn update(&mut self, entity: &mut Entity, _state: &mut State, _delta_time: f64) {
+        entity.move_by((1.0, 0.0).into());
+    }
+}
+
+fn spawn(state: &mut State, components: Vec<Box<dyn Component>>) -> usize {
+    let mut entity = Entity::create(
+        (0.0, 0.0).into(),
+        cgmath::Quaternion::zero(),
+        0,
+        state.sorting_layer("foreground").unwrap(),
+        None,
+    );
+
+    for component in components {
+        entity.add_component(component);
+    }
+
+    state.add_entity(entity).unwrap()
+}
+
+// Particles that neither move nor die, emitted only by bursts.
+fn still_emitter() -> ParticleEmitter {
+    let mut emitter = ParticleEmitter::new(0);
+    emitter.rate = 0.0;
+    emitter.lifetime = 100.0..100.0;
+    emitter.velocity_min = cgmath::Vector2::zero();
+    emitter.velocity_max = cgmath::Vector2::zero();
+    emitter
+}
+
+fn step(state: &mut State, input: &mut Input, times: usize) {
+    for _ in 0..times {
+        state.step(input.take_read_only(), 0.25);
+    }
+}
+
+fn positions(state: &State) -> Vec<[f32; 2]> {
+    state
+        .particles()
+        .instances()
+        .iter()
+        .map(|instance| [instance.transform[3][0], instance.transform[3][1]])
+        .collect()
+}
+
+#[test]
+fn curves_blend_between_sorted_keys() {
+    let curve = Curve::new(vec![(1.0, 10.0), (0.0, 0.0), (0.5, 2.0)]).unwrap();
+
+    assert_eq!(curve.evaluate(-1.0), 0.0);
+    assert_eq!(curve.evaluate(0.25), 1.0);
+    assert_eq!(curve.evaluate(0.75), 6.0);
+    assert_eq!(curve.evaluate(2.0), 10.0);
+
+    assert!(Curve::<f32>::new(Vec::new()).is_err());
+}
+
+#[test]
+fn bursts_repeat_every_cycle_until_looping_stops() {
+    let mut state = State::new_headless(PhysicalSize::new(800, 600)).unwrap();
+    let mut input = Input::new();
+
+    let mut emitter = still_emitter();
+    emitter.bursts = vec![Burst {
+        time: 0.0,
+        count: 3,
+    }];
+    emitter.duration = 0.5;
+    emitter.looping = false;
+
+    let mut looping = still_emitter();
+    looping.bursts = emitter.bursts.clone();
+    looping.duration = 0.5;
+
+    spawn(&mut state, vec![Box::new(emitter), Box::new(looping)]);
+
+    // Steps start at 0, 0.25, 0.5 and 0.75 seconds, the looping emitter bursts at 0 and 0.5.
+    step(&mut state, &mut input, 4);
+
+    let batches: Vec<_> = state
+        .particles()
+        .batches()
+        .iter()
+        .map(|batch| batch.instances.len())
+        .collect();
+
+    assert_eq!(batches, [3, 6]);
+}
+
+#[test]
+fn emitters_share_the_particle_budget_in_update_order() {
+    let mut state = State::new_headless(PhysicalSize::new(800, 600)).unwrap();
+    let mut input = Input::new();
+
+    state.particles_mut().set_budget(5);
+
+    for _ in 0..2 {
+        let mut emitter = still_emitter();
+        emitter.burst(4);
+        spawn(&mut state, vec![Box::new(emitter)]);
+    }
+
+    step(&mut state, &mut input, 1);
+
+    let batches: Vec<_> = state
+        .particles()
+        .batches()
+        .iter()
+        .map(|batch| batch.instances.len())
+        .collect();
+
+    assert_eq!(batches, [4, 1]);
+    assert_eq!(state.particles().claimed(), 5);
+
+    // Lowering the budget drops the oldest particles.
+    state.particles_mut().set_budget(2);
+    step(&mut state, &mut input, 1);
+
+    assert_eq!(state.particles().instances().len(), 2);
+}
+
+#[test]
+fn local_particles_follow_their_entity() {
+    let mut state = State::new_headless(PhysicalSize::new(800, 600)).unwrap();
+    let mut input = Input::new();
+
+    let mut world = still_emitter();
+    world.burst(1);
+
+    let mut local = still_emitter();
+    local.space = SimulationSpace::Local;
+    local.burst(1);
+
+    spawn(&mut state, vec![Box::new(Mover), Box::new(world)]);
+    spawn(&mut state, vec![Box::new(Mover), Box::new(local)]);
+
+    step(&mut state, &mut input, 3);
+
+    // Both were emitted at x = 1, after the first move.
+    assert_eq!(positions(&state), [[1.0, 0.0], [3.0, 0.0]]);
+}
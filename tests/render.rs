@@ -0,0 +1,713 @@
+use std::rc::Rc;
+
+use cgmath::prelude::*;
+
+use fooheppy::{
+    animation::{clip::AnimationFrame, curve::Curve},
+    camera::{Camera, CameraBounds, CameraClear, Viewport},
+    components::{
+        light::{PointLight, SpotLight},
+        particle_emitter::ParticleEmitter,
+        shadow_caster::{Occluder, ShadowCaster},
+        Component,
+    },
+    entity::Entity,
+    graphics::{
+        pipeline::BlendMode, post_process::PostEffect, render_queue::RenderStats,
+        shader::ShaderValue,
+    },
+    input::Input,
+    parsing::tiled::{LoadTiledMap, TiledComponents},
+    physics::collider::Collider,
+    resources::Resource,
+    tilemap::{Tile, Tilemap},
+};
+
+mod common;
+
+#[test]
+fn clear_color() {
+    common::assert_golden("clear");
+}
+
+#[test]
+fn sprite_layers() {
+    common::assert_golden("sprites");
+}
+
+#[test]
+fn player_walk() {
+    common::assert_golden("player_walk");
+}
+
+#[test]
+fn debug_ui() {
+    common::assert_golden("ui");
+}
+
+#[test]
+fn text_fields_draw_outside_developer_mode() {
+    let scene = common::load_scene("text_field");
+    let Some(mut state) = common::build_state(&scene) else {
+        return;
+    };
+
+    state.load_ui("name_entry.nml", "name_entry.json").unwrap();
+    assert!(state.focus_text_field("name"));
+
+    // Only the field is drawn, with its cursor.
+    state.step(Input::new().take_read_only(), 0.0);
+    let actual = state.capture_frame().unwrap();
+
+    common::assert_matches_golden("text_field", &scene, &actual);
+}
+
+#[test]
+fn split_screen_viewports() {
+    common::assert_golden("split_screen");
+}
+
+#[test]
+fn cameras_replace_their_viewport_with_the_clear_color_before_any_step() {
+    let scene = common::load_scene("clear");
+    let Some(mut state) = common::build_state(&scene) else {
+        return;
+    };
+
+    let mut camera = Camera::new(1.0, (0.0, 0.0, 5.0).into(), 1.0, 0.1, 100.0);
+    camera.clear = CameraClear::Color(wgpu::Color {
+        r: 1.0,
+        g: 0.0,
+        b: 0.0,
+        a: 0.5,
+    });
+    camera
+        .set_viewport(Viewport {
+            x: 0.5,
+            y: 0.0,
+            width: 0.5,
+            height: 1.0,
+        })
+        .unwrap();
+    state.add_camera(camera);
+
+    // A translucent clear isn't blended over the main camera's.
+    let actual = state.capture_frame().unwrap();
+    assert_eq!(actual.get_pixel(120, 60).0, [255, 0, 0, 128]);
+}
+
+#[test]
+fn pixel_perfect_upscale() {
+    common::assert_golden("pixel_perfect");
+}
+
+#[test]
+fn sprite_sheet_frames() {
+    common::assert_golden("sprite_sheet");
+}
+
+#[test]
+fn sprite_tint_opacity_flash_and_flips() {
+    common::assert_golden("appearance");
+}
+
+#[test]
+fn packed_textures_share_an_atlas_page() {
+    let scene = common::load_scene("clear");
+    let Some(state) = common::build_state(&scene) else {
+        return;
+    };
+
+    let graphics = state.graphics.as_ref().unwrap();
+    let grass = Resource::read_texture(graphics, "grass.png").unwrap();
+    let ball = Resource::read_texture(graphics, "ball.png").unwrap();
+
+    assert!(Rc::ptr_eq(&grass.page, &ball.page));
+    assert_ne!(grass.region, ball.region);
+    assert_eq!((ball.width, ball.height), (16, 16));
+}
+
+#[test]
+fn sprite_buffer_grows_without_losing_existing_sprites() {
+    let scene = common::load_scene("sprites");
+    let Some(mut state) = common::build_state(&scene) else {
+        return;
+    };
+
+    let mut input = Input::new();
+    state.step(input.take_read_only(), 0.0);
+    let before = state.capture_frame().unwrap();
+
+    // Well past the initial allocation, and far enough away that none of them are in view.
+    for i in 0..3000 {
+        let entity = Entity::create(
+            cgmath::Vector2::new(1000.0 + i as f32, 1000.0),
+            cgmath::Quaternion::zero(),
+            0,
+            state.sorting_layer("background").unwrap(),
+            None,
+        );
+
+        state.add_entity(entity).unwrap();
+    }
+
+    state.step(input.take_read_only(), 0.0);
+    let after = state.capture_frame().unwrap();
+
+    assert!(before == after);
+}
+
+#[test]
+fn render_stats_count_batches_and_binds() {
+    let scene = common::load_scene("sprites");
+    let Some(mut state) = common::build_state(&scene) else {
+        return;
+    };
+
+    state.step(Input::new().take_read_only(), 0.0);
+    state.capture_frame().unwrap();
+
+    // One batch for the grass and one for the balls, both textures are packed onto one page.
+    assert_eq!(
+        state.graphics.as_ref().unwrap().render_stats(),
+        RenderStats {
+            draw_calls: 2,
+            binds: 1,
+            sprites: 37,
+        }
+    );
+}
+
+#[test]
+fn tilemap_layers_flips_animation_and_edits() {
+    let scene = common::load_scene("tilemap");
+    let Some(mut state) = common::build_state(&scene) else {
+        return;
+    };
+
+    let background = state.sorting_layer("background").unwrap();
+    // The sheet's two cell wide `top` region, after its four grid cells.
+    let top = 4;
+
+    let mut tilemap = Tilemap::new(6, 4, 0, (-3.0, -2.0).into());
+    let ground = tilemap.add_layer("ground", background);
+    let details = tilemap.add_layer("details", background);
+
+    tilemap.fill(ground, Some(Tile::new(1)));
+    tilemap
+        .set_tile(details, 0, 0, Some(Tile::new(top)))
+        .unwrap();
+
+    let flipped = Tile {
+        index: top,
+        flip_x: true,
+        flip_y: true,
+    };
+    tilemap.set_tile(details, 5, 3, Some(flipped)).unwrap();
+
+    // Blue for a tenth of a second, then yellow.
+    tilemap.set_tile(details, 1, 2, Some(Tile::new(2))).unwrap();
+    tilemap.animate_tile(
+        2,
+        vec![
+            AnimationFrame {
+                frame: 2,
+                duration: 0.1,
+            },
+            AnimationFrame {
+                frame: 3,
+                duration: 0.1,
+            },
+        ],
+    );
+
+    let index = state.add_tilemap(tilemap);
+    let mut input = Input::new();
+
+    state.step(input.take_read_only(), 0.0);
+    state.capture_frame().unwrap();
+
+    // A draw per chunk of each layer and one for the ball, tiles count as sprites.
+    assert_eq!(
+        state.graphics.as_ref().unwrap().render_stats(),
+        RenderStats {
+            draw_calls: 3,
+            binds: 1,
+            sprites: 28,
+        }
+    );
+
+    let edited = state.tilemap_mut(index).unwrap();
+    edited.set_tile(details, 4, 0, Some(Tile::new(0))).unwrap();
+    edited.set_tile(ground, 2, 1, None).unwrap();
+
+    state.step(input.take_read_only(), 0.15);
+    let actual = state.capture_frame().unwrap();
+
+    common::assert_matches_golden("tilemap", &scene, &actual);
+}
+
+#[test]
+fn tiled_map_spawns_with_its_tileset() {
+    let scene = common::load_scene("tilemap");
+    let Some(mut state) = common::build_state(&scene) else {
+        return;
+    };
+
+    let map = Resource::load_tiled_map("example.tmx").unwrap();
+    let spawned = map
+        .spawn(&mut state, (-2.0, -1.5).into(), &TiledComponents::new())
+        .unwrap();
+
+    assert_eq!(spawned.materials.len(), 1);
+
+    state.step(Input::new().take_read_only(), 0.0);
+    let actual = state.capture_frame().unwrap();
+
+    common::assert_matches_golden("tiled", &scene, &actual);
+}
+
+#[test]
+fn particle_emitter_draws_in_one_instanced_call() {
+    let scene = common::load_scene("tilemap");
+    let Some(mut state) = common::build_state(&scene) else {
+        return;
+    };
+
+    // The sheet's blue cell, fading and shrinking as it rises.
+    let mut emitter = ParticleEmitter::new(0).with_seed(7);
+    emitter.frame = 2;
+    emitter.rate = 0.0;
+    emitter.lifetime = 1.0..2.0;
+    emitter.velocity_min = (-1.5, 0.5).into();
+    emitter.velocity_max = (1.5, 2.0).into();
+    emitter.gravity = (0.0, -1.0).into();
+    emitter.color = Curve::linear(
+        wgpu::Color::WHITE,
+        wgpu::Color {
+            a: 0.0,
+            ..wgpu::Color::WHITE
+        },
+    );
+    emitter.size = Curve::linear(0.5, 0.1);
+    emitter.burst(20);
+
+    let mut entity = Entity::create(
+        (0.0, -1.0).into(),
+        cgmath::Quaternion::zero(),
+        0,
+        state.sorting_layer("background").unwrap(),
+        None,
+    );
+    // The emitter's own sprite is hidden, and still drawn in the particles' batch.
+    entity.appearance.opacity = 0.0;
+    entity.add_component(Box::new(emitter));
+    state.add_entity(entity).unwrap();
+
+    let mut input = Input::new();
+    state.step(input.take_read_only(), 0.0);
+    state.step(input.take_read_only(), 0.5);
+    let actual = state.capture_frame().unwrap();
+
+    // One draw for every particle and one for the ball over them.
+    assert_eq!(
+        state.graphics.as_ref().unwrap().render_stats(),
+        RenderStats {
+            draw_calls: 2,
+            binds: 1,
+            sprites: 22,
+        }
+    );
+
+    common::assert_matches_golden("particles", &scene, &actual);
+}
+
+#[test]
+fn custom_shader_materials_read_their_params() {
+    let scene = common::load_scene("custom_shader");
+    let Some(mut state) = common::build_state(&scene) else {
+        return;
+    };
+
+    state
+        .set_material_param(1, "amount", ShaderValue::Float(0.25))
+        .unwrap();
+
+    // Parameters keep the type they were declared with.
+    assert!(state
+        .set_material_param(1, "amount", ShaderValue::Vector2((0.0, 0.0).into()))
+        .is_err());
+    assert!(state
+        .set_material_param(1, "missing", ShaderValue::Float(0.0))
+        .is_err());
+    assert!(state
+        .set_material_param(0, "amount", ShaderValue::Float(0.0))
+        .is_err());
+
+    state.step(Input::new().take_read_only(), 0.0);
+    let actual = state.capture_frame().unwrap();
+
+    // Both balls share an atlas page, the dissolving one also binds its parameters.
+    assert_eq!(
+        state.graphics.as_ref().unwrap().render_stats(),
+        RenderStats {
+            draw_calls: 2,
+            binds: 2,
+            sprites: 2,
+        }
+    );
+
+    common::assert_matches_golden("custom_shader", &scene, &actual);
+}
+
+#[test]
+fn materials_blend_with_their_own_mode() {
+    let scene = common::load_scene("blend_modes");
+    let Some(mut state) = common::build_state(&scene) else {
+        return;
+    };
+
+    let mut input = Input::new();
+    state.step(input.take_read_only(), 0.0);
+    let actual = state.capture_frame().unwrap();
+
+    // The grass, then a ball each for alpha, additive and multiply, all from one atlas page.
+    assert_eq!(
+        state.graphics.as_ref().unwrap().render_stats(),
+        RenderStats {
+            draw_calls: 4,
+            binds: 1,
+            sprites: 17,
+        }
+    );
+
+    common::assert_matches_golden("blend_modes", &scene, &actual);
+
+    // Switching a material to a mode nothing used yet creates its pipeline on the next frame.
+    state.material_mut(2).unwrap().blend_mode = BlendMode::Premultiplied;
+    state.step(input.take_read_only(), 0.0);
+    state.capture_frame().unwrap();
+
+    assert_eq!(
+        state.graphics.as_ref().unwrap().render_stats().draw_calls,
+        4
+    );
+}
+
+#[test]
+fn post_effects_chain_and_toggle() {
+    let scene = common::load_scene("sprites");
+    let Some(mut state) = common::build_state(&scene) else {
+        return;
+    };
+
+    let effects = [
+        PostEffect::Bloom {
+            threshold: 0.4,
+            intensity: 1.5,
+            radius: 6.0,
+        },
+        PostEffect::ColorGrading {
+            lut: String::from("effects/warm_lut.png"),
+            intensity: 1.0,
+        },
+        PostEffect::Vignette {
+            intensity: 0.8,
+            radius: 0.4,
+        },
+        PostEffect::Crt {
+            curvature: 0.1,
+            scanlines: 0.25,
+        },
+    ];
+
+    let graphics = state.graphics.as_mut().unwrap();
+
+    for effect in effects {
+        graphics.add_post_effect(effect).unwrap();
+    }
+
+    assert!(graphics
+        .add_post_effect(PostEffect::Custom {
+            shader: String::from("missing.wgsl"),
+            params: [0.0; 4],
+        })
+        .is_err());
+
+    state.step(Input::new().take_read_only(), 0.0);
+    let actual = state.capture_frame().unwrap();
+
+    common::assert_matches_golden("post_processing", &scene, &actual);
+
+    // With every effect off the world goes straight to the target again.
+    let graphics = state.graphics.as_mut().unwrap();
+
+    for index in 0..4 {
+        graphics.set_post_effect_enabled(index, false).unwrap();
+    }
+
+    let actual = state.capture_frame().unwrap();
+    common::assert_matches_golden("sprites", &scene, &actual);
+
+    // Replacing an effect rebuilds its pass with the new effect's shader.
+    let vignette = PostEffect::Vignette {
+        intensity: 1.0,
+        radius: 0.0,
+    };
+
+    let graphics = state.graphics.as_mut().unwrap();
+    graphics.set_post_effect(3, vignette.clone()).unwrap();
+    graphics.set_post_effect_enabled(3, true).unwrap();
+    assert_eq!(graphics.post_effect(3), Some(&vignette));
+
+    let vignetted = state.capture_frame().unwrap();
+    let corner = |image: &image::RgbaImage| {
+        image.get_pixel(0, 0).0[..3]
+            .iter()
+            .map(|&c| c as u32)
+            .sum::<u32>()
+    };
+    assert!(corner(&vignetted) < corner(&actual));
+}
+
+// A ball in the foreground, still to be added.
+fn ball(
+    state: &fooheppy::state::State,
+    position: (f32, f32),
+    collider: Option<Collider>,
+    component: Box<dyn Component>,
+) -> Entity {
+    let mut entity = Entity::create(
+        position.into(),
+        cgmath::Quaternion::zero(),
+        1,
+        state.sorting_layer("foreground").unwrap(),
+        collider,
+    );
+    entity.add_component(component);
+    entity
+}
+
+#[test]
+fn lights_and_shadows_multiply_over_the_world() {
+    let scene = common::load_scene("lighting");
+    let Some(mut state) = common::build_state(&scene) else {
+        return;
+    };
+
+    // One ball casts a shadow with its collider, one with a polygon close to its outline.
+    let collider = Collider {
+        origin: cgmath::Vector2::zero(),
+        width: 0.75,
+        height: 0.75,
+    };
+
+    let octagon = (0..8)
+        .map(|i| {
+            let angle = i as f32 * std::f32::consts::FRAC_PI_4;
+            cgmath::Vector2::new(angle.cos(), angle.sin()) * 0.4
+        })
+        .collect();
+
+    let casters = [
+        ball(
+            &state,
+            (-2.0, -0.5),
+            Some(collider),
+            Box::new(ShadowCaster {
+                occluder: Occluder::Collider,
+            }),
+        ),
+        ball(
+            &state,
+            (-1.0, 0.5),
+            None,
+            Box::new(ShadowCaster {
+                occluder: Occluder::Polygon(octagon),
+            }),
+        ),
+    ];
+
+    for caster in casters {
+        state.add_entity(caster).unwrap();
+    }
+
+    let mut input = Input::new();
+    state.step(input.take_read_only(), 0.0);
+    let unlit = state.capture_frame().unwrap();
+
+    // Full ambient light with nothing else leaves the world as it was.
+    state.lighting_mut().set_ambient(Some(wgpu::Color::WHITE));
+    state.step(input.take_read_only(), 0.0);
+    assert!(state.capture_frame().unwrap() == unlit);
+
+    state.lighting_mut().set_ambient(Some(wgpu::Color {
+        r: 0.08,
+        g: 0.08,
+        b: 0.2,
+        a: 1.0,
+    }));
+
+    let mut torch = PointLight::new(
+        wgpu::Color {
+            r: 1.0,
+            g: 0.8,
+            b: 0.5,
+            a: 1.0,
+        },
+        2.5,
+    );
+    torch.intensity = 1.5;
+    torch.height = 0.5;
+
+    let mut lamp = SpotLight::new(
+        wgpu::Color {
+            r: 0.5,
+            g: 0.7,
+            b: 1.0,
+            a: 1.0,
+        },
+        3.5,
+        -std::f32::consts::FRAC_PI_2,
+        0.4,
+    );
+    lamp.cone.softness = 0.2;
+
+    // Lights are hidden balls.
+    let lights: [(_, Box<dyn Component>); 2] =
+        [((0.0, 0.5), Box::new(torch)), ((2.5, 1.5), Box::new(lamp))];
+
+    for (position, light) in lights {
+        let mut entity = ball(&state, position, None, light);
+        entity.appearance.opacity = 0.0;
+        state.add_entity(entity).unwrap();
+    }
+
+    state.step(input.take_read_only(), 0.0);
+    let actual = state.capture_frame().unwrap();
+
+    common::assert_matches_golden("lighting", &scene, &actual);
+}
+
+#[test]
+fn gizmos_draw_over_the_world_through_every_camera() {
+    let scene = common::load_scene("gizmos");
+    let Some(mut state) = common::build_state(&scene) else {
+        return;
+    };
+
+    let collider = Collider {
+        origin: cgmath::Vector2::new(0.0, 0.25),
+        width: 1.0,
+        height: 0.5,
+    };
+
+    let entity = Entity::create(
+        (0.0, -1.0).into(),
+        cgmath::Quaternion::zero(),
+        1,
+        state.sorting_layer("foreground").unwrap(),
+        Some(collider),
+    );
+    state.add_entity(entity).unwrap();
+
+    state.cameras[0].bounds = Some(CameraBounds {
+        min: (-1.5, -1.5).into(),
+        max: (1.5, 1.5).into(),
+    });
+
+    let mut input = Input::new();
+    state.step(input.take_read_only(), 0.0);
+
+    // Developer mode outlines the collider and the bounds and marks all three origins.
+    let graphics = state.graphics.as_mut().unwrap();
+    assert_eq!(graphics.gizmos().vertices().len(), 8 + 8 + 3 * 4);
+
+    let gizmos = graphics.gizmos_mut();
+    let red = wgpu::Color::RED;
+    gizmos.line((-1.5, -1.8).into(), (2.5, -1.8).into(), red);
+    gizmos.circle((1.0, 1.0).into(), 0.6, wgpu::Color::BLUE);
+    gizmos.arrow((1.0, -0.5).into(), (2.0, 0.5).into(), red);
+    gizmos.label((-1.4, 1.6).into(), "gizmo", wgpu::Color::WHITE);
+
+    // Gizmos outlive developer mode until the next step, without its UI overlay on top.
+    state.config_mut().set_developer_mode(false);
+    let actual = state.capture_frame().unwrap();
+    common::assert_matches_golden("gizmos", &scene, &actual);
+
+    // Everything's cleared by the next step.
+    state.step(input.take_read_only(), 0.0);
+
+    let gizmos = state.graphics.as_ref().unwrap().gizmos();
+    assert!(gizmos.vertices().is_empty() && gizmos.labels().is_empty());
+}
+
+#[test]
+fn gizmos_draw_after_post_effects() {
+    let scene = common::load_scene("gizmos");
+    let Some(mut state) = common::build_state(&scene) else {
+        return;
+    };
+
+    state.config_mut().set_developer_mode(false);
+
+    let graphics = state.graphics.as_mut().unwrap();
+    graphics
+        .add_post_effect(PostEffect::Vignette {
+            intensity: 1.0,
+            radius: 0.0,
+        })
+        .unwrap();
+
+    state.step(Input::new().take_read_only(), 0.0);
+
+    // A line along the bottom of the left camera, where the vignette is darkest.
+    let y = -state.cameras[0].half_extents().y * 0.9;
+    let graphics = state.graphics.as_mut().unwrap();
+    graphics
+        .gizmos_mut()
+        .line((-10.0, y).into(), (10.0, y).into(), wgpu::Color::RED);
+
+    let actual = state.capture_frame().unwrap();
+    let red = (0..actual.height()).find(|&y| actual.get_pixel(2, y).0 == [255, 0, 0, 255]);
+
+    assert!(red.is_some());
+}
+
+#[test]
+fn multiply_leaves_transparent_texels_alone() {
+    let scene = common::load_scene("multiply_edges");
+    let Some(mut state) = common::build_state(&scene) else {
+        return;
+    };
+
+    let mut input = Input::new();
+    state.step(input.take_read_only(), 0.0);
+    let actual = state.capture_frame().unwrap();
+
+    common::assert_matches_golden("multiply_edges", &scene, &actual);
+
+    state.despawn_entity(6).unwrap();
+    state.step(input.take_read_only(), 0.0);
+    let background = state.capture_frame().unwrap();
+
+    // Blending happens in linear space, on an sRGB target.
+    let to_linear = |c: f32| ((c / 255.0 + 0.055) / 1.055).powf(2.4);
+    let to_srgb = |c: f32| (1.055 * c.powf(1.0 / 2.4) - 0.055) * 255.0;
+
+    // The shadow's texels are white where they're transparent, and half transparent in a ring.
+    for (point, alpha) in [((41, 21), 0.0), ((60, 40), 1.0), ((73, 38), 128.0 / 255.0)] {
+        let under = background.get_pixel(point.0, point.1).0;
+        let pixel = actual.get_pixel(point.0, point.1).0;
+
+        for (i, color) in [60.0, 40.0, 110.0].into_iter().enumerate() {
+            let scale = to_linear(color) * alpha + 1.0 - alpha;
+            let expected = to_srgb(to_linear(under[i] as f32) * scale);
+
+            assert!(
+                (pixel[i] as f32 - expected).abs() <= 2.0,
+                "{point:?}: {pixel:?} over {under:?}"
+            );
+        }
+    }
+}
@@ -0,0 +1,176 @@
+use cgmath::prelude::*;
+
+use fooheppy::{
+    camera::Camera,
+    entity::Entity,
+    graphics::{
+        particles::Particles,
+        render_queue::{RenderQueue, SpriteBatch},
+        sorting_layer::{SortingLayer, SortingLayerSettings, SortingLayers},
+        sprite_sheet::UvRect,
+        tile_chunk::TileChunks,
+        vertex::SpriteInstance,
+    },
+};
+
+fn layers(y_sorted: &[bool]) -> SortingLayers {
+    SortingLayers::new(
+        y_sorted
+            .iter()
+            .enumerate()
+            .map(|(i, &y_sort)| SortingLayerSettings {
+                name: format!("layer {i}"),
+                y_sort,
+            })
+            .collect(),
+    )
+    .unwrap()
+}
+
+fn sprite(position: [f32; 2], material: usize, layer: SortingLayer) -> Option<Entity> {
+    Some(Entity::create(
+        position.into(),
+        cgmath::Quaternion::zero(),
+        material,
+        layer,
+        None,
+    ))
+}
+
+fn build(entities: &[Option<Entity>], layers: &SortingLayers) -> RenderQueue {
+    let instances: Vec<_> = entities
+        .iter()
+        .map(|entity| match entity {
+            Some(entity) => SpriteInstance::new(
+                entity.get_position(),
+                entity.get_rotation(),
+                UvRect::FULL,
+                &entity.appearance,
+            ),
+            None => bytemuck::Zeroable::zeroed(),
+        })
+        .collect();
+
+    let camera = Camera::new(1.8, (0.0, 0.0, 5.0).into(), 6.0, 0.1, 100.0);
+
+    let mut queue = RenderQueue::new();
+    queue.build(
+        &[camera],
+        entities,
+        &instances,
+        &TileChunks::default(),
+        &Particles::default(),
+        layers,
+    );
+    queue
+}
+
+fn drawn_positions(queue: &RenderQueue) -> Vec<[f32; 2]> {
+    queue
+        .instances()
+        .iter()
+        .map(|instance| [instance.transform[3][0], instance.transform[3][1]])
+        .collect()
+}
+
+#[test]
+fn queue_sorts_by_layer_then_order_then_material() {
+    let layers = layers(&[false, false]);
+    let background = layers.get("layer 0").unwrap();
+    let foreground = layers.get("layer 1").unwrap();
+
+    let mut entities = vec![
+        sprite([0.0, 0.0], 1, foreground),
+        sprite([1.0, 0.0], 0, background),
+        None,
+        sprite([3.0, 0.0], 1, background),
+        sprite([4.0, 0.0], 0, background),
+        sprite([5.0, 0.0], 1, foreground),
+        sprite([6.0, 0.0], 0, foreground),
+    ];
+
+    // Below the other foreground sprites despite being spawned last.
+    entities[6].as_mut().unwrap().set_order_in_layer(-1);
+
+    let queue = build(&entities, &layers);
+
+    let xs: Vec<f32> = drawn_positions(&queue).iter().map(|p| p[0]).collect();
+    assert_eq!(xs, vec![1.0, 4.0, 3.0, 6.0, 0.0, 5.0]);
+
+    assert_eq!(
+        queue.batches(0),
+        &[
+            SpriteBatch {
+                material: 0,
+                instances: 0..2,
+                chunk: None,
+            },
+            SpriteBatch {
+                material: 1,
+                instances: 2..3,
+                chunk: None,
+            },
+            SpriteBatch {
+                material: 0,
+                instances: 3..4,
+                chunk: None,
+            },
+            SpriteBatch {
+                material: 1,
+                instances: 4..6,
+                chunk: None,
+            },
+        ]
+    );
+    assert!(queue.batches(1).is_empty());
+}
+
+#[test]
+fn y_sorted_layers_draw_lower_sprites_on_top() {
+    let layers = layers(&[true, false]);
+    let characters = layers.get("layer 0").unwrap();
+    let effects = layers.get("layer 1").unwrap();
+
+    let mut entities = vec![
+        sprite([0.0, -2.0], 0, characters),
+        sprite([1.0, 3.0], 0, characters),
+        sprite([2.0, 5.0], 0, effects),
+        sprite([3.0, 0.0], 0, characters),
+        sprite([4.0, 1.0], 0, effects),
+        sprite([5.0, 9.0], 0, characters),
+    ];
+
+    // Order in layer still wins over height.
+    entities[5].as_mut().unwrap().set_order_in_layer(1);
+
+    let queue = build(&entities, &layers);
+
+    assert_eq!(
+        drawn_positions(&queue),
+        vec![
+            [1.0, 3.0],
+            [3.0, 0.0],
+            [0.0, -2.0],
+            [5.0, 9.0],
+            [2.0, 5.0],
+            [4.0, 1.0],
+        ]
+    );
+}
+
+#[test]
+fn sorting_layer_names_are_unique() {
+    let duplicate = vec![
+        SortingLayerSettings {
+            name: String::from("ground"),
+            y_sort: false,
+        },
+        SortingLayerSettings {
+            name: String::from("ground"),
+            y_sort: true,
+        },
+    ];
+
+    assert!(SortingLayers::new(duplicate).is_err());
+    assert!(SortingLayers::new(Vec::new()).is_err());
+}
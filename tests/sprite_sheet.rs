@@ -0,0 +1,81 @@
+use fooheppy::graphics::sprite_sheet::{GridLayout, PixelRect, SpriteSheet, UvRect};
+
+#[test]
+fn grid_slices_cells_between_margin_and_spacing() {
+    let layout = GridLayout {
+        cell_width: 16,
+        cell_height: 8,
+        margin: 2,
+        spacing: 4,
+    };
+
+    // 2 + 16 + 4 + 16 + 2 across, 2 + 8 + 4 + 8 + 4 + 8 + 2 down.
+    let mut sheet = SpriteSheet::grid(40, 36, layout).unwrap();
+    assert_eq!(sheet.len(), 6);
+
+    assert_eq!(
+        sheet.frame(3),
+        UvRect {
+            x: 22.0 / 40.0,
+            y: 14.0 / 36.0,
+            width: 16.0 / 40.0,
+            height: 8.0 / 36.0,
+        }
+    );
+
+    let region = PixelRect {
+        x: 0,
+        y: 0,
+        width: 40,
+        height: 36,
+    };
+    let index = sheet.add_named_frame("whole", region).unwrap();
+    assert_eq!(sheet.frame_index("whole"), Some(6));
+    assert_eq!(sheet.frame(index), UvRect::FULL);
+
+    assert!(sheet.name_frame("missing", 7).is_err());
+    assert!(sheet
+        .add_frame(PixelRect {
+            x: 30,
+            y: 0,
+            width: 16,
+            height: 8,
+        })
+        .is_err());
+}
+
+#[test]
+fn oversized_descriptor_values_are_errors() {
+    let mut sheet = SpriteSheet::new(64, 64);
+
+    assert!(sheet
+        .add_frame(PixelRect {
+            x: u32::MAX,
+            y: 0,
+            width: 16,
+            height: 16,
+        })
+        .is_err());
+    assert!(sheet
+        .add_frame(PixelRect {
+            x: 0,
+            y: 8,
+            width: 16,
+            height: u32::MAX,
+        })
+        .is_err());
+
+    let layout = |margin, spacing| GridLayout {
+        cell_width: 16,
+        cell_height: 16,
+        margin,
+        spacing,
+    };
+
+    assert!(SpriteSheet::grid(64, 64, layout(u32::MAX, 0)).is_err());
+    assert!(SpriteSheet::grid(64, 64, layout(u32::MAX, u32::MAX)).is_err());
+
+    // Spacing that wide leaves room for the first cell only.
+    let sheet = SpriteSheet::grid(64, 64, layout(0, u32::MAX)).unwrap();
+    assert_eq!(sheet.len(), 1);
+}
@@ -0,0 +1,122 @@
+use winit::{dpi::PhysicalSize, event::WindowEvent};
+
+use fooheppy::{
+    input::{Input, TextEvent},
+    state::State,
+    ui::text_field::TextField,
+};
+
+fn type_events(field: &mut TextField, events: &[TextEvent]) {
+    for event in events {
+        field.handle_event(event);
+    }
+}
+
+#[test]
+fn cursor_steps_over_whole_multi_byte_chars() {
+    let mut field = TextField::new();
+    field.set_value("añ日");
+
+    type_events(
+        &mut field,
+        &[
+            TextEvent::CursorLeft,
+            TextEvent::Backspace,
+            TextEvent::Insert("é".to_owned()),
+        ],
+    );
+
+    assert_eq!(field.value(), "aé日");
+    assert_eq!(field.display_text(true), "aé|日");
+
+    type_events(&mut field, &[TextEvent::Home, TextEvent::Delete]);
+    assert_eq!(field.display_text(true), "|é日");
+
+    type_events(&mut field, &[TextEvent::End, TextEvent::Backspace]);
+    assert_eq!(field.display_text(true), "é|");
+
+    // Nothing left of the start or right of the end.
+    type_events(
+        &mut field,
+        &[TextEvent::CursorRight, TextEvent::Delete, TextEvent::Home],
+    );
+    type_events(&mut field, &[TextEvent::CursorLeft, TextEvent::Backspace]);
+    assert_eq!(field.display_text(true), "|é");
+}
+
+#[test]
+fn pasted_text_stays_on_one_line() {
+    let mut field = TextField::new();
+    field.handle_event(&TextEvent::Insert("one\ntwo\tthree".to_owned()));
+
+    assert_eq!(field.value(), "onetwothree");
+}
+
+#[test]
+fn preedit_shows_at_the_cursor_until_committed() {
+    let mut field = TextField::new();
+    type_events(
+        &mut field,
+        &[
+            TextEvent::Insert("ab".to_owned()),
+            TextEvent::CursorLeft,
+            TextEvent::Preedit("か".to_owned(), Some((0, 3))),
+        ],
+    );
+
+    assert_eq!(field.value(), "ab");
+    assert_eq!(field.display_text(false), "aかb");
+    assert_eq!(field.display_text(true), "aか|b");
+
+    field.handle_event(&TextEvent::Insert("漢".to_owned()));
+    assert_eq!(field.value(), "a漢b");
+    assert_eq!(field.display_text(true), "a漢|b");
+
+    // An empty preedit or cancelling drops the composition.
+    field.handle_event(&TextEvent::Preedit("x".to_owned(), None));
+    field.handle_event(&TextEvent::Preedit(String::new(), None));
+    assert_eq!(field.display_text(false), "a漢b");
+
+    field.handle_event(&TextEvent::Preedit("x".to_owned(), None));
+    field.handle_event(&TextEvent::Cancel);
+    assert_eq!(field.display_text(false), "a漢b");
+}
+
+#[test]
+fn submit_is_taken_once() {
+    let mut field = TextField::new();
+    field.handle_event(&TextEvent::Submit);
+
+    assert!(field.take_submitted());
+    assert!(!field.take_submitted());
+}
+
+#[test]
+fn clicks_focus_the_field_under_the_cursor() {
+    let mut state = State::new_headless(PhysicalSize::new(320, 240)).unwrap();
+    state.load_ui("name_entry.nml", "name_entry.json").unwrap();
+
+    let mut input = Input::new();
+
+    // The field is the second line, under the label.
+    input.click((160.0, 66.0).into());
+    state.step(input.take_read_only(), 0.0);
+
+    assert!(state.is_text_input_active());
+
+    let focused = state.ui_canvas().focused_element().unwrap();
+    assert_eq!(focused.script_id.as_deref(), Some("name"));
+
+    input.set_text_mode(true);
+    input.handle_event(&WindowEvent::ReceivedCharacter('!'));
+    state.step(input.take_read_only(), 0.0);
+
+    let field = state.ui_canvas().focused_element().unwrap();
+    assert_eq!(field.text_field.as_ref().unwrap().value(), "Ada!");
+
+    // Clicking anything else blurs it.
+    input.click((160.0, 30.0).into());
+    state.step(input.take_read_only(), 0.0);
+
+    assert!(!state.is_text_input_active());
+}
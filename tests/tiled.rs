@@ -0,0 +1,191 @@
+use winit::dpi::PhysicalSize;
+
+use fooheppy::{
+    components::{player_movement::PlayerMovement, Component},
+    parsing::tiled::{LoadTiledMap, TiledComponents, TiledLayer, TiledMap, TiledObject},
+    physics::collision_grid::TileCollision,
+    resources::Resource,
+    state::State,
+    tilemap::Tile,
+};
+
+fn player(object: &TiledObject) -> anyhow::Result<Box<dyn Component>> {
+    let walk_speed = object
+        .properties
+        .get_float("walk_speed")
+        .ok_or_else(|| anyhow::anyhow!("Players need a walk_speed."))?;
+
+    Ok(Box::new(PlayerMovement {
+        walk_speed: walk_speed as f32,
+    }))
+}
+
+fn object_names(map: &TiledMap) -> Vec<(String, String, f32, f32)> {
+    map.layers
+        .iter()
+        .filter_map(|layer| match layer {
+            TiledLayer::Objects(layer) => Some(&layer.objects),
+            TiledLayer::Tiles(_) => None,
+        })
+        .flatten()
+        .map(|object| {
+            (
+                object.name.clone(),
+                object.class.clone(),
+                object.x,
+                object.y,
+            )
+        })
+        .collect()
+}
+
+#[test]
+fn tmx_and_json_maps_load_the_same() {
+    let tmx = Resource::load_tiled_map("example.tmx").unwrap();
+    let json = Resource::load_tiled_map("example.tmj").unwrap();
+
+    for map in [&tmx, &json] {
+        assert_eq!((map.width, map.height), (4, 3));
+        assert_eq!(map.tilesets.len(), 1);
+        assert_eq!(map.tilesets[0].image, "sheet.png");
+        assert_eq!(map.tilesets[0].tiles[&2].animation.len(), 2);
+        assert_eq!(
+            map.tilesets[0].tiles[&0].properties.get_str("collision"),
+            Some("solid")
+        );
+        assert_eq!(map.layers.len(), 3);
+    }
+
+    assert_eq!(object_names(&tmx), object_names(&json));
+
+    let tiles = |map: &TiledMap| match &map.layers[0] {
+        TiledLayer::Tiles(layer) => layer.tiles.clone(),
+        TiledLayer::Objects(_) => panic!("Expected a tile layer."),
+    };
+
+    assert_eq!(tiles(&tmx), tiles(&json));
+}
+
+#[test]
+fn spawning_builds_tilemaps_and_entities() {
+    let mut state = State::new_headless(PhysicalSize::new(800, 600)).unwrap();
+    let map = Resource::load_tiled_map("example.tmx").unwrap();
+
+    let mut components = TiledComponents::new();
+    components.register("player", player);
+
+    let spawned = map
+        .spawn(&mut state, (10.0, 20.0).into(), &components)
+        .unwrap();
+
+    assert!(spawned.materials.is_empty());
+    assert_eq!(spawned.tilemaps.len(), 1);
+    assert_eq!(spawned.entities.len(), 4);
+
+    let tilemap = &state.tilemaps()[spawned.tilemaps[0]];
+    let ground = tilemap.layer_index("ground").unwrap();
+    let details = tilemap.layer_index("details").unwrap();
+
+    assert_eq!(
+        tilemap.layer(ground).unwrap().sorting_layer,
+        state.sorting_layer("background").unwrap()
+    );
+
+    // Tiled's rows count down from the top, tilemap rows up from the bottom.
+    assert_eq!(tilemap.get_tile(ground, 1, 1), Some(Tile::new(2)));
+    assert_eq!(
+        tilemap.get_tile(ground, 3, 0),
+        Some(Tile {
+            index: 1,
+            flip_x: true,
+            flip_y: false,
+        })
+    );
+    assert_eq!(tilemap.get_tile(details, 3, 2), Some(Tile::new(0)));
+    assert_eq!(tilemap.get_tile(details, 3, 0), None);
+
+    // The details layer's red tile is solid.
+    assert_eq!(spawned.collision_grids.len(), 1);
+    let grid = &state.collision_grids()[spawned.collision_grids[0]];
+    assert_eq!(grid.get(3, 2), TileCollision::Solid);
+    assert_eq!(grid.solid_rects().len(), 1);
+
+    let entity = |i: usize| state.entity(spawned.entities[i]).unwrap();
+
+    let player = entity(0);
+    assert_eq!(player.get_position(), (11.5, 21.0).into());
+    assert_eq!(player.components.len(), 1);
+    assert_eq!(
+        player.get_sorting_layer(),
+        state.sorting_layer("foreground").unwrap()
+    );
+    let collider = player.collider.as_ref().unwrap();
+    assert_eq!((collider.width, collider.height), (1.0, 2.0));
+
+    let wall = entity(1);
+    assert_eq!(wall.get_position(), (12.0, 20.5).into());
+    assert_eq!(wall.get_render_layers(), 0);
+    assert!(wall.components.is_empty());
+
+    // Tile objects sit on their position and show their tile.
+    let crate_object = entity(2);
+    assert_eq!(crate_object.get_position(), (12.5, 20.5).into());
+    assert_eq!(crate_object.get_frame(), 3);
+    assert_ne!(crate_object.get_render_layers(), 0);
+
+    let spawn = entity(3);
+    assert_eq!(spawn.get_position(), (10.5, 22.5).into());
+    assert!(spawn.collider.is_none());
+}
+
+#[test]
+fn component_errors_name_the_object() {
+    let mut state = State::new_headless(PhysicalSize::new(800, 600)).unwrap();
+    let mut map = Resource::load_tiled_map("example.tmj").unwrap();
+
+    if let TiledLayer::Objects(layer) = &mut map.layers[2] {
+        layer.objects[0].properties = Default::default();
+    }
+
+    let mut components = TiledComponents::new();
+    components.register("player", player);
+
+    let error = map
+        .spawn(&mut state, (0.0, 0.0).into(), &components)
+        .unwrap_err();
+
+    let message = format!("{error:#}");
+    assert!(message.contains("object 1 of layer `objects`"), "{message}");
+    assert!(message.contains("Players need a walk_speed."), "{message}");
+}
+
+#[test]
+fn parse_errors_report_the_line() {
+    let source = r#"<?xml version="1.0" encoding="UTF-8"?>
+<map width="2" height="1" tilewidth="16" tileheight="16">
+ <layer name="ground">
+  <data encoding="csv">1,x</data>
+ </layer>
+</map>
+"#;
+
+    let error = TiledMap::from_tmx(source, "broken.tmx").unwrap_err();
+    let message = format!("{error:#}");
+
+    assert!(
+        message.contains("Invalid Tiled map broken.tmx"),
+        "{message}"
+    );
+    assert!(message.contains("line 4"), "{message}");
+    assert!(message.contains("`x` isn't a tile id."), "{message}");
+
+    let error =
+        TiledMap::from_json("{\n  \"width\": 2,\n  \"height\": }", "broken.tmj").unwrap_err();
+    let message = format!("{error:#}");
+
+    assert!(
+        message.contains("Invalid Tiled map broken.tmj"),
+        "{message}"
+    );
+    assert!(message.contains("line 3"), "{message}");
+}
@@ -0,0 +1,103 @@
+use fooheppy::{
+    animation::clip::AnimationFrame,
+    graphics::sorting_layer::{SortingLayerSettings, SortingLayers},
+    tilemap::{Tile, Tilemap, CHUNK_SIZE},
+};
+
+fn layers() -> SortingLayers {
+    SortingLayers::new(vec![SortingLayerSettings {
+        name: "background".into(),
+        y_sort: false,
+    }])
+    .unwrap()
+}
+
+fn clear_all_dirty(tilemap: &mut Tilemap) {
+    for layer in 0..tilemap.num_layers() {
+        for chunk in 0..tilemap.num_chunks() {
+            tilemap.clear_dirty(layer, chunk);
+        }
+    }
+}
+
+#[test]
+fn edits_only_dirty_their_own_chunk() {
+    let background = layers().get("background").unwrap();
+    let mut tilemap = Tilemap::new(40, 20, 0, (0.0, 0.0).into());
+    let ground = tilemap.add_layer("ground", background);
+    let details = tilemap.add_layer("details", background);
+
+    assert_eq!((tilemap.num_chunks_x(), tilemap.num_chunks_y()), (3, 2));
+    assert_eq!(tilemap.layer(details).unwrap().order_in_layer, 1);
+    assert_eq!(tilemap.layer_index("details"), Some(details));
+
+    clear_all_dirty(&mut tilemap);
+
+    let tile = Tile {
+        index: 2,
+        flip_x: true,
+        flip_y: false,
+    };
+
+    tilemap.set_tile(ground, 35, 18, Some(tile)).unwrap();
+    assert_eq!(tilemap.get_tile(ground, 35, 18), Some(tile));
+    assert_eq!(tilemap.get_tile(details, 35, 18), None);
+
+    let chunk = tilemap.chunk_at(35, 18);
+    assert_eq!(chunk, 5);
+
+    for other in 0..tilemap.num_chunks() {
+        assert_eq!(tilemap.is_chunk_dirty(ground, other), other == chunk);
+        assert!(!tilemap.is_chunk_dirty(details, other));
+    }
+
+    // The last chunk is cut short by the edge of the map.
+    let bounds = tilemap.chunk_bounds(chunk);
+    assert_eq!(
+        bounds.min,
+        (2.0 * CHUNK_SIZE as f32, CHUNK_SIZE as f32).into()
+    );
+    assert_eq!(bounds.max, (40.0, 20.0).into());
+
+    assert!(tilemap.set_tile(ground, 40, 0, Some(tile)).is_err());
+    assert!(tilemap.set_tile(5, 0, 0, Some(tile)).is_err());
+}
+
+#[test]
+fn animated_tiles_loop_and_dirty_the_chunks_showing_them() {
+    let background = layers().get("background").unwrap();
+    let mut tilemap = Tilemap::new(20, 4, 0, (0.0, 0.0).into());
+    let ground = tilemap.add_layer("ground", background);
+
+    tilemap.set_tile(ground, 18, 0, Some(Tile::new(2))).unwrap();
+    tilemap.animate_tile(
+        2,
+        vec![
+            AnimationFrame {
+                frame: 2,
+                duration: 0.5,
+            },
+            AnimationFrame {
+                frame: 3,
+                duration: 0.5,
+            },
+        ],
+    );
+
+    clear_all_dirty(&mut tilemap);
+
+    tilemap.update(0.25);
+    assert_eq!(tilemap.displayed_frame(2), 2);
+    assert!(!tilemap.is_chunk_dirty(ground, 1));
+
+    tilemap.update(0.5);
+    assert_eq!(tilemap.displayed_frame(2), 3);
+    assert!(tilemap.is_chunk_dirty(ground, 1));
+    assert!(!tilemap.is_chunk_dirty(ground, 0));
+
+    tilemap.update(0.5);
+    assert_eq!(tilemap.displayed_frame(2), 2);
+
+    // Indices without an animation show themselves.
+    assert_eq!(tilemap.displayed_frame(1), 1);
+}